@@ -1,85 +1,164 @@
 #import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::{screen, screen_texture, noise}
 
-const DITHER: bool = true;
-const DOWN_SCALE: f32 = 1.0;
-const PALETTE_SIZE: i32 = 4;
-const HIGHLIGHT_SIZE: i32 = 5;
-const HIGHLIGHT_THRESHOLD: f32 = 0.01;
+// Keep in sync with `Dither` in palette/mod.rs
+const DITHER_BLUE_NOISE: u32 = 0u;
+const DITHER_BAYER4: u32 = 1u;
+const DITHER_BAYER8: u32 = 2u;
 
+// Keep in sync with `TransitionStyle` in transition.rs
+const TRANSITION_DISSOLVE: u32 = 1u;
+const TRANSITION_IRIS: u32 = 2u;
+const TRANSITION_SMEAR: u32 = 3u;
+const TRANSITION_PALETTE_CYCLE: u32 = 4u;
+
+// Keep in sync with MAX_RAMP and MAX_HIGHLIGHTS in palette/asset.rs
 struct PaletteSqueeze {
     resolution: vec3f,
     time: f32,
     darken: f32,
+    darken_style: u32,
+    dither: u32,
+    ramp_size: u32,
+    highlight_count: u32,
+    down_scale: f32,
+    highlight_threshold: f32,
+    instability: f32,
+    flicker: f32,
+    ramp: array<vec4f, 16>,
+    highlights: array<vec4f, 8>,
 }
 
-@group(0) @binding(0) var screen_texture: texture_2d<f32>;
-@group(0) @binding(1) var texture_sampler: sampler;
 @group(0) @binding(2) var<uniform> u: PaletteSqueeze;
-@group(0) @binding(3) var noise_tex: texture_2d<f32>;
-@group(0) @binding(4) var noise_samp: sampler;
-
-const palette = array<vec3f, 4>(
-    vec3f(0.000, 0.000, 0.000),
-    vec3f(0.100, 0.100, 0.100),
-    vec3f(0.278, 0.278, 0.278),
-    vec3f(1.000, 1.000, 1.000),
-);
-
-const highlights = array<vec3f, 5>(
-    vec3f(1.000, 0.000, 0.929),
-    vec3f(1.000, 1.000, 0.000),
-    vec3f(0.196, 1.000, 0.000),
-    vec3f(0.000, 0.906, 1.000),
-    vec3f(1.000, 0.263, 0.235),
-);
-
-// Get a color from the palette with optional dithering between entries
+
+// Ordered dither threshold from a 2^bits square Bayer matrix, built by interleaving the bits of
+// x ^ y and y in reverse order
+fn bayer(pixel: vec2u, bits: u32) -> f32 {
+    var rank = 0u;
+    for (var bit = 0u; bit < bits; bit++) {
+        let x = (pixel.x >> bit) & 1u;
+        let y = (pixel.y >> bit) & 1u;
+        rank = (rank << 2u) | ((x ^ y) << 1u) | y;
+    }
+    return (f32(rank) + 0.5) / f32(1u << (2u * bits));
+}
+
+// Threshold in [0, 1) a pixel must pass to take the lighter of two palette entries
+fn dither_threshold(pixel: vec2f) -> f32 {
+    switch u.dither {
+        case DITHER_BLUE_NOISE: {
+            return noise(pixel);
+        }
+        case DITHER_BAYER4: {
+            return bayer(vec2u(pixel), 2u);
+        }
+        case DITHER_BAYER8: {
+            return bayer(vec2u(pixel), 3u);
+        }
+        default: {
+            return 0.5;
+        }
+    }
+}
+
+// Get a color from the palette, dithering between entries
 // - x: expected to be in [0, 1] range, representing position between palette entries
 // - pixel: used for dithering noise lookup
 fn get_dithered_palette(x: f32, pixel: vec2f) -> vec3f {
-    let idx = clamp(x, 0.0, 1.0) * f32(PALETTE_SIZE - 1);
+    let size = i32(u.ramp_size);
+    let idx = clamp(x, 0.0, 1.0) * f32(size - 1);
     let i = i32(idx);
-    let i_next = min(i + 1, PALETTE_SIZE - 1);
+    let i_next = min(i + 1, size - 1);
 
-    let c1 = palette[i];
-    let c2 = palette[i_next];
+    let c1 = u.ramp[i].rgb;
+    let c2 = u.ramp[i_next].rgb;
 
-    var mix_amt: f32;
-    if DITHER {
-        let noise_dims = vec2f(textureDimensions(noise_tex));
-        let dith = textureSample(noise_tex, noise_samp, pixel / noise_dims).r;
-        mix_amt = select(0.0, 1.0, fract(idx) > dith);
-    } else {
-        mix_amt = fract(idx);
-    }
-
-    return mix(c1, c2, mix_amt);
+    return select(c1, c2, fract(idx) > dither_threshold(pixel));
 }
 
 fn check_highlight(color: vec3f) -> vec4f {
-    for (var i = 0; i < HIGHLIGHT_SIZE; i++) {
-        let diff = length(color - highlights[i]);
-        if diff < HIGHLIGHT_THRESHOLD {
-            return vec4f(highlights[i], 1.0);
+    for (var i = 0u; i < u.highlight_count; i++) {
+        let highlight = u.highlights[i].rgb;
+        if length(color - highlight) < u.highlight_threshold {
+            return vec4f(highlight, 1.0);
         }
     }
     return vec4f(0.0);
 }
 
+// Pseudo-random value in [0, 1) for a whole number
+fn hash(n: f32) -> f32 {
+    return fract(sin(n * 12.9898) * 43758.5453);
+}
+
+// Brightness jolt for this moment: now and then, more often the higher `u.flicker`, the picture
+// jumps lighter or darker for a frame or two
+fn flicker_offset() -> f32 {
+    let beat = floor(u.time * 15.0);
+    if hash(beat) >= u.flicker * 0.2 {
+        return 0.0;
+    }
+    return (hash(beat + 0.5) - 0.5) * 0.6;
+}
+
+// Whether a highlight pixel loses its colour to the greyscale ramp. Bands of it creep
+// down the screen, dithered so their edges fray
+fn highlight_bleeds(pixel: vec2f) -> bool {
+    let band = 0.5 + 0.5 * sin(pixel.y * 0.05 - u.time * 2.0 + sin(pixel.x * 0.03 + u.time));
+    return dither_threshold(pixel) < u.instability * band * 1.5;
+}
+
+// Where to read the screen from: a smear drags each row sideways at its own speed
+fn transition_uv(uv: vec2f, pixel: vec2f) -> vec2f {
+    if u.darken_style != TRANSITION_SMEAR {
+        return uv;
+    }
+    let row_speed = 0.3 + 0.7 * hash(floor(pixel.y / 2.0));
+    return vec2f(uv.x - u.darken * u.darken * row_speed, uv.y);
+}
+
+// How far the transition has blacked out this pixel, 0.0 to 1.0
+fn transition_cover(in: FullscreenVertexOutput, pixel: vec2f) -> f32 {
+    switch u.darken_style {
+        case TRANSITION_DISSOLVE: {
+            return select(0.0, 1.0, noise(pixel) < u.darken);
+        }
+        case TRANSITION_IRIS: {
+            let size = vec2f(textureDimensions(screen_texture));
+            let radius = length(size) * 0.5 * (1.0 - u.darken);
+            return select(0.0, 1.0, length((in.uv - 0.5) * size) >= radius);
+        }
+        case TRANSITION_SMEAR, TRANSITION_PALETTE_CYCLE: {
+            return u.darken * u.darken;
+        }
+        default: {
+            return u.darken;
+        }
+    }
+}
+
+// Spin brightness around the ramp, faster the darker it gets, sinking to its first entry
+fn cycle_palette(x: f32) -> f32 {
+    if u.darken_style != TRANSITION_PALETTE_CYCLE || u.darken <= 0.0 {
+        return x;
+    }
+    return fract(x + u.darken * u.darken * 3.0) * (1.0 - u.darken);
+}
+
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
-    let fc = floor(in.position.xy / DOWN_SCALE) * DOWN_SCALE;
-    let pixel = fc / DOWN_SCALE;
+    let pixel = floor(in.position.xy / u.down_scale);
 
-    let screen_color = textureSample(screen_texture, texture_sampler, in.uv).rgb;
-    let incolor = mix(screen_color.rgb, vec3f(0.0), u.darken);
+    let screen_color = screen(transition_uv(in.uv, pixel)).rgb;
+    let incolor = mix(screen_color.rgb, vec3f(0.0), transition_cover(in, pixel));
 
-    // Check if input color is near any highlight — pass through directly
+    // Colours near a highlight pass through directly, unless low sanity bleeds them away
     let highlight = check_highlight(incolor);
-    if highlight.a > 0.5 {
+    if highlight.a > 0.5 && !highlight_bleeds(pixel) {
         return highlight;
     } else {
-        let color = get_dithered_palette(dot(incolor, vec3f(0.299, 0.587, 0.114)), pixel);
+        let luma = dot(incolor, vec3f(0.299, 0.587, 0.114)) + flicker_offset();
+        let color = get_dithered_palette(cycle_palette(luma), pixel);
         return vec4f(color, 1.0);
     }
 }
@@ -0,0 +1,16 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::screen
+
+struct ChromaticAberration {
+    intensity: f32,
+}
+
+@group(0) @binding(2) var<uniform> u: ChromaticAberration;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
+    // Twice the offset from the centre, so the corners shift by the full intensity
+    let offset = (in.uv - 0.5) * 2.0 * u.intensity;
+    let color = screen(in.uv);
+    return vec4f(screen(in.uv + offset).r, color.g, screen(in.uv - offset).b, color.a);
+}
@@ -0,0 +1,21 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::{screen, globals}
+
+struct Distortion {
+    amount: f32,
+    frequency: f32,
+    speed: f32,
+}
+
+@group(0) @binding(2) var<uniform> u: Distortion;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
+    let t = globals.time * u.speed;
+    // Two crossing waves at unrelated rates, so the wobble never settles into a loop
+    let wave = vec2f(
+        sin(in.uv.y * u.frequency + t) + 0.5 * sin(in.uv.y * u.frequency * 2.3 - t * 1.7),
+        sin(in.uv.x * u.frequency * 1.3 + t * 0.8) + 0.5 * sin(in.uv.x * u.frequency * 2.9 + t * 1.3),
+    ) / 1.5;
+    return screen(clamp(in.uv + wave * u.amount, vec2f(0.0), vec2f(1.0)));
+}
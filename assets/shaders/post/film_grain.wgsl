@@ -0,0 +1,19 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::{screen, noise, globals}
+
+struct FilmGrain {
+    intensity: f32,
+}
+
+@group(0) @binding(2) var<uniform> u: FilmGrain;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
+    let color = screen(in.uv);
+    // Jump the noise tile around every frame so the grain flickers instead of sitting still
+    let frame = f32(globals.frame_count % 4096u);
+    let jump = floor(fract(vec2f(frame * 0.754877, frame * 0.569840)) * 64.0);
+    let grain = (noise(in.position.xy + jump) - 0.5) * 2.0 * u.intensity;
+    let luma = dot(color.rgb, vec3f(0.299, 0.587, 0.114));
+    return vec4f(color.rgb + grain * (1.0 - luma * 0.5), color.a);
+}
@@ -0,0 +1,20 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::{screen, globals}
+
+struct Scanlines {
+    intensity: f32,
+    spacing: f32,
+    speed: f32,
+}
+
+@group(0) @binding(2) var<uniform> u: Scanlines;
+
+const TAU: f32 = 6.28318530718;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
+    let color = screen(in.uv);
+    let phase = (in.position.y - globals.time * u.speed) / max(u.spacing, 1.0);
+    let line = 0.5 + 0.5 * cos(phase * TAU);
+    return vec4f(color.rgb * (1.0 - u.intensity * line), color.a);
+}
@@ -0,0 +1,19 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::screen
+
+struct ScreenWarp {
+    curvature: f32,
+}
+
+@group(0) @binding(2) var<uniform> u: ScreenWarp;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
+    let centered = in.uv * 2.0 - 1.0;
+    let warped = centered * (1.0 + u.curvature * dot(centered, centered)) / (1.0 + u.curvature);
+    let uv = warped * 0.5 + 0.5;
+    if any(uv < vec2f(0.0)) || any(uv > vec2f(1.0)) {
+        return vec4f(0.0, 0.0, 0.0, 1.0);
+    }
+    return screen(uv);
+}
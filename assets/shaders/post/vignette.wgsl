@@ -0,0 +1,19 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+#import game::post_process::screen
+
+struct Vignette {
+    intensity: f32,
+    radius: f32,
+    softness: f32,
+}
+
+@group(0) @binding(2) var<uniform> u: Vignette;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4f {
+    let color = screen(in.uv);
+    // 0 at the centre, 1 at the corners
+    let distance = length(in.uv - 0.5) * sqrt(2.0);
+    let shade = smoothstep(u.radius, u.radius + u.softness, distance) * u.intensity;
+    return vec4f(color.rgb * (1.0 - shade), color.a);
+}
@@ -0,0 +1,21 @@
+#define_import_path game::post_process
+
+#import bevy_render::globals::Globals
+
+// Keep in sync with the layout in post_process/mod.rs. Binding 2 is each effect's own uniform.
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(3) var noise_texture: texture_2d<f32>;
+@group(0) @binding(4) var noise_sampler: sampler;
+@group(0) @binding(5) var<uniform> globals: Globals;
+
+// The image so far, at `uv`
+fn screen(uv: vec2f) -> vec4f {
+    return textureSampleLevel(screen_texture, screen_sampler, uv, 0.0);
+}
+
+// Blue noise threshold in [0, 1) for a pixel, tiling across the screen
+fn noise(pixel: vec2f) -> f32 {
+    let dims = vec2f(textureDimensions(noise_texture));
+    return textureSampleLevel(noise_texture, noise_sampler, (pixel + 0.5) / dims, 0.0).r;
+}
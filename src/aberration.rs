@@ -1,18 +1,19 @@
 use crate::GameState;
 use crate::actor::{Actor, ActorIntent, GROUND_Y};
-use crate::dialog::Npc;
+use crate::dialog::{Npc, NpcIdentity, NpcIdentityRon};
 use crate::pause::game_not_paused;
 use crate::player::Player;
 use bevy::asset::RenderAssetUsages;
 use bevy::mesh::{Indices, MeshVertexBufferLayoutRef, PrimitiveTopology};
 use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
 use bevy::prelude::*;
+use bevy::render::RenderApp;
 use bevy::render::render_resource::{
     AsBindGroup, RenderPipelineDescriptor, SpecializedMeshPipelineError,
 };
 use bevy::shader::ShaderRef;
 use rand::Rng;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 
 const MAX_ABERRATIONS: usize = 5;
 const SPAWN_MIN_SECS: f32 = 5.0;
@@ -61,12 +62,20 @@ pub struct AberrationPlugin;
 
 impl Plugin for AberrationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(MaterialPlugin::<AberrationMaterial>::default())
+        // Headless apps (the test harness) have no renderer to hand the material to.
+        if app.get_sub_app(RenderApp).is_some() {
+            app.add_plugins(MaterialPlugin::<AberrationMaterial>::default());
+        } else {
+            app.init_asset::<AberrationMaterial>();
+        }
+
+        app.add_message::<SpawnAberration>()
             .add_systems(OnEnter(GameState::Playing), init_aberrations)
             .add_systems(
                 Update,
                 (
                     spawn_aberration_periodic,
+                    spawn_requested_aberrations,
                     aberration_face_player,
                     aberration_distance_scale,
                     animate_spawn,
@@ -91,14 +100,34 @@ struct AberrationTypeRon {
     layers: Vec<LayerRon>,
     #[serde(default = "default_size")]
     size: f32,
+    /// Aberrations of this type can be talked to.
+    #[serde(default, deserialize_with = "npc_or_flag")]
+    npc: Option<NpcIdentityRon>,
+    /// Sigil (see `sigils.ron`) that must be drawn to dispel this type.
     #[serde(default)]
-    npc: bool,
+    sigil: Option<String>,
 }
 
 fn default_size() -> f32 {
     2.0
 }
 
+/// An NPC identity, or `npc: true` as types were written before NPCs had identities.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NpcRon {
+    Flag(bool),
+    Identity(Option<NpcIdentityRon>),
+}
+
+/// `npc: true` is an unnamed NPC that talks through any dialog tree.
+fn npc_or_flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NpcIdentityRon>, D::Error> {
+    Ok(match NpcRon::deserialize(deserializer)? {
+        NpcRon::Flag(npc) => npc.then(NpcIdentityRon::default),
+        NpcRon::Identity(identity) => identity,
+    })
+}
+
 #[derive(Deserialize)]
 struct LayerRon {
     texture: String,
@@ -113,7 +142,8 @@ struct AberrationTypes(Vec<AberrationTypeDef>);
 struct AberrationTypeDef {
     layers: Vec<LayerDef>,
     size: f32,
-    npc: bool,
+    npc: Option<NpcIdentity>,
+    sigil: Option<String>,
 }
 
 struct LayerDef {
@@ -121,10 +151,22 @@ struct LayerDef {
     columns: u32,
 }
 
+/// Request to spawn an aberration near the player outside the regular spawn timer.
+#[derive(Message)]
+pub struct SpawnAberration;
+
 /// Marker for aberration enemies.
 #[derive(Component)]
 pub struct Aberration;
 
+/// Side length of an aberration's sprite quad in world units, before its transform scale.
+#[derive(Component)]
+pub struct AberrationSize(pub f32);
+
+/// Name of the sigil that must be drawn to dispel this aberration. Without it any closed loop works.
+#[derive(Component)]
+pub struct RequiredSigil(pub String);
+
 /// Tracks the spawn-in scale animation.
 #[derive(Component)]
 pub struct SpawnAnimation {
@@ -194,7 +236,8 @@ fn init_aberrations(mut commands: Commands, asset_server: Res<AssetServer>) {
         .into_iter()
         .map(|t| AberrationTypeDef {
             size: t.size,
-            npc: t.npc,
+            npc: t.npc.map(|identity| identity.load(&asset_server)),
+            sigil: t.sigil,
             layers: t
                 .layers
                 .into_iter()
@@ -240,6 +283,27 @@ fn spawn_aberration_periodic(
         return;
     }
 
+    spawn_near_player(
+        &mut commands,
+        &types,
+        &mut meshes,
+        &mut materials,
+        player_tf,
+        player_actor,
+    );
+
+    spawn_timer.timer = Timer::from_seconds(random_spawn_delay(), TimerMode::Once);
+}
+
+/// Spawn a random aberration type in front of the player.
+fn spawn_near_player(
+    commands: &mut Commands,
+    types: &AberrationTypes,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<AberrationMaterial>,
+    player_tf: &Transform,
+    player_actor: &Actor,
+) {
     let mut rng = rand::rng();
     let angle_offset = rng.random_range(-SPAWN_HALF_ANGLE..SPAWN_HALF_ANGLE);
     let spawn_yaw = player_actor.yaw + angle_offset;
@@ -258,6 +322,7 @@ fn spawn_aberration_periodic(
         Transform::from_translation(spawn_pos).with_scale(Vec3::new(0.0, 1.0, 1.0)),
         Visibility::default(),
         Aberration,
+        AberrationSize(type_def.size),
         SpawnAnimation {
             timer: Timer::from_seconds(SPAWN_ANIM_SECS, TimerMode::Once),
         },
@@ -271,25 +336,54 @@ fn spawn_aberration_periodic(
         ActorIntent::default(),
     ));
 
-    if type_def.npc {
-        entity_cmd.insert(Npc { range: KILL_PROXIMITY });
+    if let Some(identity) = &type_def.npc {
+        entity_cmd.insert((Npc { range: KILL_PROXIMITY }, identity.clone()));
+    }
+    if let Some(sigil) = &type_def.sigil {
+        entity_cmd.insert(RequiredSigil(sigil.clone()));
     }
 
     entity_cmd.with_children(|parent| {
-            for (i, layer) in type_def.layers.iter().enumerate() {
-                let frame = rng.random_range(0..layer.columns);
-                let quad = meshes.add(sprite_frame_quad(type_def.size, type_def.size, layer.columns, frame));
-                parent.spawn((
-                    Mesh3d(quad),
-                    MeshMaterial3d(materials.add(AberrationMaterial {
-                        base_texture: Some(layer.texture.clone()),
-                    })),
-                    Transform::from_xyz(0.0, 0.0, i as f32 * 0.01),
-                ));
-            }
-        });
+        for (i, layer) in type_def.layers.iter().enumerate() {
+            let frame = rng.random_range(0..layer.columns);
+            let quad = meshes.add(sprite_frame_quad(type_def.size, type_def.size, layer.columns, frame));
+            parent.spawn((
+                Mesh3d(quad),
+                MeshMaterial3d(materials.add(AberrationMaterial {
+                    base_texture: Some(layer.texture.clone()),
+                })),
+                Transform::from_xyz(0.0, 0.0, i as f32 * 0.01),
+            ));
+        }
+    });
+}
 
-    spawn_timer.timer = Timer::from_seconds(random_spawn_delay(), TimerMode::Once);
+/// Spawn an aberration right away, e.g. from a dialog effect.
+fn spawn_requested_aberrations(
+    mut commands: Commands,
+    mut requests: MessageReader<SpawnAberration>,
+    types: Res<AberrationTypes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<AberrationMaterial>>,
+    player_query: Query<(&Transform, &Actor), With<Player>>,
+) {
+    let Ok((player_tf, player_actor)) = player_query.single() else {
+        requests.clear();
+        return;
+    };
+    for _ in requests.read() {
+        if types.0.is_empty() {
+            continue;
+        }
+        spawn_near_player(
+            &mut commands,
+            &types,
+            &mut meshes,
+            &mut materials,
+            player_tf,
+            player_actor,
+        );
+    }
 }
 
 fn kill_countdown_proximity(
@@ -409,3 +503,37 @@ fn cleanup_aberrations(mut commands: Commands, query: Query<Entity, With<Aberrat
     commands.remove_resource::<AberrationSpawnTimer>();
     commands.remove_resource::<AberrationTypes>();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialog::DIALOG_RON;
+    use crate::dialog::graph::DialogGraph;
+
+    #[test]
+    fn npc_types_reference_existing_dialog_trees() {
+        let data: AberrationTypesRon = ron::from_str(ABERRATION_TYPES_RON).unwrap();
+        let graph = DialogGraph::from_ron(DIALOG_RON).unwrap();
+        let identities: Vec<_> = data.types.iter().filter_map(|t| t.npc.as_ref()).collect();
+        assert!(!identities.is_empty());
+        for tree in identities.iter().flat_map(|i| i.dialog.iter().chain(&i.returning)) {
+            assert!(graph.tree_start(tree).is_some(), "unknown dialog tree `{tree}`");
+        }
+    }
+
+    #[test]
+    fn npc_flag_from_older_types_still_parses() {
+        let parse = |npc: &str| {
+            let source = format!("(types: [(layers: [], npc: {npc})])");
+            let data: AberrationTypesRon = ron::from_str(&source).unwrap();
+            data.types.into_iter().next().unwrap().npc
+        };
+        let unnamed = parse("true").expect("`npc: true` should be an NPC");
+        assert!(unnamed.name.is_none() && unnamed.dialog.is_empty());
+        assert!(parse("false").is_none());
+        assert!(parse("None").is_none());
+        let named = parse(r#"Some((name: Some("Green"), dialog: ["how_long"]))"#).unwrap();
+        assert_eq!(named.name.as_deref(), Some("Green"));
+        assert_eq!(named.dialog, vec!["how_long".to_string()]);
+    }
+}
@@ -0,0 +1,319 @@
+//! Walk the dialog trees in a terminal, without starting the game.
+//!
+//! `cargo run --bin dialog_preview [path/to/dialog.ron]`
+//!
+//! Reports nodes no tree reaches and lines the conversation stops on without being marked as
+//! an `end` or a `win`, then lets you pick a tree and talk through it. Every response and
+//! continuation shows its condition, and a line's effects are listed and applied to a
+//! simulated run as it is shown, so later conditions see them. Besides choosing by number,
+//! any prompt takes:
+//!
+//! - `t`: let a timed line run out
+//! - `q`: leave the conversation, or quit from the tree list
+//! - `sanity 0.3`, `env Dissociation`, `flag name`, `var name 2`, `dispelled 3`: change the run
+//! - `state`: show the run
+//! - `reset`: forget everything, as if starting a new run with a stranger
+
+use bevy_game::dialog::condition::{Condition, DialogContext, DialogVars};
+use bevy_game::dialog::effect::Effect;
+use bevy_game::dialog::graph::{ContinuationDef, DialogGraph, NodeDef, NodeId};
+use bevy_game::dialog::history::NpcMemory;
+use bevy_game::dialog::markup::Markup;
+use bevy_game::environment::Environment;
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+const DEFAULT_PATH: &str = "assets/defs/dialog.ron";
+
+/// The game state conditions are checked against, changed by effects and commands.
+struct Run {
+    sanity: f32,
+    environment: Option<Environment>,
+    vars: DialogVars,
+    /// What the one NPC being previewed remembers.
+    memory: NpcMemory,
+}
+
+impl Default for Run {
+    fn default() -> Self {
+        Self {
+            sanity: 1.0,
+            environment: Some(Environment::default()),
+            vars: DialogVars::default(),
+            memory: NpcMemory::default(),
+        }
+    }
+}
+
+impl Run {
+    fn context(&self) -> DialogContext<'_> {
+        DialogContext {
+            sanity: self.sanity,
+            environment: self.environment.clone(),
+            vars: &self.vars,
+            npc: Some(&self.memory),
+        }
+    }
+
+    fn apply(&mut self, effect: &Effect) {
+        match effect {
+            Effect::SetFlag(name) => self.vars.set_flag(name, true),
+            Effect::ClearFlag(name) => self.vars.set_flag(name, false),
+            Effect::AddVar(name, delta) => self.vars.add_var(name, *delta),
+            Effect::Heal(amount) => self.sanity = (self.sanity + amount).min(1.0),
+            Effect::Damage(amount) => self.sanity = (self.sanity - amount).max(0.0),
+            Effect::SwitchEnvironment(environment) => self.environment = Some(environment.clone()),
+            // Only matter in the game
+            Effect::SpawnAberration | Effect::PlaySound(_) => {}
+        }
+    }
+
+    /// Handle a command that changes the run. Returns false if `line` isn't one.
+    fn command(&mut self, line: &str) -> bool {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["sanity", value] => match value.parse::<f32>() {
+                Ok(value) => self.sanity = value.clamp(0.0, 1.0),
+                Err(_) => println!("sanity takes a fraction, like 0.3"),
+            },
+            ["env", name] => match ron::from_str::<Environment>(name) {
+                Ok(environment) => self.environment = Some(environment),
+                Err(_) => println!("unknown environment `{name}`"),
+            },
+            ["flag", name] => {
+                let set = !self.vars.flag(name);
+                self.vars.set_flag(name, set);
+                println!("{name} is {}", if set { "set" } else { "cleared" });
+            }
+            ["var", name, delta] => match delta.parse::<i32>() {
+                Ok(delta) => self.vars.add_var(name, delta),
+                Err(_) => println!("var takes a name and a whole number"),
+            },
+            ["dispelled", count] => match count.parse::<u32>() {
+                Ok(count) => self.vars.dispelled = count,
+                Err(_) => println!("dispelled takes a count"),
+            },
+            ["state"] => self.print(),
+            ["reset"] => *self = Run::default(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn print(&self) {
+        let mut flags: Vec<&str> = self.vars.flags().collect();
+        flags.sort();
+        let mut vars: Vec<(&str, i32)> = self.vars.vars().collect();
+        vars.sort();
+        println!("  sanity      {:.2}", self.sanity);
+        println!("  environment {:?}", self.environment);
+        println!("  dispelled   {}", self.vars.dispelled);
+        println!("  flags       {flags:?}");
+        println!("  vars        {vars:?}");
+        println!("  spoken to   {} times", self.memory.times_spoken);
+        println!("  last line   {:?}", self.memory.last_node);
+    }
+}
+
+fn main() -> ExitCode {
+    let path = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_PATH.to_string());
+    let graph = match std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|source| DialogGraph::from_ron(&source).map_err(|e| e.to_string()))
+    {
+        Ok(graph) => graph,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    report(&graph);
+    let mut run = Run::default();
+    loop {
+        println!();
+        for (i, tree) in graph.trees().iter().enumerate() {
+            println!("{:>3}) {}", i + 1, tree.id);
+        }
+        let Some(line) = prompt("tree") else {
+            return ExitCode::SUCCESS;
+        };
+        if line == "q" {
+            return ExitCode::SUCCESS;
+        }
+        if run.command(&line) {
+            continue;
+        }
+        let tree = match line.parse::<usize>() {
+            Ok(n) => graph.trees().get(n.wrapping_sub(1)),
+            Err(_) => graph.trees().iter().find(|t| t.id == line),
+        };
+        match tree {
+            Some(tree) => talk(&graph, &mut run, tree.start),
+            None => println!("no tree `{line}`"),
+        }
+    }
+}
+
+fn report(graph: &DialogGraph) {
+    let unreachable = graph.unreachable_nodes();
+    let dead_ends = graph.dead_ends();
+    println!(
+        "{} trees, {} lines, {} unreachable, {} dead ends",
+        graph.trees().len(),
+        graph.nodes().len(),
+        unreachable.len(),
+        dead_ends.len()
+    );
+    for id in unreachable {
+        println!("  unreachable: {}", graph.node(id).id);
+    }
+    for id in dead_ends {
+        println!("  dead end:    {} (no way on, not marked `end`)", graph.node(id).id);
+    }
+}
+
+/// Play a conversation from `start` until it ends or the author leaves it.
+fn talk(graph: &DialogGraph, run: &mut Run, start: NodeId) {
+    let mut current = start;
+    // A winning response only counts once the conversation ends
+    let mut won = false;
+    loop {
+        let node = graph.node(current);
+        run.memory.visited.insert(node.id.clone());
+        run.memory.last_node = Some(node.id.clone());
+        println!();
+        println!("[{}]{}", node.id, if node.win { " (win)" } else { "" });
+        println!("  \"{}\"", Markup::parse_or_plain(&node.text).to_plain_string());
+        for effect in &node.effects {
+            println!("  effect: {}", ron_text(effect));
+            run.apply(effect);
+        }
+
+        let visible = node.visible_responses(&run.context());
+        print_responses(graph, run, node, &visible);
+        if visible.is_empty() {
+            end(run, won || node.win);
+            return;
+        }
+
+        // Returns the next line, or `None` once the conversation ends
+        let next = loop {
+            let Some(line) = prompt("response") else {
+                return;
+            };
+            if line == "q" {
+                return;
+            }
+            if run.command(&line) {
+                continue;
+            }
+            if line == "t" && node.timer.is_some() {
+                let ctx = run.context();
+                break match node.timeout_response(&ctx) {
+                    Some(index) => choose_response(node, index, run, &mut won),
+                    None => {
+                        println!("  (silence)");
+                        node.choose_silence(&ctx, &mut rand::rng())
+                    }
+                };
+            }
+            match line.parse::<usize>().ok().and_then(|n| visible.get(n.wrapping_sub(1))) {
+                Some(&index) => break choose_response(node, index, run, &mut won),
+                None => println!("pick 1-{}", visible.len()),
+            }
+        };
+        match next {
+            Some(next) => current = next,
+            None => {
+                end(run, won);
+                return;
+            }
+        }
+    }
+}
+
+fn choose_response(node: &NodeDef, index: usize, run: &Run, won: &mut bool) -> Option<NodeId> {
+    let response = &node.responses[index];
+    println!("  > {}", Markup::parse_or_plain(&response.text).to_plain_string());
+    *won |= response.win;
+    response.choose(&run.context(), &mut rand::rng())
+}
+
+fn end(run: &mut Run, won: bool) {
+    run.memory.times_spoken += 1;
+    println!("  -- conversation ends{} --", if won { ", won over" } else { "" });
+}
+
+fn print_responses(graph: &DialogGraph, run: &Run, node: &NodeDef, visible: &[usize]) {
+    let ctx = run.context();
+    for (index, response) in node.responses.iter().enumerate() {
+        let number = match visible.iter().position(|&i| i == index) {
+            Some(position) => format!("{:>3})", position + 1),
+            None => "  -)".to_string(),
+        };
+        let mut notes = Vec::new();
+        if let Some(condition) = &response.condition {
+            notes.push(condition_note(condition, &ctx));
+        }
+        if response.win {
+            notes.push("win".to_string());
+        }
+        if response.timeout {
+            notes.push("on timeout".to_string());
+        }
+        println!(
+            "{number} {}{}",
+            Markup::parse_or_plain(&response.text).to_plain_string(),
+            if notes.is_empty() { String::new() } else { format!("  [{}]", notes.join(", ")) }
+        );
+        print_continuations(graph, &response.next, &ctx);
+    }
+    if let Some(secs) = node.timer {
+        println!("  timer {secs}s, `t` to let it run out");
+        if node.timeout_response(&ctx).is_none() {
+            println!("    on silence:");
+            print_continuations(graph, &node.silence, &ctx);
+        }
+    }
+}
+
+fn print_continuations(graph: &DialogGraph, next: &[ContinuationDef], ctx: &DialogContext) {
+    if next.is_empty() {
+        println!("       -> (ends)");
+    }
+    for continuation in next {
+        let mut notes = Vec::new();
+        if continuation.weight != 1.0 {
+            notes.push(format!("weight {}", continuation.weight));
+        }
+        if let Some(condition) = &continuation.condition {
+            notes.push(condition_note(condition, ctx));
+        }
+        println!(
+            "       -> {}{}",
+            graph.node(continuation.target).id,
+            if notes.is_empty() { String::new() } else { format!("  [{}]", notes.join(", ")) }
+        );
+    }
+}
+
+fn condition_note(condition: &Condition, ctx: &DialogContext) -> String {
+    let holds = if condition.holds(ctx) { "holds" } else { "doesn't hold" };
+    format!("when {}: {holds}", ron_text(condition))
+}
+
+fn ron_text(value: &impl serde::Serialize) -> String {
+    ron::to_string(value).unwrap_or_else(|e| format!("<{e}>"))
+}
+
+/// Read a trimmed line, or `None` at the end of input.
+fn prompt(label: &str) -> Option<String> {
+    print!("{label}> ");
+    io::stdout().flush().ok()?;
+    let mut line = String::new();
+    match io::stdin().lock().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
@@ -0,0 +1,137 @@
+//! Recorded GIF frames. Captured frames come out of the palette squeeze, so they hold a couple
+//! of dozen colours at most and are kept as indices into their own colour table, a quarter of
+//! the size of RGBA. Each frame carries its own table, since the palette shifts between
+//! environments; the encoder gives each its own GIF colour table without further quantisation.
+
+use bevy::prelude::*;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageResult, RgbaImage};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Most colours a GIF colour table holds.
+const MAX_COLORS: usize = 256;
+
+/// A frame as indices into its own colour table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedFrame {
+    pub size: UVec2,
+    pub palette: Vec<[u8; 3]>,
+    pub indices: Vec<u8>,
+}
+
+impl IndexedFrame {
+    /// Index an RGBA8 frame, row by row. Alpha is ignored. Past 256 colours, later ones take
+    /// the closest of the first 256.
+    pub fn from_rgba(size: UVec2, rgba: &[u8]) -> Self {
+        let mut palette: Vec<[u8; 3]> = Vec::new();
+        let mut lookup = HashMap::new();
+        let indices = rgba
+            .chunks_exact(4)
+            .map(|pixel| {
+                let color = [pixel[0], pixel[1], pixel[2]];
+                *lookup.entry(color).or_insert_with(|| {
+                    if palette.len() < MAX_COLORS {
+                        palette.push(color);
+                        (palette.len() - 1) as u8
+                    } else {
+                        closest(&palette, color)
+                    }
+                })
+            })
+            .collect();
+        Self {
+            size,
+            palette,
+            indices,
+        }
+    }
+
+    /// Back to opaque RGBA8.
+    pub fn to_rgba(&self) -> RgbaImage {
+        let pixels = self
+            .indices
+            .iter()
+            .flat_map(|&index| {
+                let [r, g, b] = self
+                    .palette
+                    .get(index as usize)
+                    .copied()
+                    .unwrap_or_default();
+                [r, g, b, 255]
+            })
+            .collect();
+        RgbaImage::from_raw(self.size.x, self.size.y, pixels).expect("indices cover the frame")
+    }
+}
+
+fn closest(palette: &[[u8; 3]], color: [u8; 3]) -> u8 {
+    let distance = |entry: &[u8; 3]| {
+        entry
+            .iter()
+            .zip(color)
+            .map(|(&a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    (0..palette.len())
+        .min_by_key(|&i| distance(&palette[i]))
+        .unwrap_or(0) as u8
+}
+
+/// Write `frames` as a looping GIF, each shown for `delay_ms` milliseconds.
+pub fn write_gif(out: impl Write, frames: &[IndexedFrame], delay_ms: u32) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new(out);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_numer_denom_ms(delay_ms, 1);
+    encoder.encode_frames(
+        frames
+            .iter()
+            .map(|frame| Frame::from_parts(frame.to_rgba(), 0, 0, delay)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::AnimationDecoder;
+    use image::codecs::gif::GifDecoder;
+    use std::io::Cursor;
+
+    #[test]
+    fn frames_index_their_own_colours() {
+        let rgba = [10, 20, 30, 255, 200, 0, 0, 255, 10, 20, 30, 0];
+        let frame = IndexedFrame::from_rgba(UVec2::new(3, 1), &rgba);
+        assert_eq!(frame.palette, vec![[10, 20, 30], [200, 0, 0]]);
+        assert_eq!(frame.indices, vec![0, 1, 0]);
+    }
+
+    #[test]
+    fn gif_frames_decode_to_the_captured_colours() {
+        let size = UVec2::new(40, 30);
+        let frames: Vec<_> = (0..3u8)
+            .map(|n| {
+                let rgba: Vec<u8> = (0..size.x * size.y)
+                    .flat_map(|i| {
+                        let shade = ((i as u8) % 5) * 40 + n;
+                        [shade, 255 - shade, n * 60, 255]
+                    })
+                    .collect();
+                IndexedFrame::from_rgba(size, &rgba)
+            })
+            .collect();
+
+        let mut gif = Vec::new();
+        write_gif(&mut gif, &frames, 100).unwrap();
+
+        let decoded = GifDecoder::new(Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(decoded.len(), frames.len());
+        for (decoded, frame) in decoded.iter().zip(&frames) {
+            assert_eq!(decoded.delay().numer_denom_ms(), (100, 1));
+            assert_eq!(*decoded.buffer(), frame.to_rgba());
+        }
+    }
+}
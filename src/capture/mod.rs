@@ -0,0 +1,289 @@
+//! Captures of the canvas as the player sees it, after post-processing and the palette
+//! squeeze. F12 saves a PNG at the canvas's own size and another scaled up as on screen; F10
+//! writes the last few seconds, kept in a rolling buffer, as a GIF. The buffer only records
+//! during play, paused or not photo mode aside. Files go to `captures_dir`.
+
+mod gif;
+
+use crate::GameState;
+use crate::pause::game_not_paused;
+use crate::photo_mode::PhotoMode;
+use crate::scaling::{CanvasImage, CanvasLayout};
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured};
+use bevy::tasks::futures::check_ready;
+use bevy::tasks::{AsyncComputeTaskPool, IoTaskPool, Task};
+use bevy::window::PrimaryWindow;
+use gif::IndexedFrame;
+use image::imageops::{self, FilterType};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CaptureRequest>()
+            .init_resource::<GifRecorder>()
+            .add_systems(
+                Update,
+                (
+                    request_captures,
+                    take_screenshot,
+                    record_gif_frame.run_if(
+                        in_state(GameState::Playing)
+                            .and(game_not_paused.or(resource_exists::<PhotoMode>)),
+                    ),
+                    collect_gif_frames,
+                    export_gif,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Ask for a capture, from a key or elsewhere in the game.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureRequest {
+    /// Save the current frame as PNGs.
+    Screenshot,
+    /// Save the rolling buffer as a GIF.
+    Gif,
+}
+
+/// GIF frames per second. Recording reads back the canvas this often.
+const GIF_FPS: f32 = 10.0;
+
+/// The last few seconds of canvas frames, ready to export.
+#[derive(Resource)]
+pub struct GifRecorder {
+    /// Seconds of footage kept.
+    pub length: f32,
+    frames: VecDeque<IndexedFrame>,
+    /// Frames read back and being indexed off the main thread, oldest first.
+    indexing: VecDeque<Task<IndexedFrame>>,
+    timer: Timer,
+    /// A readback is on its way, so don't queue another behind it.
+    pending: bool,
+}
+
+impl Default for GifRecorder {
+    fn default() -> Self {
+        Self {
+            length: 6.0,
+            frames: VecDeque::new(),
+            indexing: VecDeque::new(),
+            timer: Timer::from_seconds(1.0 / GIF_FPS, TimerMode::Repeating),
+            pending: false,
+        }
+    }
+}
+
+impl GifRecorder {
+    fn push(&mut self, frame: IndexedFrame) {
+        self.frames.push_back(frame);
+        let capacity = (self.length * GIF_FPS).ceil().max(1.0) as usize;
+        while self.frames.len() > capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    /// The buffered frames since the canvas last changed size.
+    fn clip(&self) -> Vec<IndexedFrame> {
+        let Some(last) = self.frames.back() else {
+            return Vec::new();
+        };
+        let start = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.size != last.size)
+            .map_or(0, |i| i + 1);
+        self.frames.range(start..).cloned().collect()
+    }
+}
+
+/// Where captures are saved: a `captures` folder under the platform's user data directory.
+pub fn captures_dir() -> Option<PathBuf> {
+    let var = |name| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    let data_dir = if cfg!(windows) {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share")))
+    };
+    data_dir.map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("captures"))
+}
+
+/// A fresh path in `captures_dir`, without extension, named after `kind` and the time.
+fn capture_stem(kind: &str) -> Option<PathBuf> {
+    let dir = captures_dir()?;
+    if let Err(error) = fs::create_dir_all(&dir) {
+        error!("Cannot create {}: {error}", dir.display());
+        return None;
+    }
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis());
+    Some(dir.join(format!("{kind}-{millis}")))
+}
+
+fn request_captures(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut requests: MessageWriter<CaptureRequest>,
+) {
+    if keyboard.just_pressed(KeyCode::F12) {
+        requests.write(CaptureRequest::Screenshot);
+    }
+    if keyboard.just_pressed(KeyCode::F10) {
+        requests.write(CaptureRequest::Gif);
+    }
+}
+
+fn take_screenshot(
+    mut commands: Commands,
+    mut requests: MessageReader<CaptureRequest>,
+    canvas: Option<Res<CanvasImage>>,
+    layout: Res<CanvasLayout>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+) {
+    if !requests.read().any(|request| *request == CaptureRequest::Screenshot) {
+        return;
+    }
+    let Some(canvas) = canvas else {
+        return;
+    };
+    // Physical pixels per canvas pixel, so the large copy matches the screen
+    let scale_factor = window_q.single().map_or(1.0, Window::scale_factor);
+    let upscale = (layout.scale.max_element() * scale_factor).round().max(2.0) as u32;
+
+    commands
+        .spawn(Screenshot::image(canvas.0.clone()))
+        .observe(move |captured: On<ScreenshotCaptured>| {
+            let frame = match captured.image.clone().try_into_dynamic() {
+                Ok(image) => image.to_rgb8(),
+                Err(error) => {
+                    error!("Cannot read the canvas for a screenshot: {error}");
+                    return;
+                }
+            };
+            IoTaskPool::get()
+                .spawn(async move {
+                    let Some(stem) = capture_stem("screenshot") else {
+                        return;
+                    };
+                    let (width, height) = frame.dimensions();
+                    let upscaled =
+                        imageops::resize(&frame, width * upscale, height * upscale, FilterType::Nearest);
+                    let large = PathBuf::from(format!("{}-x{upscale}.png", stem.display()));
+                    for (image, path) in [(frame, stem.with_extension("png")), (upscaled, large)] {
+                        match image.save(&path) {
+                            Ok(()) => info!("Screenshot saved to {}", path.display()),
+                            Err(error) => error!("Cannot save {}: {error}", path.display()),
+                        }
+                    }
+                })
+                .detach();
+        });
+}
+
+fn record_gif_frame(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut recorder: ResMut<GifRecorder>,
+    canvas: Option<Res<CanvasImage>>,
+) {
+    recorder.timer.tick(time.delta());
+    if !recorder.timer.just_finished() || recorder.pending {
+        return;
+    }
+    let Some(canvas) = canvas else {
+        return;
+    };
+    recorder.pending = true;
+
+    commands.spawn(Screenshot::image(canvas.0.clone())).observe(
+        |captured: On<ScreenshotCaptured>, mut recorder: ResMut<GifRecorder>| {
+            recorder.pending = false;
+            let size = captured.image.size();
+            match captured.image.clone().try_into_dynamic() {
+                Ok(image) => {
+                    let task = AsyncComputeTaskPool::get()
+                        .spawn(async move { IndexedFrame::from_rgba(size, &image.to_rgba8()) });
+                    recorder.indexing.push_back(task);
+                }
+                Err(error) => error!("Cannot read the canvas for a GIF frame: {error}"),
+            }
+        },
+    );
+}
+
+/// Move frames that have finished indexing into the buffer, keeping them in order.
+fn collect_gif_frames(mut recorder: ResMut<GifRecorder>) {
+    while let Some(task) = recorder.indexing.front_mut() {
+        let Some(frame) = check_ready(task) else {
+            break;
+        };
+        recorder.indexing.pop_front();
+        recorder.push(frame);
+    }
+}
+
+fn export_gif(mut requests: MessageReader<CaptureRequest>, recorder: Res<GifRecorder>) {
+    if !requests.read().any(|request| *request == CaptureRequest::Gif) {
+        return;
+    }
+    let frames = recorder.clip();
+    if frames.is_empty() {
+        warn!("No frames recorded for a GIF yet");
+        return;
+    }
+    IoTaskPool::get()
+        .spawn(async move {
+            let Some(path) = capture_stem("clip").map(|stem| stem.with_extension("gif")) else {
+                return;
+            };
+            let delay_ms = (1000.0 / GIF_FPS).round() as u32;
+            let written = File::create(&path)
+                .map_err(image::ImageError::from)
+                .and_then(|file| gif::write_gif(BufWriter::new(file), &frames, delay_ms));
+            match written {
+                Ok(()) => info!("GIF of {} frames saved to {}", frames.len(), path.display()),
+                Err(error) => error!("Cannot save {}: {error}", path.display()),
+            }
+        })
+        .detach();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: u32, color: u8) -> IndexedFrame {
+        IndexedFrame::from_rgba(UVec2::new(width, 1), &[color, color, color, 255].repeat(width as usize))
+    }
+
+    #[test]
+    fn recorder_keeps_the_last_seconds_at_one_size() {
+        let mut recorder = GifRecorder {
+            length: 1.0,
+            ..default()
+        };
+        for i in 0..15 {
+            recorder.push(frame(if i < 12 { 4 } else { 2 }, i));
+        }
+        assert_eq!(recorder.frames.len(), GIF_FPS as usize);
+
+        // Frames from before the canvas was resized are left out
+        let clip = recorder.clip();
+        assert_eq!(clip.len(), 3);
+        assert_eq!(clip[0], frame(2, 12));
+    }
+}
@@ -1,7 +1,9 @@
 use crate::GameState;
 use crate::environment::RunTimer;
 use crate::loading::{AudioAssets, FontAssets, TextureAssets};
+use crate::locale::Locale;
 use crate::pause::Paused;
+use crate::transition::{Ease, GameStateTransition, SceneTransition, Transition};
 use bevy::prelude::*;
 use bevy::text::FontSmoothing;
 use bevy_kira_audio::{Audio, AudioControl};
@@ -20,6 +22,12 @@ impl Plugin for DeathPlugin {
     }
 }
 
+/// The death screen surfaces out of a palette spinning down to black.
+const DEATH_TRANSITION: Transition = Transition::palette_cycle(1.5).eased(Ease::OutQuad);
+
+/// Back to the menu through a slow fade.
+const LEAVE_TRANSITION: Transition = Transition::fade(1.5).eased(Ease::InQuad);
+
 /// Insert this resource to trigger the death screen.
 #[derive(Resource)]
 pub struct Dead;
@@ -46,7 +54,9 @@ fn show_death_screen(
     fonts: Res<FontAssets>,
     textures: Res<TextureAssets>,
     run_timer: Option<Res<RunTimer>>,
+    locale: Res<Locale>,
     mut paused: ResMut<Paused>,
+    mut transition: Option<ResMut<SceneTransition>>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
 ) {
@@ -54,6 +64,10 @@ fn show_death_screen(
         return;
     }
 
+    if let Some(transition) = transition.as_mut() {
+        transition.fade_in(DEATH_TRANSITION);
+    }
+
     audio.play(audio_assets.death.clone());
 
     // Freeze gameplay
@@ -96,7 +110,10 @@ fn show_death_screen(
 
             // Survival time
             parent.spawn((
-                Text::new(format!("You survived {}:{:02}", minutes, seconds)),
+                Text::new(locale.format(
+                    "death.survived",
+                    &[("time", &format!("{minutes}:{seconds:02}"))],
+                )),
                 TextFont {
                     font: font.clone(),
                     font_size: 32.0,
@@ -129,7 +146,7 @@ fn show_death_screen(
                     DeathReturnButton,
                 ))
                 .with_child((
-                    Text::new("Return to Menu"),
+                    Text::new(locale.get("death.return")),
                     TextFont {
                         font,
                         font_size: 32.0,
@@ -142,7 +159,7 @@ fn show_death_screen(
 }
 
 fn handle_death_button(
-    mut next_state: ResMut<NextState<GameState>>,
+    mut states: GameStateTransition,
     query: Query<&Interaction, (Changed<Interaction>, With<DeathReturnButton>)>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
@@ -150,7 +167,7 @@ fn handle_death_button(
     for interaction in &query {
         if *interaction == Interaction::Pressed {
             audio.play(audio_assets.fx1.clone());
-            next_state.set(GameState::Menu);
+            states.go_to(GameState::Menu, LEAVE_TRANSITION);
         }
     }
 }
@@ -161,3 +178,49 @@ fn cleanup_death(mut commands: Commands, query: Query<Entity, With<DeathScreen>>
         commands.entity(entity).despawn();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aberration::{Aberration, AberrationPlugin};
+    use crate::pause::PausePlugin;
+    use crate::testing::TestApp;
+
+    fn setup() -> (TestApp, Entity) {
+        let mut app = TestApp::new().with_plugins((DeathPlugin, PausePlugin, AberrationPlugin));
+        app.start_playing();
+        let player = app.spawn_player(Vec3::new(0.0, 1.7, 5.0));
+        app.app
+            .world_mut()
+            .spawn((Transform::from_xyz(0.0, 1.0, 4.0), Aberration));
+        app.step();
+        (app, player)
+    }
+
+    #[test]
+    fn lingering_next_to_an_aberration_kills() {
+        let (mut app, _) = setup();
+
+        app.advance(6.0);
+
+        assert!(app.has_resource::<Dead>());
+        assert!(app.resource::<Paused>().0);
+    }
+
+    #[test]
+    fn walking_away_cancels_the_countdown() {
+        let (mut app, player) = setup();
+
+        app.advance(2.0);
+        app.app
+            .world_mut()
+            .get_mut::<Transform>(player)
+            .unwrap()
+            .translation
+            .z = 20.0;
+        app.advance(6.0);
+
+        assert!(!app.has_resource::<Dead>());
+        assert!(!app.resource::<Paused>().0);
+    }
+}
@@ -0,0 +1,372 @@
+//! Ambient barks: short lines an NPC says on its own when the player comes near, shown in a
+//! speech bubble floating above it until the player talks to it properly.
+
+use super::markup::Markup;
+use super::{DialogState, Npc, NpcIdentity, text_font};
+use crate::loading::{FontAssets, TextureAssets};
+use crate::locale::Locale;
+use crate::player::{FpsCamera, Player};
+use crate::scaling::CanvasLayout;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+/// Side of the bubble billboard, in world units.
+const BUBBLE_SIZE: f32 = 0.6;
+const LABEL_SIZE: f32 = 20.0;
+const LABEL_MAX_WIDTH: f32 = 320.0;
+/// Gap between the top of the bubble and the bottom of its line, in window pixels.
+const LABEL_GAP: f32 = 4.0;
+
+/// What an NPC says unprompted, as written in `npcs.ron` and `types.ron`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Barks {
+    /// One is picked at random for each bark, never the same one twice in a row.
+    pub lines: Vec<String>,
+    /// How close the player must be for the NPC to bark.
+    #[serde(default = "default_radius")]
+    pub radius: f32,
+    /// Seconds from one bark starting to the next.
+    #[serde(default = "default_cooldown")]
+    pub cooldown: f32,
+    /// Seconds the bubble stays up.
+    #[serde(default = "default_duration")]
+    pub duration: f32,
+    /// Height of the bubble above the NPC's origin.
+    #[serde(default = "default_height")]
+    pub height: f32,
+}
+
+fn default_radius() -> f32 {
+    8.0
+}
+
+fn default_cooldown() -> f32 {
+    12.0
+}
+
+fn default_duration() -> f32 {
+    3.0
+}
+
+fn default_height() -> f32 {
+    2.6
+}
+
+#[derive(Resource)]
+pub(super) struct BarkAssets {
+    quad: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+/// Added to an NPC when it first barks.
+#[derive(Component)]
+pub(super) struct BarkCooldown {
+    timer: Timer,
+    last_line: usize,
+}
+
+/// Speech bubble billboard above `npc`.
+#[derive(Component)]
+pub struct BarkBubble {
+    pub npc: Entity,
+    /// The UI text with the line, kept above the bubble on screen.
+    label: Entity,
+    height: f32,
+    timer: Timer,
+}
+
+#[derive(Component)]
+pub(super) struct BarkLabel;
+
+pub(super) fn init_barks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    textures: Res<TextureAssets>,
+) {
+    commands.insert_resource(BarkAssets {
+        quad: meshes.add(Rectangle::new(BUBBLE_SIZE, BUBBLE_SIZE)),
+        material: materials.add(StandardMaterial {
+            base_color_texture: Some(textures.talk_bubble.clone()),
+            alpha_mode: AlphaMode::Mask(0.5),
+            unlit: true,
+            cull_mode: None,
+            ..default()
+        }),
+    });
+}
+
+/// Start a bark for every NPC whose cooldown is over, whose last bubble has popped, and who has
+/// the player in range.
+pub(super) fn start_barks(
+    mut commands: Commands,
+    time: Res<Time>,
+    state: Res<DialogState>,
+    player_q: Query<&GlobalTransform, With<Player>>,
+    mut npc_q: Query<
+        (Entity, &GlobalTransform, &NpcIdentity, Option<&mut BarkCooldown>),
+        With<Npc>,
+    >,
+    bubble_q: Query<&BarkBubble>,
+    assets: Res<BarkAssets>,
+    locale: Res<Locale>,
+    fonts: Res<FontAssets>,
+) {
+    let Ok(player_tf) = player_q.single() else {
+        return;
+    };
+    let player_pos = player_tf.translation();
+    let mut rng = rand::rng();
+
+    for (npc, npc_tf, identity, cooldown) in &mut npc_q {
+        let Some(barks) = identity.barks.as_ref().filter(|b| !b.lines.is_empty()) else {
+            continue;
+        };
+        let last_line = match cooldown {
+            Some(mut cooldown) => {
+                cooldown.timer.tick(time.delta());
+                if !cooldown.timer.is_finished() {
+                    continue;
+                }
+                Some(cooldown.last_line)
+            }
+            None => None,
+        };
+        if state.active
+            || player_pos.distance(npc_tf.translation()) > barks.radius
+            || bubble_q.iter().any(|bubble| bubble.npc == npc)
+        {
+            continue;
+        }
+
+        let index = pick_line(barks.lines.len(), last_line, &mut rng);
+        let line = bark_text(&locale, identity.name.as_deref(), &barks.lines[index], index);
+        let label = commands
+            .spawn((
+                Text::new(Markup::parse_or_plain(line).to_plain_string()),
+                TextFont {
+                    font_size: LABEL_SIZE,
+                    ..text_font(&fonts)
+                },
+                TextColor(Color::WHITE),
+                TextLayout::new_with_justify(Justify::Center),
+                Node {
+                    position_type: PositionType::Absolute,
+                    max_width: Val::Px(LABEL_MAX_WIDTH),
+                    ..default()
+                },
+                // Shown once it has been placed above the bubble
+                Visibility::Hidden,
+                GlobalZIndex(70),
+                BarkLabel,
+            ))
+            .id();
+        commands.spawn((
+            Mesh3d(assets.quad.clone()),
+            MeshMaterial3d(assets.material.clone()),
+            Transform::from_translation(npc_tf.translation() + Vec3::Y * barks.height),
+            BarkBubble {
+                npc,
+                label,
+                height: barks.height,
+                timer: Timer::from_seconds(barks.duration, TimerMode::Once),
+            },
+        ));
+        commands.entity(npc).insert(BarkCooldown {
+            timer: Timer::from_seconds(barks.cooldown, TimerMode::Once),
+            last_line: index,
+        });
+    }
+}
+
+/// Bark `index` in the current language. Named NPCs' barks are keyed by name and index; unnamed
+/// ones share keys by their English text.
+fn bark_text<'a>(locale: &'a Locale, name: Option<&str>, english: &'a str, index: usize) -> &'a str {
+    match name {
+        Some(name) => locale.get_or(&format!("npc.{name}.bark.{index}"), english),
+        None => locale.get_or(&format!("bark.{english}"), english),
+    }
+}
+
+/// A random line index, other than `last` when there is a choice.
+fn pick_line(count: usize, last: Option<usize>, rng: &mut impl Rng) -> usize {
+    match last {
+        Some(last) if count > 1 => (last + rng.random_range(1..count)) % count,
+        _ => rng.random_range(0..count),
+    }
+}
+
+/// Keep bubbles above their NPC and facing the player, and pop them once their time is up, the
+/// NPC is gone, or a conversation starts.
+pub(super) fn update_barks(
+    mut commands: Commands,
+    time: Res<Time>,
+    state: Res<DialogState>,
+    player_q: Query<&GlobalTransform, With<Player>>,
+    npc_q: Query<&GlobalTransform, With<Npc>>,
+    mut bubble_q: Query<(Entity, &mut BarkBubble, &mut Transform)>,
+) {
+    let player_pos = player_q.single().ok().map(|tf| tf.translation());
+
+    for (entity, mut bubble, mut transform) in &mut bubble_q {
+        bubble.timer.tick(time.delta());
+        let over = state.active || bubble.timer.is_finished();
+        let Some(npc_tf) = npc_q.get(bubble.npc).ok().filter(|_| !over) else {
+            commands.entity(entity).despawn();
+            commands.entity(bubble.label).despawn();
+            continue;
+        };
+
+        transform.translation = npc_tf.translation() + Vec3::Y * bubble.height;
+        if let Some(player_pos) = player_pos {
+            let dir = player_pos - transform.translation;
+            transform.rotation = Quat::from_rotation_y(dir.x.atan2(dir.z));
+        }
+    }
+}
+
+/// Centre each bubble's line just above it on screen, hiding it while the bubble is out of view.
+pub(super) fn place_bark_labels(
+    camera_q: Query<(&Camera, &GlobalTransform), With<FpsCamera>>,
+    layout: Res<CanvasLayout>,
+    ui_scale: Res<UiScale>,
+    bubble_q: Query<(&BarkBubble, &Transform)>,
+    mut label_q: Query<(&mut Node, &mut Visibility, &ComputedNode), With<BarkLabel>>,
+) {
+    let Ok((camera, cam_tf)) = camera_q.single() else {
+        return;
+    };
+
+    for (bubble, transform) in &bubble_q {
+        let Ok((mut node, mut visibility, computed)) = label_q.get_mut(bubble.label) else {
+            continue;
+        };
+        let top = transform.translation + Vec3::Y * BUBBLE_SIZE / 2.0;
+        match camera.world_to_viewport(cam_tf, top) {
+            Ok(viewport_pos) => {
+                // UI lengths are in window pixels divided by the UI scale
+                let pos = layout.canvas_to_window(viewport_pos) / ui_scale.0;
+                let size = computed.size() * computed.inverse_scale_factor();
+                node.left = Val::Px(pos.x - size.x / 2.0);
+                node.top = Val::Px(pos.y - size.y - LABEL_GAP);
+                *visibility = Visibility::Inherited;
+            }
+            Err(_) => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+pub(super) fn cleanup_barks(
+    mut commands: Commands,
+    bubble_q: Query<Entity, Or<(With<BarkBubble>, With<BarkLabel>)>>,
+) {
+    for entity in &bubble_q {
+        commands.entity(entity).despawn();
+    }
+    commands.remove_resource::<BarkAssets>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aberration::SpawnAberration;
+    use crate::dialog::DialogPlugin;
+    use crate::dispel::Dispelled;
+    use crate::environment::SwitchEnvironment;
+    use crate::testing::TestApp;
+
+    /// A playing app with a barking NPC `distance` in front of the player.
+    fn setup(distance: f32) -> (TestApp, Entity) {
+        setup_with(distance, |_| {})
+    }
+
+    fn setup_with(distance: f32, edit: impl FnOnce(&mut Barks)) -> (TestApp, Entity) {
+        let mut barks = Barks {
+            lines: vec!["Psst.".into(), "[wave]Over here.[/wave]".into()],
+            radius: 6.0,
+            cooldown: 4.0,
+            duration: 1.0,
+            height: 1.0,
+        };
+        edit(&mut barks);
+        let mut app = TestApp::new().with_plugins(DialogPlugin);
+        app.app
+            .add_message::<SpawnAberration>()
+            .add_message::<SwitchEnvironment>()
+            .add_message::<Dispelled>();
+        app.start_playing();
+        app.spawn_player(Vec3::new(0.0, 1.7, 0.0));
+        let npc = app
+            .app
+            .world_mut()
+            .spawn((
+                Transform::from_xyz(0.0, 1.7, -distance),
+                Npc { range: 3.0 },
+                NpcIdentity {
+                    barks: Some(barks),
+                    ..default()
+                },
+            ))
+            .id();
+        app.step();
+        (app, npc)
+    }
+
+    fn bark_lines(app: &mut TestApp) -> Vec<String> {
+        app.app
+            .world_mut()
+            .query_filtered::<&Text, With<BarkLabel>>()
+            .iter(app.app.world())
+            .map(|t| t.0.clone())
+            .collect()
+    }
+
+    #[test]
+    fn npcs_bark_in_range_and_wait_out_their_cooldown() {
+        let (mut app, _) = setup(5.0);
+        let first = bark_lines(&mut app);
+        assert_eq!(first.len(), 1);
+        assert!(["Psst.", "Over here."].contains(&first[0].as_str()));
+
+        app.advance(1.1);
+        assert!(bark_lines(&mut app).is_empty(), "bubble outlasted its duration");
+        app.advance(2.5);
+        assert!(bark_lines(&mut app).is_empty(), "barked again during the cooldown");
+
+        app.advance(0.5);
+        let second = bark_lines(&mut app);
+        assert_eq!(second.len(), 1);
+        assert_ne!(first, second, "repeated the last line");
+    }
+
+    #[test]
+    fn bubbles_do_not_stack_when_the_cooldown_is_shorter() {
+        let (mut app, _) = setup_with(5.0, |barks| {
+            barks.cooldown = 0.5;
+            barks.duration = 2.0;
+        });
+        assert_eq!(bark_lines(&mut app).len(), 1);
+        app.advance(1.0);
+        assert_eq!(bark_lines(&mut app).len(), 1, "barked over its own bubble");
+        app.advance(1.1);
+        assert_eq!(bark_lines(&mut app).len(), 1, "didn't bark again once the bubble popped");
+    }
+
+    #[test]
+    fn npcs_out_of_range_stay_quiet() {
+        let (mut app, _) = setup(7.0);
+        app.advance(1.0);
+        assert!(bark_lines(&mut app).is_empty());
+    }
+
+    #[test]
+    fn talking_pops_the_bubble() {
+        let (mut app, _) = setup(2.0);
+        assert_eq!(bark_lines(&mut app).len(), 1);
+
+        app.tap_key(KeyCode::KeyE);
+        assert!(app.resource::<DialogState>().active);
+        assert!(bark_lines(&mut app).is_empty());
+    }
+}
@@ -0,0 +1,189 @@
+//! Conditions that gate dialog responses and NPC continuations, and the per-run flags and
+//! variables they read.
+
+use super::history::NpcMemory;
+use crate::environment::Environment;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A test against the game state at the moment a response is shown or chosen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    /// Sanity (`Health::fraction`) is below the given fraction.
+    SanityBelow(f32),
+    /// Sanity is at or above the given fraction.
+    SanityAbove(f32),
+    /// The run is currently in this environment.
+    Environment(Environment),
+    /// A flag set by an earlier dialog effect.
+    Flag(String),
+    /// A dialog variable is at least this value (unset variables are 0).
+    VarAtLeast(String, i32),
+    /// At least this many aberrations have been dispelled this run.
+    DispelledAtLeast(u32),
+    /// The player has finished at least this many conversations with this NPC.
+    SpokenToAtLeast(u32),
+    /// This NPC has said the `tree/node` line to the player before.
+    Visited(String),
+    /// The last conversation with this NPC ended on the `tree/node` line.
+    LastNode(String),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+/// Flags and counters that live for one run, set by dialog effects and gameplay.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct DialogVars {
+    flags: HashSet<String>,
+    vars: HashMap<String, i32>,
+    /// Aberrations dispelled this run.
+    pub dispelled: u32,
+}
+
+impl DialogVars {
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    pub fn set_flag(&mut self, name: &str, value: bool) {
+        if value {
+            self.flags.insert(name.to_string());
+        } else {
+            self.flags.remove(name);
+        }
+    }
+
+    pub fn var(&self, name: &str) -> i32 {
+        self.vars.get(name).copied().unwrap_or(0)
+    }
+
+    pub fn add_var(&mut self, name: &str, delta: i32) {
+        *self.vars.entry(name.to_string()).or_insert(0) += delta;
+    }
+
+    /// Every flag currently set.
+    pub fn flags(&self) -> impl Iterator<Item = &str> {
+        self.flags.iter().map(String::as_str)
+    }
+
+    /// Every variable that has been changed, with its value.
+    pub fn vars(&self) -> impl Iterator<Item = (&str, i32)> {
+        self.vars.iter().map(|(name, value)| (name.as_str(), *value))
+    }
+}
+
+/// Snapshot of the game state conditions are checked against.
+#[derive(Debug, Clone)]
+pub struct DialogContext<'a> {
+    /// Sanity as a fraction of its maximum.
+    pub sanity: f32,
+    /// `None` outside of a run, where the environment sub-state doesn't exist.
+    pub environment: Option<Environment>,
+    pub vars: &'a DialogVars,
+    /// What the NPC being talked to remembers, if they have talked before.
+    pub npc: Option<&'a NpcMemory>,
+}
+
+impl<'a> DialogContext<'a> {
+    /// Full sanity, no environment, a stranger, and the given variables.
+    pub fn with_vars(vars: &'a DialogVars) -> Self {
+        Self {
+            sanity: 1.0,
+            environment: None,
+            vars,
+            npc: None,
+        }
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, ctx: &DialogContext) -> bool {
+        match self {
+            Condition::SanityBelow(x) => ctx.sanity < *x,
+            Condition::SanityAbove(x) => ctx.sanity >= *x,
+            Condition::Environment(env) => ctx.environment.as_ref() == Some(env),
+            Condition::Flag(name) => ctx.vars.flag(name),
+            Condition::VarAtLeast(name, min) => ctx.vars.var(name) >= *min,
+            Condition::DispelledAtLeast(n) => ctx.vars.dispelled >= *n,
+            Condition::SpokenToAtLeast(n) => ctx.npc.map_or(0, |m| m.times_spoken) >= *n,
+            Condition::Visited(node) => ctx.npc.is_some_and(|m| m.visited.contains(node)),
+            Condition::LastNode(node) => {
+                ctx.npc.and_then(|m| m.last_node.as_ref()) == Some(node)
+            }
+            Condition::Not(c) => !c.holds(ctx),
+            Condition::All(cs) => cs.iter().all(|c| c.holds(ctx)),
+            Condition::Any(cs) => cs.iter().any(|c| c.holds(ctx)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combinators_follow_boolean_logic() {
+        let vars = DialogVars::default();
+        let ctx = DialogContext {
+            sanity: 0.3,
+            ..DialogContext::with_vars(&vars)
+        };
+        let low = Condition::SanityBelow(0.5);
+        let high = Condition::SanityAbove(0.5);
+        assert!(low.holds(&ctx));
+        assert!(!high.holds(&ctx));
+        assert!(Condition::Not(Box::new(high.clone())).holds(&ctx));
+        assert!(Condition::Any(vec![low.clone(), high.clone()]).holds(&ctx));
+        assert!(!Condition::All(vec![low, high]).holds(&ctx));
+        assert!(Condition::All(vec![]).holds(&ctx));
+    }
+
+    #[test]
+    fn run_state_conditions_read_vars_and_environment() {
+        let mut vars = DialogVars::default();
+        vars.set_flag("met", true);
+        vars.add_var("insults", 2);
+        vars.dispelled = 3;
+        let ctx = DialogContext {
+            environment: Some(Environment::Dissociation),
+            ..DialogContext::with_vars(&vars)
+        };
+
+        assert!(Condition::Flag("met".into()).holds(&ctx));
+        assert!(!Condition::Flag("other".into()).holds(&ctx));
+        assert!(Condition::VarAtLeast("insults".into(), 2).holds(&ctx));
+        assert!(!Condition::VarAtLeast("insults".into(), 3).holds(&ctx));
+        assert!(Condition::VarAtLeast("unset".into(), 0).holds(&ctx));
+        assert!(Condition::DispelledAtLeast(3).holds(&ctx));
+        assert!(Condition::Environment(Environment::Dissociation).holds(&ctx));
+        assert!(!Condition::Environment(Environment::Delirium).holds(&ctx));
+
+        vars.set_flag("met", false);
+        assert!(!vars.flag("met"));
+    }
+
+    #[test]
+    fn npc_conditions_read_its_memory() {
+        let vars = DialogVars::default();
+        let stranger = DialogContext::with_vars(&vars);
+        assert!(Condition::SpokenToAtLeast(0).holds(&stranger));
+        assert!(!Condition::SpokenToAtLeast(1).holds(&stranger));
+        assert!(!Condition::Visited("t/a".into()).holds(&stranger));
+
+        let memory = NpcMemory {
+            visited: ["t/a".to_string(), "t/b".to_string()].into(),
+            last_node: Some("t/b".into()),
+            times_spoken: 2,
+        };
+        let ctx = DialogContext {
+            npc: Some(&memory),
+            ..DialogContext::with_vars(&vars)
+        };
+        assert!(Condition::SpokenToAtLeast(2).holds(&ctx));
+        assert!(Condition::Visited("t/a".into()).holds(&ctx));
+        assert!(Condition::LastNode("t/b".into()).holds(&ctx));
+        assert!(!Condition::LastNode("t/a".into()).holds(&ctx));
+    }
+}
@@ -0,0 +1,46 @@
+//! Effects an NPC line has on the run when it is shown.
+
+use crate::environment::Environment;
+use crate::loading::AudioAssets;
+use bevy::prelude::*;
+use bevy_kira_audio::AudioSource;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    SetFlag(String),
+    ClearFlag(String),
+    /// Add to a dialog variable (unset variables start at 0).
+    AddVar(String, i32),
+    /// Restore sanity, as a fraction of the maximum.
+    Heal(f32),
+    /// Take sanity, as a fraction of the maximum.
+    Damage(f32),
+    /// Spawn an aberration near the player.
+    SpawnAberration,
+    /// Switch environment now and restart the cycle.
+    SwitchEnvironment(Environment),
+    PlaySound(Sound),
+}
+
+/// Sounds dialog can play, named after the fields of `AudioAssets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sound {
+    Death,
+    Dispel,
+    Footsteps,
+    Fx1,
+    Talk,
+}
+
+impl Sound {
+    pub fn handle(self, assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            Sound::Death => assets.death.clone(),
+            Sound::Dispel => assets.dispel.clone(),
+            Sound::Footsteps => assets.footsteps.clone(),
+            Sound::Fx1 => assets.fx1.clone(),
+            Sound::Talk => assets.talk.clone(),
+        }
+    }
+}
@@ -0,0 +1,825 @@
+//! Dialog graph: conversations as NPC lines keyed by ID, linked by `goto` targets.
+//!
+//! Each tree is a list of nodes and starts at its first one. A node is an NPC line with the
+//! player's responses; each response names the node(s) the NPC continues with. A target is a
+//! node ID in the same tree, or `tree/node` to jump into another tree, so conversations can
+//! loop back, share branches and hand over between trees. When a response lists several
+//! continuations, one is picked at random by weight from those whose condition holds; a
+//! response with none ends the conversation. Responses can be hidden behind a `when`
+//! condition, and nodes can carry `effects` that apply when the line is shown.
+//!
+//! A node with a `timer` only waits that many seconds for an answer. When it runs out the
+//! response marked `timeout` is chosen, or, without one, the NPC continues with the node's
+//! `silence` continuations as if the player had said nothing.
+//!
+//! Responses are keyed for translation by an `id`, unique within their node. Left out, it is
+//! the first `goto` as written, or `end` for a response without one, so only responses that
+//! would clash need one spelled out.
+//!
+//! The original nested `DialogueTrees` format still loads. It is converted on the fly, and
+//! `migrate_legacy` prints the equivalent graph RON to replace it with.
+
+use super::condition::{Condition, DialogContext};
+use super::effect::Effect;
+use super::markup::{Markup, MarkupError};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Separates the tree from the node in a cross-tree `goto`.
+const TREE_SEPARATOR: char = '/';
+/// ID of a response without an `id` or a `goto`.
+const END_RESPONSE: &str = "end";
+
+// --- RON data ---
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DialogGraphRon {
+    trees: Vec<TreeRon>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TreeRon {
+    id: String,
+    nodes: Vec<NodeRon>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NodeRon {
+    id: String,
+    text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    responses: Vec<ResponseRon>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    win: bool,
+    /// The conversation is meant to stop on this line.
+    #[serde(default, skip_serializing_if = "is_false")]
+    end: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    effects: Vec<Effect>,
+    /// Seconds the player has to answer once the responses are shown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timer: Option<f32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    silence: Vec<ContinuationRon>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ResponseRon {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    next: Vec<ContinuationRon>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    win: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    when: Option<Condition>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    timeout: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ContinuationRon {
+    goto: String,
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    weight: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    when: Option<Condition>,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn is_default_weight(weight: &f32) -> bool {
+    *weight == 1.0
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+// --- Legacy nested format ---
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+enum Role {
+    Npc,
+    Player,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DialogueNode {
+    role: Role,
+    text: String,
+    responses: Vec<DialogueNode>,
+    #[serde(default)]
+    win: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct DialogueTrees(Vec<DialogueNode>);
+
+// --- Runtime graph ---
+
+/// Index of a node in a `DialogGraph`.
+pub type NodeId = usize;
+
+#[derive(Debug, Clone)]
+pub struct TreeDef {
+    pub id: String,
+    pub start: NodeId,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeDef {
+    /// Fully qualified `tree/node` ID.
+    pub id: String,
+    pub text: String,
+    pub responses: Vec<ResponseDef>,
+    /// Ending the conversation on this line counts as a win.
+    pub win: bool,
+    /// The author means the conversation to stop here, so it isn't a dead end.
+    pub end: bool,
+    /// Applied each time the line is shown.
+    pub effects: Vec<Effect>,
+    /// Seconds the player has to answer.
+    pub timer: Option<f32>,
+    /// Where the NPC goes when the timer runs out and no timeout response is offered.
+    pub silence: Vec<ContinuationDef>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResponseDef {
+    /// Unique within the node; keys the response's translations.
+    pub id: String,
+    pub text: String,
+    pub next: Vec<ContinuationDef>,
+    /// Choosing this response wins the conversation once it ends.
+    pub win: bool,
+    /// The response is only offered while this holds.
+    pub condition: Option<Condition>,
+    /// Chosen for the player when the node's timer runs out.
+    pub timeout: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContinuationDef {
+    pub target: NodeId,
+    pub weight: f32,
+    pub condition: Option<Condition>,
+}
+
+#[derive(Debug)]
+pub enum GraphError {
+    Parse(ron::error::SpannedError),
+    EmptyTree(String),
+    DuplicateTree(String),
+    DuplicateNode(String),
+    DuplicateResponse { node: String, response: String },
+    UnknownTarget { from: String, goto: String },
+    /// A continuation weight that is negative, infinite or NaN.
+    BadWeight { from: String, goto: String, weight: f32 },
+    /// A bad `timer`, several `timeout` responses, or a timeout or silence without a timer.
+    BadTimer(String),
+    Markup { node: String, error: MarkupError },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::Parse(e) => write!(f, "{e}"),
+            GraphError::EmptyTree(tree) => write!(f, "tree `{tree}` has no nodes"),
+            GraphError::DuplicateTree(tree) => write!(f, "tree `{tree}` is defined twice"),
+            GraphError::DuplicateNode(node) => write!(f, "node `{node}` is defined twice"),
+            GraphError::DuplicateResponse { node, response } => write!(
+                f,
+                "node `{node}` has several responses with ID `{response}`; give them distinct `id`s"
+            ),
+            GraphError::UnknownTarget { from, goto } => {
+                write!(f, "`{from}` continues to unknown node `{goto}`")
+            }
+            GraphError::BadWeight { from, goto, weight } => write!(
+                f,
+                "`{from}` continues to `{goto}` with weight {weight}; weights must be finite and not negative"
+            ),
+            GraphError::BadTimer(node) => write!(f, "node `{node}` has an invalid response timer"),
+            GraphError::Markup { node, error } => write!(f, "`{node}`: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+/// All dialog trees, with every `goto` resolved to a `NodeId`.
+#[derive(Debug, Clone, Default)]
+pub struct DialogGraph {
+    trees: Vec<TreeDef>,
+    nodes: Vec<NodeDef>,
+}
+
+impl DialogGraph {
+    /// Parse a dialog file in either the graph format or the legacy `DialogueTrees` format.
+    pub fn from_ron(source: &str) -> Result<Self, GraphError> {
+        match ron::from_str::<DialogGraphRon>(source) {
+            Ok(data) => Self::build(data),
+            Err(graph_err) => match ron::from_str::<DialogueTrees>(source) {
+                Ok(legacy) => Self::build(convert_legacy(legacy)),
+                // Report against the current format; the legacy one is only a fallback
+                Err(_) => Err(GraphError::Parse(graph_err)),
+            },
+        }
+    }
+
+    fn build(data: DialogGraphRon) -> Result<Self, GraphError> {
+        // First pass: assign every node an index so gotos can point forwards and across trees
+        let mut index = HashMap::new();
+        let mut trees = Vec::new();
+        for tree in &data.trees {
+            if tree.nodes.is_empty() {
+                return Err(GraphError::EmptyTree(tree.id.clone()));
+            }
+            if trees.iter().any(|t: &TreeDef| t.id == tree.id) {
+                return Err(GraphError::DuplicateTree(tree.id.clone()));
+            }
+            trees.push(TreeDef {
+                id: tree.id.clone(),
+                start: index.len(),
+            });
+            for node in &tree.nodes {
+                let id = qualify(&tree.id, &node.id);
+                if index.insert(id.clone(), index.len()).is_some() {
+                    return Err(GraphError::DuplicateNode(id));
+                }
+            }
+        }
+
+        let mut nodes = Vec::with_capacity(index.len());
+        for tree in data.trees {
+            for node in tree.nodes {
+                let id = qualify(&tree.id, &node.id);
+                check_markup(&id, &node.text)?;
+                check_timer(&id, &node)?;
+                let mut response_ids = HashSet::new();
+                let responses = node
+                    .responses
+                    .into_iter()
+                    .map(|response| {
+                        let response_id = response.id.clone().unwrap_or_else(|| {
+                            response
+                                .next
+                                .first()
+                                .map_or_else(|| END_RESPONSE.to_string(), |c| c.goto.clone())
+                        });
+                        if !response_ids.insert(response_id.clone()) {
+                            return Err(GraphError::DuplicateResponse {
+                                node: id.clone(),
+                                response: response_id,
+                            });
+                        }
+                        let next = resolve_all(&index, &tree.id, &id, response.next)?;
+                        check_markup(&id, &response.text)?;
+                        Ok(ResponseDef {
+                            id: response_id,
+                            text: response.text,
+                            next,
+                            win: response.win,
+                            condition: response.when,
+                            timeout: response.timeout,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, GraphError>>()?;
+                let silence = resolve_all(&index, &tree.id, &id, node.silence)?;
+                nodes.push(NodeDef {
+                    id,
+                    text: node.text,
+                    responses,
+                    win: node.win,
+                    end: node.end,
+                    effects: node.effects,
+                    timer: node.timer,
+                    silence,
+                });
+            }
+        }
+
+        Ok(Self { trees, nodes })
+    }
+
+    pub fn trees(&self) -> &[TreeDef] {
+        &self.trees
+    }
+
+    pub fn nodes(&self) -> &[NodeDef] {
+        &self.nodes
+    }
+
+    pub fn node(&self, id: NodeId) -> &NodeDef {
+        &self.nodes[id]
+    }
+
+    /// Look a node up by its `tree/node` ID.
+    pub fn find(&self, qualified_id: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.id == qualified_id)
+    }
+
+    pub fn tree_start(&self, tree: &str) -> Option<NodeId> {
+        self.trees.iter().find(|t| t.id == tree).map(|t| t.start)
+    }
+
+    /// Nodes that no tree start leads to, whatever the conditions along the way.
+    pub fn unreachable_nodes(&self) -> Vec<NodeId> {
+        let mut reached = vec![false; self.nodes.len()];
+        let mut stack: Vec<NodeId> = self.trees.iter().map(|t| t.start).collect();
+        while let Some(id) = stack.pop() {
+            if !std::mem::replace(&mut reached[id], true) {
+                stack.extend(self.successors(id));
+            }
+        }
+        (0..self.nodes.len()).filter(|&id| !reached[id]).collect()
+    }
+
+    /// Lines the conversation stops on by accident: no responses, nowhere to go on silence, and
+    /// neither a win nor marked as an `end`.
+    pub fn dead_ends(&self) -> Vec<NodeId> {
+        (0..self.nodes.len())
+            .filter(|&id| {
+                let node = &self.nodes[id];
+                node.responses.is_empty() && node.silence.is_empty() && !node.win && !node.end
+            })
+            .collect()
+    }
+
+    /// Every node the NPC may continue with after `id`, through a response or silence.
+    fn successors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        let node = &self.nodes[id];
+        node.responses
+            .iter()
+            .flat_map(|r| &r.next)
+            .chain(&node.silence)
+            .map(|c| c.target)
+    }
+}
+
+fn check_timer(node_id: &str, node: &NodeRon) -> Result<(), GraphError> {
+    let timeouts = node.responses.iter().filter(|r| r.timeout).count();
+    let valid = match node.timer {
+        Some(secs) => secs.is_finite() && secs > 0.0 && timeouts <= 1,
+        None => timeouts == 0 && node.silence.is_empty(),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(GraphError::BadTimer(node_id.to_string()))
+    }
+}
+
+fn check_markup(node: &str, text: &str) -> Result<(), GraphError> {
+    Markup::parse(text).map(|_| ()).map_err(|error| GraphError::Markup {
+        node: node.to_string(),
+        error,
+    })
+}
+
+impl NodeDef {
+    /// The response to choose when the timer runs out, if it is currently offered.
+    pub fn timeout_response(&self, ctx: &DialogContext) -> Option<usize> {
+        self.responses
+            .iter()
+            .position(|r| r.timeout)
+            .filter(|&i| self.responses[i].is_visible(ctx))
+    }
+
+    /// The NPC's next line after the player stays silent. `None` ends the conversation.
+    pub fn choose_silence(&self, ctx: &DialogContext, rng: &mut impl Rng) -> Option<NodeId> {
+        choose(&self.silence, ctx, rng)
+    }
+
+    /// Indices of the responses whose condition currently holds.
+    pub fn visible_responses(&self, ctx: &DialogContext) -> Vec<usize> {
+        (0..self.responses.len())
+            .filter(|&i| self.responses[i].is_visible(ctx))
+            .collect()
+    }
+}
+
+impl ResponseDef {
+    pub fn is_visible(&self, ctx: &DialogContext) -> bool {
+        self.condition.as_ref().is_none_or(|c| c.holds(ctx))
+    }
+
+    /// Pick the NPC's next line: a weighted random choice among continuations whose
+    /// condition holds. `None` ends the conversation.
+    pub fn choose(&self, ctx: &DialogContext, rng: &mut impl Rng) -> Option<NodeId> {
+        choose(&self.next, ctx, rng)
+    }
+}
+
+fn choose(next: &[ContinuationDef], ctx: &DialogContext, rng: &mut impl Rng) -> Option<NodeId> {
+    let eligible: Vec<&ContinuationDef> = next
+        .iter()
+        .filter(|c| c.weight > 0.0 && c.condition.as_ref().is_none_or(|cond| cond.holds(ctx)))
+        .collect();
+    let total: f32 = eligible.iter().map(|c| c.weight).sum();
+    if eligible.is_empty() {
+        return None;
+    }
+
+    let mut roll = rng.random_range(0.0..total);
+    for c in &eligible {
+        if roll < c.weight {
+            return Some(c.target);
+        }
+        roll -= c.weight;
+    }
+    // Rounding can leave the roll a hair past the last weight
+    eligible.last().map(|c| c.target)
+}
+
+fn qualify(tree: &str, node: &str) -> String {
+    format!("{tree}{TREE_SEPARATOR}{node}")
+}
+
+fn resolve_all(
+    index: &HashMap<String, NodeId>,
+    tree: &str,
+    from: &str,
+    next: Vec<ContinuationRon>,
+) -> Result<Vec<ContinuationDef>, GraphError> {
+    next.into_iter()
+        .map(|c| {
+            let target = resolve(index, tree, &c.goto).ok_or_else(|| GraphError::UnknownTarget {
+                from: from.to_string(),
+                goto: c.goto.clone(),
+            })?;
+            if !c.weight.is_finite() || c.weight < 0.0 {
+                return Err(GraphError::BadWeight {
+                    from: from.to_string(),
+                    goto: c.goto,
+                    weight: c.weight,
+                });
+            }
+            Ok(ContinuationDef {
+                target,
+                weight: c.weight,
+                condition: c.when,
+            })
+        })
+        .collect()
+}
+
+fn resolve(index: &HashMap<String, NodeId>, tree: &str, goto: &str) -> Option<NodeId> {
+    if goto.contains(TREE_SEPARATOR) {
+        index.get(goto).copied()
+    } else {
+        index.get(&qualify(tree, goto)).copied()
+    }
+}
+
+/// Flatten nested legacy trees into graph trees. Legacy trees have no IDs, so trees and lines
+/// are numbered in the order they appear.
+fn convert_legacy(legacy: DialogueTrees) -> DialogGraphRon {
+    fn convert_npc(node: &DialogueNode, nodes: &mut Vec<NodeRon>) -> String {
+        let id = format!("line_{}", nodes.len());
+        let slot = nodes.len();
+        nodes.push(NodeRon {
+            id: id.clone(),
+            text: node.text.clone(),
+            responses: Vec::new(),
+            win: node.win,
+            // Legacy lines without responses were where conversations finished
+            end: !node.win && !node.responses.iter().any(|r| r.role == Role::Player),
+            effects: Vec::new(),
+            timer: None,
+            silence: Vec::new(),
+        });
+        let responses = node
+            .responses
+            .iter()
+            .filter(|r| r.role == Role::Player)
+            .enumerate()
+            .map(|(i, player)| ResponseRon {
+                // Continuations are fresh lines, so only responses that end could clash
+                id: player
+                    .responses
+                    .iter()
+                    .all(|r| r.role != Role::Npc)
+                    .then(|| format!("{END_RESPONSE}_{i}")),
+                text: player.text.clone(),
+                next: player
+                    .responses
+                    .iter()
+                    .filter(|r| r.role == Role::Npc)
+                    .map(|npc| ContinuationRon {
+                        goto: convert_npc(npc, nodes),
+                        weight: 1.0,
+                        when: None,
+                    })
+                    .collect(),
+                win: player.win,
+                when: None,
+                timeout: false,
+            })
+            .collect();
+        nodes[slot].responses = responses;
+        id
+    }
+
+    let trees = legacy
+        .0
+        .iter()
+        .enumerate()
+        .map(|(i, root)| {
+            let mut nodes = Vec::new();
+            convert_npc(root, &mut nodes);
+            TreeRon {
+                id: format!("tree_{i}"),
+                nodes,
+            }
+        })
+        .collect();
+    DialogGraphRon { trees }
+}
+
+/// Convert a legacy `DialogueTrees` file into the graph format, as RON text.
+pub fn migrate_legacy(source: &str) -> Result<String, GraphError> {
+    let legacy: DialogueTrees = ron::from_str(source).map_err(GraphError::Parse)?;
+    let config = ron::ser::PrettyConfig::default().struct_names(false);
+    Ok(ron::ser::to_string_pretty(&convert_legacy(legacy), config)
+        .expect("dialog graph serializes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialog::condition::DialogVars;
+    use crate::dialog::effect::Sound;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const GRAPH: &str = r#"
+        #![enable(implicit_some)]
+        (
+            trees: [
+                (
+                    id: "greeting",
+                    nodes: [
+                        (
+                            id: "start",
+                            text: "Hello?",
+                            responses: [
+                                (text: "Again.", next: [(goto: "start")]),
+                                (text: "Tell me more.", next: [(goto: "other/middle")]),
+                                (
+                                    text: "How are you?",
+                                    next: [
+                                        (goto: "fine", when: SanityAbove(0.5)),
+                                        (goto: "bad", when: SanityBelow(0.5)),
+                                    ],
+                                ),
+                                (text: "Bye.", win: true),
+                            ],
+                        ),
+                        (id: "fine", text: "Fine."),
+                        (id: "bad", text: "Bad.", win: true),
+                    ],
+                ),
+                (
+                    id: "other",
+                    nodes: [
+                        (id: "intro", text: "Elsewhere."),
+                        (id: "middle", text: "More.", responses: [(text: "Back.", next: [(goto: "greeting/start")])]),
+                    ],
+                ),
+            ],
+        )
+    "#;
+
+    const LEGACY: &str = r#"
+        DialogueTrees([
+            DialogueNode(
+                role: Npc,
+                text: "Hi.",
+                responses: [
+                    DialogueNode(
+                        role: Player,
+                        text: "Hello.",
+                        responses: [DialogueNode(role: Npc, text: "Bye.", win: true, responses: [])],
+                    ),
+                    DialogueNode(role: Player, text: "...", win: true, responses: []),
+                ],
+            ),
+        ])
+    "#;
+
+    fn graph() -> DialogGraph {
+        DialogGraph::from_ron(GRAPH).expect("test graph is valid")
+    }
+
+    fn choose(graph: &DialogGraph, node: &str, response: usize, sanity: f32) -> Option<String> {
+        let node = graph.node(graph.find(node).unwrap());
+        let mut rng = StdRng::seed_from_u64(7);
+        node.responses[response]
+            .choose(
+                &DialogContext {
+                    sanity,
+                    ..DialogContext::with_vars(&DialogVars::default())
+                },
+                &mut rng,
+            )
+            .map(|id| graph.node(id).id.clone())
+    }
+
+    #[test]
+    fn gotos_resolve_within_and_across_trees() {
+        let graph = graph();
+        assert_eq!(graph.tree_start("other"), graph.find("other/intro"));
+        assert_eq!(choose(&graph, "greeting/start", 0, 1.0).as_deref(), Some("greeting/start"));
+        assert_eq!(choose(&graph, "greeting/start", 1, 1.0).as_deref(), Some("other/middle"));
+        assert_eq!(choose(&graph, "other/middle", 0, 1.0).as_deref(), Some("greeting/start"));
+        assert_eq!(choose(&graph, "greeting/start", 3, 1.0), None);
+    }
+
+    #[test]
+    fn conditions_pick_the_continuation() {
+        let graph = graph();
+        assert_eq!(choose(&graph, "greeting/start", 2, 0.9).as_deref(), Some("greeting/fine"));
+        assert_eq!(choose(&graph, "greeting/start", 2, 0.1).as_deref(), Some("greeting/bad"));
+    }
+
+    #[test]
+    fn weights_bias_the_choice() {
+        let response = ResponseDef {
+            id: String::new(),
+            text: String::new(),
+            next: vec![
+                ContinuationDef {
+                    target: 0,
+                    weight: 3.0,
+                    condition: None,
+                },
+                ContinuationDef {
+                    target: 1,
+                    weight: 1.0,
+                    condition: None,
+                },
+                ContinuationDef {
+                    target: 2,
+                    weight: 0.0,
+                    condition: None,
+                },
+            ],
+            win: false,
+            condition: None,
+            timeout: false,
+        };
+        let vars = DialogVars::default();
+        let ctx = DialogContext::with_vars(&vars);
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[response.choose(&ctx, &mut rng).unwrap()] += 1;
+        }
+        assert_eq!(counts[2], 0);
+        let ratio = counts[0] as f32 / counts[1] as f32;
+        assert!((2.5..3.5).contains(&ratio), "{counts:?}");
+    }
+
+    #[test]
+    fn responses_and_effects_parse() {
+        let source = r#"
+            #![enable(implicit_some)]
+            (trees: [(id: "a", nodes: [(
+                id: "x",
+                text: "",
+                effects: [SetFlag("met"), Heal(0.1), PlaySound(Talk)],
+                responses: [(id: "again", text: "Again", when: Flag("met")), (text: "Hi")],
+            )])])
+        "#;
+        let graph = DialogGraph::from_ron(source).unwrap();
+        let node = graph.node(0);
+        assert_eq!(node.effects[2], Effect::PlaySound(Sound::Talk));
+
+        let mut vars = DialogVars::default();
+        assert_eq!(node.visible_responses(&DialogContext::with_vars(&vars)), vec![1]);
+        vars.set_flag("met", true);
+        assert_eq!(node.visible_responses(&DialogContext::with_vars(&vars)), vec![0, 1]);
+    }
+
+    #[test]
+    fn unknown_targets_are_rejected() {
+        let source = r#"(trees: [(id: "a", nodes: [(id: "x", text: "", responses: [(text: "", next: [(goto: "y")])])])])"#;
+        assert!(matches!(
+            DialogGraph::from_ron(source),
+            Err(GraphError::UnknownTarget { .. })
+        ));
+    }
+
+    #[test]
+    fn infinite_weights_are_rejected() {
+        let source = r#"(trees: [(id: "a", nodes: [(id: "x", text: "", responses: [(text: "", next: [(goto: "x", weight: inf)])])])])"#;
+        assert!(matches!(
+            DialogGraph::from_ron(source),
+            Err(GraphError::BadWeight { .. })
+        ));
+    }
+
+    #[test]
+    fn negative_weights_are_rejected() {
+        let source = r#"(trees: [(id: "a", nodes: [(id: "x", text: "", silence: [(goto: "x", weight: -1.0)], timer: Some(1.0))])])"#;
+        assert!(matches!(
+            DialogGraph::from_ron(source),
+            Err(GraphError::BadWeight { .. })
+        ));
+    }
+
+    #[test]
+    fn responses_are_keyed_by_id_or_target() {
+        let node = |responses: &str| {
+            format!(r#"(trees: [(id: "a", nodes: [(id: "x", text: "", responses: [{responses}])])])"#)
+        };
+        let graph = DialogGraph::from_ron(&node(
+            r#"(text: "", next: [(goto: "x")]), (text: ""), (id: Some("again"), text: "", next: [(goto: "x")])"#,
+        ))
+        .unwrap();
+        let ids: Vec<&str> = graph.node(0).responses.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["x", "end", "again"]);
+
+        assert!(matches!(
+            DialogGraph::from_ron(&node(r#"(text: "Bye."), (text: "Later.")"#)),
+            Err(GraphError::DuplicateResponse { .. })
+        ));
+    }
+
+    #[test]
+    fn timers_are_validated() {
+        let node = |fields: &str| format!(r#"(trees: [(id: "a", nodes: [(id: "x", text: "", {fields})])])"#);
+        let graph = DialogGraph::from_ron(&node(
+            r#"timer: Some(2.0), silence: [(goto: "x")], responses: [(text: "", timeout: true)]"#,
+        ))
+        .unwrap();
+        assert_eq!(graph.node(0).silence[0].target, 0);
+
+        for bad in [
+            "timer: Some(0.0)",
+            r#"responses: [(text: "", timeout: true)]"#,
+            r#"silence: [(goto: "x")]"#,
+            r#"timer: Some(1.0), responses: [(text: "", timeout: true), (text: "", timeout: true)]"#,
+        ] {
+            assert!(
+                matches!(DialogGraph::from_ron(&node(bad)), Err(GraphError::BadTimer(_))),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn unreachable_nodes_and_dead_ends_are_found() {
+        let source = r#"(trees: [
+            (id: "a", nodes: [
+                (id: "start", text: "", timer: Some(1.0), silence: [(goto: "quiet")], responses: [
+                    (text: "", next: [(goto: "loop")]),
+                    (text: "", next: [(goto: "b/start")]),
+                    (text: "", next: [(goto: "bye")]),
+                ]),
+                (id: "loop", text: "", responses: [(text: "", next: [(goto: "start")])]),
+                (id: "quiet", text: ""),
+                (id: "bye", text: "", end: true),
+                (id: "orphan", text: "", win: true),
+            ]),
+            (id: "b", nodes: [(id: "start", text: "", responses: [(text: "", win: true)])]),
+        ])"#;
+        let graph = DialogGraph::from_ron(source).unwrap();
+        let ids = |nodes: Vec<NodeId>| -> Vec<String> {
+            nodes.into_iter().map(|id| graph.node(id).id.clone()).collect()
+        };
+        assert_eq!(ids(graph.unreachable_nodes()), vec!["a/orphan"]);
+        // Lines meant to end the conversation, or with a way on, aren't dead ends
+        assert_eq!(ids(graph.dead_ends()), vec!["a/quiet"]);
+    }
+
+    #[test]
+    fn legacy_trees_load_and_migrate() {
+        let graph = DialogGraph::from_ron(LEGACY).expect("legacy file converts");
+        assert!(graph.dead_ends().is_empty(), "legacy leaves are intended endings");
+        let start = graph.node(graph.tree_start("tree_0").unwrap());
+        assert_eq!(start.text, "Hi.");
+        assert_eq!(start.responses.len(), 2);
+        assert!(start.responses[1].win);
+        let bye = graph.node(start.responses[0].next[0].target);
+        assert_eq!((bye.text.as_str(), bye.win), ("Bye.", true));
+
+        // The migrated text is itself a valid graph with the same shape
+        let migrated = migrate_legacy(LEGACY).unwrap();
+        let reloaded = DialogGraph::from_ron(&migrated).expect("migrated file parses");
+        assert_eq!(reloaded.nodes().len(), graph.nodes().len());
+        assert_eq!(reloaded.node(1).id, "tree_0/line_1");
+    }
+
+    #[test]
+    fn shipped_dialog_parses() {
+        let graph = DialogGraph::from_ron(super::super::DIALOG_RON).expect("dialog.ron is valid");
+        assert!(!graph.trees().is_empty());
+    }
+}
@@ -0,0 +1,109 @@
+//! What NPCs remember of past conversations, and the log of lines the player can reread.
+
+use super::markup::Markup;
+use super::{DialogEnded, DialogNodeEntered, DialogTrees, NpcIdentity, line_text, npc_name};
+use crate::locale::Locale;
+use bevy::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+/// Oldest lines are dropped from the log past this many entries.
+const LOG_CAPACITY: usize = 200;
+/// Locale key of the speaker shown for NPCs without a name.
+pub const UNKNOWN_SPEAKER: &str = "log.unknown";
+/// Locale key of the speaker shown for the player's own lines.
+pub const PLAYER_SPEAKER: &str = "log.you";
+/// Logged for the player when a timed line goes unanswered.
+pub const SILENCE: &str = "...";
+
+/// An NPC's memory of talking to the player, kept on the NPC entity.
+#[derive(Component, Debug, Clone, Default)]
+pub struct NpcMemory {
+    /// Qualified `tree/node` IDs of every line this NPC has said to the player.
+    pub visited: HashSet<String>,
+    /// The last line reached in the most recent conversation.
+    pub last_node: Option<String>,
+    /// Conversations finished with this NPC.
+    pub times_spoken: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub speaker: String,
+    pub text: String,
+}
+
+/// Lines spoken this run, oldest first.
+#[derive(Resource, Debug, Default)]
+pub struct DialogLog(VecDeque<LogEntry>);
+
+impl DialogLog {
+    pub fn push(&mut self, speaker: &str, text: &str) {
+        if self.0.len() == LOG_CAPACITY {
+            self.0.pop_front();
+        }
+        self.0.push_back(LogEntry {
+            speaker: speaker.to_string(),
+            text: text.to_string(),
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+pub(super) fn remember_dialog(
+    mut commands: Commands,
+    mut entered: MessageReader<DialogNodeEntered>,
+    mut ended: MessageReader<DialogEnded>,
+    trees: Res<DialogTrees>,
+    mut memory_q: Query<&mut NpcMemory>,
+) {
+    for event in entered.read() {
+        let Some(npc) = event.npc else {
+            continue;
+        };
+        let id = &trees.0.node(event.node).id;
+        match memory_q.get_mut(npc) {
+            Ok(mut memory) => {
+                memory.visited.insert(id.clone());
+                memory.last_node = Some(id.clone());
+            }
+            Err(_) => {
+                commands.entity(npc).insert(NpcMemory {
+                    visited: HashSet::from([id.clone()]),
+                    last_node: Some(id.clone()),
+                    times_spoken: 0,
+                });
+            }
+        }
+    }
+    // NPCs won over are already gone and have nothing left to remember
+    for event in ended.read() {
+        if let Some(mut memory) = event.npc.and_then(|npc| memory_q.get_mut(npc).ok()) {
+            memory.times_spoken += 1;
+        }
+    }
+}
+
+pub(super) fn log_npc_lines(
+    mut entered: MessageReader<DialogNodeEntered>,
+    trees: Res<DialogTrees>,
+    identity_q: Query<&NpcIdentity>,
+    locale: Res<Locale>,
+    mut log: ResMut<DialogLog>,
+) {
+    for event in entered.read() {
+        let speaker = event
+            .npc
+            .and_then(|npc| identity_q.get(npc).ok())
+            .and_then(|identity| npc_name(identity, &locale))
+            .unwrap_or_else(|| locale.get(UNKNOWN_SPEAKER));
+        let line = Markup::parse_or_plain(line_text(trees.0.node(event.node), &locale));
+        log.push(speaker, &line.to_plain_string());
+    }
+}
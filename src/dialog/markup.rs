@@ -0,0 +1,316 @@
+//! Inline markup for dialog text.
+//!
+//! Tags wrap the text they style and nest; `[[` is a literal `[`.
+//!
+//! - `[color=yellow]...[/color]`: one of the palette highlight colours, which the palette
+//!   squeeze passes through unquantized (`pink`, `yellow`, `green`, `cyan`, `red`).
+//! - `[shake]...[/shake]`, `[wave]...[/wave]`: per-character motion.
+//! - `[speed=0.5]...[/speed]`: typing speed multiplier.
+//! - `[blip]...[/blip]`: play the speaker's voice as each character is typed.
+//! - `[pause=0.6]`: wait this many seconds before typing on.
+
+use bevy::prelude::*;
+use std::fmt;
+
+/// Colours the palette squeeze shader leaves as they are, as long as the palette in use keeps the
+/// default highlights. Text in any other colour is quantized to the palette's ramp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Pink,
+    Yellow,
+    Green,
+    Cyan,
+    Red,
+}
+
+impl Highlight {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pink" => Some(Self::Pink),
+            "yellow" => Some(Self::Yellow),
+            "green" => Some(Self::Green),
+            "cyan" => Some(Self::Cyan),
+            "red" => Some(Self::Red),
+            _ => None,
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Self::Pink => Color::linear_rgb(1.0, 0.0, 0.929),
+            Self::Yellow => Color::linear_rgb(1.0, 1.0, 0.0),
+            Self::Green => Color::linear_rgb(0.196, 1.0, 0.0),
+            Self::Cyan => Color::linear_rgb(0.0, 0.906, 1.0),
+            Self::Red => Color::linear_rgb(1.0, 0.263, 0.235),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Motion {
+    #[default]
+    Still,
+    Shake,
+    Wave,
+}
+
+/// A run of text typed with one style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    pub color: Option<Highlight>,
+    pub motion: Motion,
+    /// Multiplier on the base typing speed.
+    pub speed: f32,
+    pub blip: bool,
+    /// Seconds to wait before typing this segment.
+    pub pause: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Markup {
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupError {
+    UnknownTag(String),
+    BadValue { tag: String, value: String },
+    /// A closing tag that doesn't match the innermost open one.
+    UnexpectedClose(String),
+    Unclosed(String),
+    UnterminatedTag,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkupError::UnknownTag(tag) => write!(f, "unknown tag `[{tag}]`"),
+            MarkupError::BadValue { tag, value } => write!(f, "bad value `{value}` for `[{tag}]`"),
+            MarkupError::UnexpectedClose(tag) => write!(f, "`[/{tag}]` doesn't close the open tag"),
+            MarkupError::Unclosed(tag) => write!(f, "`[{tag}]` is never closed"),
+            MarkupError::UnterminatedTag => write!(f, "tag is missing its `]`"),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+/// Style in effect at a point in the text.
+#[derive(Clone, Copy, PartialEq)]
+struct Style {
+    color: Option<Highlight>,
+    motion: Motion,
+    speed: f32,
+    blip: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            color: None,
+            motion: Motion::Still,
+            speed: 1.0,
+            blip: false,
+        }
+    }
+}
+
+impl Markup {
+    pub fn parse(source: &str) -> Result<Self, MarkupError> {
+        let mut segments: Vec<Segment> = Vec::new();
+        // Open tag names, each with the style from before it opened
+        let mut stack: Vec<(String, Style)> = Vec::new();
+        let mut style = Style::default();
+        let mut pause = 0.0;
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '[' {
+                push_char(&mut segments, style, &mut pause, c);
+                continue;
+            }
+            if chars.peek() == Some(&'[') {
+                chars.next();
+                push_char(&mut segments, style, &mut pause, '[');
+                continue;
+            }
+
+            let mut tag = String::new();
+            loop {
+                match chars.next() {
+                    Some(']') => break,
+                    Some(c) => tag.push(c),
+                    None => return Err(MarkupError::UnterminatedTag),
+                }
+            }
+
+            if let Some(name) = tag.strip_prefix('/') {
+                match stack.pop() {
+                    Some((open, before)) if open == name => style = before,
+                    _ => return Err(MarkupError::UnexpectedClose(name.to_string())),
+                }
+                continue;
+            }
+
+            let (name, value) = match tag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (tag.as_str(), None),
+            };
+            let bad_value = || MarkupError::BadValue {
+                tag: name.to_string(),
+                value: value.unwrap_or_default().to_string(),
+            };
+            let number = || {
+                value
+                    .and_then(|v| v.parse::<f32>().ok())
+                    .filter(|v| v.is_finite() && *v >= 0.0)
+                    .ok_or_else(bad_value)
+            };
+
+            if name == "pause" {
+                pause += number()?;
+                continue;
+            }
+
+            let before = style;
+            match name {
+                "color" => {
+                    style.color = Some(value.and_then(Highlight::from_name).ok_or_else(bad_value)?)
+                }
+                "shake" => style.motion = Motion::Shake,
+                "wave" => style.motion = Motion::Wave,
+                "blip" => style.blip = true,
+                "speed" => {
+                    let speed = number()?;
+                    if speed == 0.0 {
+                        return Err(bad_value());
+                    }
+                    style.speed = speed;
+                }
+                _ => return Err(MarkupError::UnknownTag(name.to_string())),
+            }
+            stack.push((name.to_string(), before));
+        }
+
+        if let Some((open, _)) = stack.pop() {
+            return Err(MarkupError::Unclosed(open));
+        }
+        // A trailing pause still holds the line open before it counts as finished
+        if pause > 0.0 {
+            segments.push(segment(Style::default(), pause));
+        }
+        Ok(Self { segments })
+    }
+
+    /// Markup for text that should be shown as written.
+    pub fn plain(text: &str) -> Self {
+        Self {
+            segments: vec![Segment {
+                text: text.to_string(),
+                ..segment(Style::default(), 0.0)
+            }],
+        }
+    }
+
+    /// Parse `source`, falling back to showing it verbatim if the markup is broken.
+    pub fn parse_or_plain(source: &str) -> Self {
+        Self::parse(source).unwrap_or_else(|e| {
+            warn!("Bad dialog markup in {source:?}: {e}");
+            Self::plain(source)
+        })
+    }
+
+    /// The text without any markup.
+    pub fn to_plain_string(&self) -> String {
+        self.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+}
+
+fn segment(style: Style, pause: f32) -> Segment {
+    Segment {
+        text: String::new(),
+        color: style.color,
+        motion: style.motion,
+        speed: style.speed,
+        blip: style.blip,
+        pause,
+    }
+}
+
+/// Append a character, starting a new segment when the style changes or a pause comes first.
+fn push_char(segments: &mut Vec<Segment>, style: Style, pause: &mut f32, c: char) {
+    let continues = *pause == 0.0
+        && segments.last().is_some_and(|last| {
+            last.color == style.color
+                && last.motion == style.motion
+                && last.speed == style.speed
+                && last.blip == style.blip
+        });
+    if !continues {
+        segments.push(segment(style, *pause));
+        *pause = 0.0;
+    }
+    segments.last_mut().unwrap().text.push(c);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_is_one_segment() {
+        let markup = Markup::parse("Do you see me?").unwrap();
+        assert_eq!(markup, Markup::plain("Do you see me?"));
+    }
+
+    #[test]
+    fn tags_split_styled_segments_and_nest() {
+        let markup = Markup::parse("I [color=yellow]see [wave]you[/wave][/color] [[now]").unwrap();
+        let parts: Vec<(&str, Option<Highlight>, Motion)> = markup
+            .segments
+            .iter()
+            .map(|s| (s.text.as_str(), s.color, s.motion))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("I ", None, Motion::Still),
+                ("see ", Some(Highlight::Yellow), Motion::Still),
+                ("you", Some(Highlight::Yellow), Motion::Wave),
+                (" [now]", None, Motion::Still),
+            ]
+        );
+        assert_eq!(markup.to_plain_string(), "I see you [now]");
+    }
+
+    #[test]
+    fn pauses_and_speed_attach_to_the_following_text() {
+        let markup = Markup::parse("Wait.[pause=0.5] [speed=0.25][blip]Now.[/blip][/speed][pause=1]").unwrap();
+        let s = &markup.segments;
+        assert_eq!(s.len(), 4);
+        assert_eq!((s[1].text.as_str(), s[1].pause), (" ", 0.5));
+        assert_eq!((s[2].text.as_str(), s[2].speed, s[2].blip), ("Now.", 0.25, true));
+        assert_eq!((s[3].text.as_str(), s[3].pause), ("", 1.0));
+    }
+
+    #[test]
+    fn malformed_markup_is_rejected() {
+        assert_eq!(
+            Markup::parse("[shout]hi[/shout]"),
+            Err(MarkupError::UnknownTag("shout".into()))
+        );
+        assert!(matches!(
+            Markup::parse("[color=purple]x[/color]"),
+            Err(MarkupError::BadValue { .. })
+        ));
+        assert!(matches!(Markup::parse("[speed=0]x[/speed]"), Err(MarkupError::BadValue { .. })));
+        assert_eq!(
+            Markup::parse("[shake][wave]x[/shake][/wave]"),
+            Err(MarkupError::UnexpectedClose("shake".into()))
+        );
+        assert_eq!(Markup::parse("[shake]x"), Err(MarkupError::Unclosed("shake".into())));
+        assert_eq!(Markup::parse("x[pause"), Err(MarkupError::UnterminatedTag));
+        assert_eq!(Markup::parse_or_plain("[shake]x"), Markup::plain("[shake]x"));
+    }
+}
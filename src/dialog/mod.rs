@@ -0,0 +1,1607 @@
+use crate::GameState;
+use crate::aberration::SpawnAberration;
+use crate::dispel::Dispelled;
+use crate::environment::{Environment, SwitchEnvironment};
+use crate::health::Health;
+use crate::loading::{AudioAssets, FontAssets, TextureAssets};
+use crate::locale::Locale;
+use crate::pause::game_not_paused;
+use crate::player::Player;
+use bevy::ecs::system::SystemParam;
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+use bevy::text::FontSmoothing;
+use bevy::ui::UiSystems;
+use bevy::window::{CursorGrabMode, CursorOptions, PrimaryWindow};
+use bevy_kira_audio::{Audio, AudioControl, AudioSource};
+use rand::Rng;
+use std::collections::HashSet;
+
+pub mod bark;
+pub mod condition;
+pub mod effect;
+pub mod graph;
+pub mod history;
+pub mod markup;
+pub mod npc;
+pub mod typewriter;
+
+pub use npc::{Npc, NpcIdentity, NpcIdentityRon};
+use npc::Portrait;
+
+use condition::{DialogContext, DialogVars};
+use effect::Effect;
+use graph::{DialogGraph, NodeDef, NodeId};
+use history::{DialogLog, NpcMemory, PLAYER_SPEAKER, SILENCE};
+use markup::Markup;
+use typewriter::{Typewriter, TypewriterFinished, insert_markup};
+
+pub struct DialogPlugin;
+
+impl Plugin for DialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<DialogNodeEntered>()
+            .add_message::<ResponseChosen>()
+            .add_message::<TypewriterFinished>()
+            .add_message::<DialogEnded>()
+            .add_systems(OnEnter(GameState::Playing), (init_dialog, bark::init_barks))
+            .add_systems(
+                Update,
+                (
+                    warn_unknown_trees,
+                    check_npc_proximity,
+                    handle_dialog_input,
+                    release_cursor_on_mouse_move,
+                    navigate_responses,
+                    handle_response_click,
+                    apply_node_effects,
+                    history::remember_dialog,
+                    history::log_npc_lines,
+                    count_dispels,
+                    typewriter::type_text,
+                    track_animation_finished,
+                    manage_dialog_ui,
+                    show_loaded_portraits,
+                    manage_prompt_ui,
+                    response_button_hover,
+                    animate_response_buttons,
+                    shrink_response_timers,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).and(game_not_paused)),
+            )
+            .add_systems(
+                Update,
+                (bark::start_barks, bark::update_barks, bark::place_bark_labels)
+                    .chain()
+                    .after(handle_dialog_input)
+                    .run_if(in_state(GameState::Playing).and(game_not_paused)),
+            )
+            .add_systems(
+                PostUpdate,
+                typewriter::animate_glyphs
+                    .after(UiSystems::PostLayout)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), (cleanup_dialog, bark::cleanup_barks));
+    }
+}
+
+pub(crate) const DIALOG_RON: &str = include_str!("../../assets/defs/dialog.ron");
+/// Base typing speed, in characters per second.
+const TEXT_SPEED: f32 = 20.0;
+const TEXT_SIZE: f32 = 32.0;
+
+// --- Resources ---
+
+#[derive(Resource)]
+struct DialogTrees(DialogGraph);
+
+#[derive(Resource, Default)]
+pub struct DialogState {
+    pub active: bool,
+    /// The current NPC node being displayed.
+    current_node: Option<NodeId>,
+    /// A winning response was chosen; the NPC is despawned when the dialog closes.
+    won: bool,
+    anim_done: bool,
+    dirty: bool,
+    /// Whether response buttons are currently visible.
+    responses_shown: bool,
+    /// Time left to answer a timed line, running while its responses are shown.
+    timer: Option<Timer>,
+    /// Response index under keyboard, gamepad or mouse focus.
+    selected: Option<usize>,
+    /// The NPC entity this dialog is with.
+    npc_entity: Option<Entity>,
+}
+
+/// Sent whenever an NPC line is shown, including the first line of a conversation.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct DialogNodeEntered {
+    pub node: NodeId,
+    pub npc: Option<Entity>,
+}
+
+/// A response picked without clicking it: by number key, or confirming the selection.
+#[derive(Message, Debug, Clone, Copy)]
+struct ResponseChosen(usize);
+
+/// Sent when a conversation closes, after a winning NPC has been despawned.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct DialogEnded {
+    pub npc: Option<Entity>,
+    pub won: bool,
+}
+
+/// The game state dialog conditions are checked against.
+#[derive(SystemParam)]
+struct ConditionState<'w, 's> {
+    health: Option<Res<'w, Health>>,
+    environment: Option<Res<'w, State<Environment>>>,
+    vars: Res<'w, DialogVars>,
+    memory_q: Query<'w, 's, &'static NpcMemory>,
+}
+
+impl ConditionState<'_, '_> {
+    /// Conditions as seen while talking to `npc`.
+    fn context(&self, npc: Option<Entity>) -> DialogContext<'_> {
+        DialogContext {
+            sanity: self.health.as_ref().map_or(1.0, |h| h.fraction()),
+            environment: self.environment.as_ref().map(|e| e.get().clone()),
+            vars: &self.vars,
+            npc: npc.and_then(|e| self.memory_q.get(e).ok()),
+        }
+    }
+}
+
+/// Run condition: returns true when no dialog is active.
+pub fn dialog_not_active(state: Option<Res<DialogState>>) -> bool {
+    state.is_none_or(|s| !s.active)
+}
+
+/// Tracks the nearest NPC within interaction range.
+#[derive(Resource, Default)]
+struct NearbyNpc(Option<Entity>);
+
+#[derive(Component)]
+struct DialogUi;
+
+#[derive(Component)]
+struct DialogText;
+
+#[derive(Component)]
+struct NamePlate;
+
+/// A portrait whose sheet was still loading when the dialog box opened, hidden until its first
+/// frame can be cut out of it.
+#[derive(Component)]
+struct PendingPortrait(Portrait);
+
+#[derive(Component)]
+struct ResponseContainer;
+
+/// Index into the current node's `responses` array.
+#[derive(Component)]
+struct ResponseButton(usize);
+
+/// Animates a response button from 0 to full height over RESPONSE_ANIM_SECS.
+#[derive(Component)]
+struct ResponseButtonAnim {
+    timer: Timer,
+}
+
+const RESPONSE_ANIM_SECS: f32 = 1.0;
+
+/// Bar along the bottom of a response button showing the time left to answer.
+#[derive(Component)]
+struct ResponseTimerBar;
+
+const TIMER_BAR_HEIGHT: f32 = 3.0;
+
+/// Tint of the selected response button.
+const SELECTED_COLOR: Color = Color::linear_rgb(0.6, 0.6, 0.6);
+
+/// Keys choosing the first nine responses, in the order they are shown.
+const RESPONSE_KEYS: [[KeyCode; 2]; 9] = [
+    [KeyCode::Digit1, KeyCode::Numpad1],
+    [KeyCode::Digit2, KeyCode::Numpad2],
+    [KeyCode::Digit3, KeyCode::Numpad3],
+    [KeyCode::Digit4, KeyCode::Numpad4],
+    [KeyCode::Digit5, KeyCode::Numpad5],
+    [KeyCode::Digit6, KeyCode::Numpad6],
+    [KeyCode::Digit7, KeyCode::Numpad7],
+    [KeyCode::Digit8, KeyCode::Numpad8],
+    [KeyCode::Digit9, KeyCode::Numpad9],
+];
+
+#[derive(Component)]
+struct PromptUi;
+
+fn init_dialog(mut commands: Commands) {
+    let graph = DialogGraph::from_ron(DIALOG_RON).expect("Failed to parse dialog.ron");
+    commands.insert_resource(DialogTrees(graph));
+    commands.insert_resource(DialogState::default());
+    commands.insert_resource(NearbyNpc::default());
+    commands.insert_resource(DialogVars::default());
+    commands.insert_resource(DialogLog::default());
+}
+
+/// Report NPCs naming dialog trees that don't exist, once per tree.
+fn warn_unknown_trees(
+    trees: Res<DialogTrees>,
+    identity_q: Query<&NpcIdentity, Added<NpcIdentity>>,
+    mut warned: Local<HashSet<String>>,
+) {
+    for tree in identity_q.iter().flat_map(|i| i.dialog.iter().chain(&i.returning)) {
+        if trees.0.tree_start(tree).is_none() && warned.insert(tree.clone()) {
+            warn!("NPC dialog tree `{tree}` does not exist");
+        }
+    }
+}
+
+fn check_npc_proximity(
+    player_q: Query<&GlobalTransform, With<Player>>,
+    npc_q: Query<(Entity, &GlobalTransform, &Npc)>,
+    mut nearby: ResMut<NearbyNpc>,
+) {
+    let Ok(player_tf) = player_q.single() else {
+        nearby.0 = None;
+        return;
+    };
+
+    let player_pos = player_tf.translation();
+    let found = npc_q
+        .iter()
+        .filter(|(_, tf, npc)| player_pos.distance(tf.translation()) <= npc.range)
+        .min_by(|(_, a, _), (_, b, _)| {
+            let da = player_pos.distance(a.translation());
+            let db = player_pos.distance(b.translation());
+            da.partial_cmp(&db).unwrap()
+        })
+        .map(|(entity, _, _)| entity);
+
+    nearby.0 = found;
+}
+
+fn handle_dialog_input(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<DialogState>,
+    nearby: Res<NearbyNpc>,
+    trees: Res<DialogTrees>,
+    identity_q: Query<&NpcIdentity>,
+    conditions: ConditionState,
+    mut entered: MessageWriter<DialogNodeEntered>,
+    mut typewriter_q: Query<&mut Typewriter, With<DialogText>>,
+    gamepad_q: Query<&Gamepad>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+) {
+    let gamepad = |button| gamepad_q.iter().any(|g| g.just_pressed(button));
+    let e_pressed = keyboard.just_pressed(KeyCode::KeyE);
+    let esc_pressed = keyboard.just_pressed(KeyCode::Escape) || gamepad(GamepadButton::East);
+    let space_pressed = keyboard.just_pressed(KeyCode::Space) || gamepad(GamepadButton::South);
+    let left_click = mouse.just_pressed(MouseButton::Left);
+
+    if !state.active {
+        let Some(npc) = nearby.0.filter(|_| e_pressed) else {
+            return;
+        };
+        let returning = conditions.memory_q.get(npc).is_ok_and(|m| m.times_spoken > 0);
+        let identity = identity_q.get(npc).ok();
+        if let Some(start) = pick_start(&trees.0, identity, returning, &mut rand::rng()) {
+            state.active = true;
+            state.current_node = Some(start);
+            state.won = false;
+            state.anim_done = false;
+            state.dirty = true;
+            state.responses_shown = false;
+            state.npc_entity = Some(npc);
+            entered.write(DialogNodeEntered {
+                node: start,
+                npc: Some(npc),
+            });
+            // The cursor stays locked until the mouse is used, see `release_cursor_on_mouse_move`
+        }
+        return;
+    }
+
+    // Escape or E always closes dialog
+    if esc_pressed || e_pressed {
+        close_dialog(&mut commands, &mut state, &trees.0, &mut cursor_q);
+        return;
+    }
+
+    // Left click closes dialog when no responses are available
+    let has_responses = state.current_node.is_some_and(|id| {
+        !trees.0.node(id).visible_responses(&conditions.context(state.npc_entity)).is_empty()
+    });
+
+    if left_click && state.anim_done && !has_responses {
+        close_dialog(&mut commands, &mut state, &trees.0, &mut cursor_q);
+        return;
+    }
+
+    // Don't handle Space when response buttons are showing
+    if state.responses_shown {
+        return;
+    }
+
+    if !space_pressed {
+        return;
+    }
+
+    if !state.anim_done {
+        // Skip animation — show full text immediately
+        for mut typewriter in &mut typewriter_q {
+            typewriter.skip();
+        }
+        state.anim_done = true;
+        state.dirty = true;
+    } else if !has_responses {
+        close_dialog(&mut commands, &mut state, &trees.0, &mut cursor_q);
+    }
+}
+
+/// Show the cursor for clicking responses once the player moves the mouse during dialog.
+/// Keyboard and gamepad players never leave mouse-look.
+fn release_cursor_on_mouse_move(
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    state: Res<DialogState>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+) {
+    if !state.active || mouse_motion.delta == Vec2::ZERO {
+        return;
+    }
+    if let Ok(mut cursor) = cursor_q.single_mut()
+        && cursor.grab_mode != CursorGrabMode::None
+    {
+        cursor.grab_mode = CursorGrabMode::None;
+        cursor.visible = true;
+    }
+}
+
+/// Move the selection with the arrow keys or d-pad and confirm it with Enter, Space or the
+/// gamepad's south button. Number keys choose a response outright.
+fn navigate_responses(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepad_q: Query<&Gamepad>,
+    mut state: ResMut<DialogState>,
+    container_q: Query<&Children, With<ResponseContainer>>,
+    button_q: Query<&ResponseButton>,
+    mut chosen: MessageWriter<ResponseChosen>,
+) {
+    if !state.responses_shown {
+        return;
+    }
+    let shown: Vec<usize> = container_q
+        .iter()
+        .flatten()
+        .filter_map(|child| button_q.get(*child).ok())
+        .map(|button| button.0)
+        .collect();
+    if shown.is_empty() {
+        return;
+    }
+    let gamepad = |button| gamepad_q.iter().any(|g| g.just_pressed(button));
+
+    let step = if keyboard.just_pressed(KeyCode::ArrowDown) || gamepad(GamepadButton::DPadDown) {
+        1
+    } else if keyboard.just_pressed(KeyCode::ArrowUp) || gamepad(GamepadButton::DPadUp) {
+        -1
+    } else {
+        0
+    };
+    if step != 0 {
+        let position = match state.selected.and_then(|s| shown.iter().position(|&i| i == s)) {
+            Some(position) => (position as isize + step).rem_euclid(shown.len() as isize) as usize,
+            None if step > 0 => 0,
+            None => shown.len() - 1,
+        };
+        state.selected = Some(shown[position]);
+    }
+
+    let numbered = RESPONSE_KEYS
+        .iter()
+        .position(|keys| keyboard.any_just_pressed(*keys))
+        .and_then(|position| shown.get(position).copied());
+    if let Some(index) = numbered {
+        state.selected = Some(index);
+        chosen.write(ResponseChosen(index));
+    } else if let Some(index) = state.selected
+        && (keyboard.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Space])
+            || gamepad(GamepadButton::South))
+    {
+        chosen.write(ResponseChosen(index));
+    }
+}
+
+/// First line of a conversation with this NPC: the start of one of their own trees (their
+/// `returning` ones if the player has talked to them before), or of any tree if they have none.
+fn pick_start(
+    graph: &DialogGraph,
+    identity: Option<&NpcIdentity>,
+    returning: bool,
+    rng: &mut impl Rng,
+) -> Option<NodeId> {
+    let own: Vec<NodeId> = identity
+        .into_iter()
+        .flat_map(|identity| {
+            if returning && !identity.returning.is_empty() {
+                &identity.returning
+            } else {
+                &identity.dialog
+            }
+        })
+        .filter_map(|tree| graph.tree_start(tree))
+        .collect();
+    if !own.is_empty() {
+        return Some(own[rng.random_range(0..own.len())]);
+    }
+
+    let trees = graph.trees();
+    if trees.is_empty() {
+        return None;
+    }
+    Some(trees[rng.random_range(0..trees.len())].start)
+}
+
+fn handle_response_click(
+    mut commands: Commands,
+    mut state: ResMut<DialogState>,
+    trees: Res<DialogTrees>,
+    conditions: ConditionState,
+    mut entered: MessageWriter<DialogNodeEntered>,
+    mut log: ResMut<DialogLog>,
+    locale: Res<Locale>,
+    mut chosen: MessageReader<ResponseChosen>,
+    interaction_q: Query<(&Interaction, &ResponseButton), Changed<Interaction>>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    time: Res<Time>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    if !state.active {
+        return;
+    }
+    let Some(id) = state.current_node else {
+        return;
+    };
+    let node = trees.0.node(id);
+    let ctx = conditions.context(state.npc_entity);
+
+    // The response chosen, with `None` for staying silent
+    let mut answer = None;
+    for (interaction, response_btn) in &interaction_q {
+        if *interaction == Interaction::Pressed {
+            audio.play(audio_assets.fx1.clone());
+            answer = Some(Some(response_btn.0));
+        }
+    }
+    for ResponseChosen(index) in chosen.read() {
+        audio.play(audio_assets.fx1.clone());
+        answer = Some(Some(*index));
+    }
+    if answer.is_none()
+        && let Some(timer) = state.timer.as_mut()
+        && timer.tick(time.delta()).just_finished()
+    {
+        answer = Some(node.timeout_response(&ctx));
+    }
+    let Some(answer) = answer else {
+        return;
+    };
+
+    let next = match answer {
+        Some(index) => {
+            let Some(response) = node.responses.get(index) else {
+                return;
+            };
+            state.won |= response.win;
+            let text = Markup::parse_or_plain(response_text(node, index, &locale)).to_plain_string();
+            log.push(locale.get(PLAYER_SPEAKER), &text);
+            response.choose(&ctx, &mut rand::rng())
+        }
+        None => {
+            log.push(locale.get(PLAYER_SPEAKER), SILENCE);
+            node.choose_silence(&ctx, &mut rand::rng())
+        }
+    };
+
+    if let Some(next) = next {
+        state.current_node = Some(next);
+        entered.write(DialogNodeEntered {
+            node: next,
+            npc: state.npc_entity,
+        });
+        state.anim_done = false;
+        state.dirty = true;
+        state.responses_shown = false;
+        state.timer = None;
+    } else {
+        // No further dialog — end
+        close_dialog(&mut commands, &mut state, &trees.0, &mut cursor_q);
+    }
+}
+
+fn apply_node_effects(
+    mut entered: MessageReader<DialogNodeEntered>,
+    trees: Res<DialogTrees>,
+    mut vars: ResMut<DialogVars>,
+    mut health: Option<ResMut<Health>>,
+    mut spawn_aberration: MessageWriter<SpawnAberration>,
+    mut switch_environment: MessageWriter<SwitchEnvironment>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for event in entered.read() {
+        for effect in &trees.0.node(event.node).effects {
+            match effect {
+                Effect::SetFlag(name) => vars.set_flag(name, true),
+                Effect::ClearFlag(name) => vars.set_flag(name, false),
+                Effect::AddVar(name, delta) => vars.add_var(name, *delta),
+                Effect::Heal(amount) => {
+                    if let Some(health) = health.as_mut() {
+                        let max = health.max;
+                        health.heal(amount * max);
+                    }
+                }
+                Effect::Damage(amount) => {
+                    if let Some(health) = health.as_mut() {
+                        let max = health.max;
+                        health.damage(amount * max);
+                    }
+                }
+                Effect::SpawnAberration => {
+                    spawn_aberration.write(SpawnAberration);
+                }
+                Effect::SwitchEnvironment(environment) => {
+                    switch_environment.write(SwitchEnvironment(environment.clone()));
+                }
+                Effect::PlaySound(sound) => {
+                    audio.play(sound.handle(&audio_assets));
+                }
+            }
+        }
+    }
+}
+
+fn count_dispels(mut dispelled: MessageReader<Dispelled>, mut vars: ResMut<DialogVars>) {
+    for event in dispelled.read() {
+        vars.dispelled += event.count as u32;
+    }
+}
+
+fn close_dialog(
+    commands: &mut Commands,
+    state: &mut ResMut<DialogState>,
+    graph: &DialogGraph,
+    cursor_q: &mut Query<&mut CursorOptions, With<PrimaryWindow>>,
+) {
+    // Check win condition before clearing state
+    let win = state.won || state.current_node.is_some_and(|id| graph.node(id).win);
+    if win && let Some(npc_entity) = state.npc_entity {
+        commands.entity(npc_entity).despawn();
+    }
+    commands.write_message(DialogEnded {
+        npc: state.npc_entity,
+        won: win,
+    });
+
+    state.active = false;
+    state.current_node = None;
+    state.won = false;
+    state.dirty = true;
+    state.responses_shown = false;
+    state.timer = None;
+    state.selected = None;
+    state.npc_entity = None;
+    // Re-lock cursor
+    if let Ok(mut cursor) = cursor_q.single_mut() {
+        cursor.grab_mode = CursorGrabMode::Locked;
+        cursor.visible = false;
+    }
+}
+
+fn track_animation_finished(
+    mut events: MessageReader<TypewriterFinished>,
+    mut state: ResMut<DialogState>,
+) {
+    for _event in events.read() {
+        if state.active && !state.anim_done {
+            state.anim_done = true;
+            state.dirty = true;
+        }
+    }
+}
+
+fn manage_dialog_ui(
+    mut commands: Commands,
+    mut state: ResMut<DialogState>,
+    trees: Res<DialogTrees>,
+    conditions: ConditionState,
+    dialog_q: Query<Entity, With<DialogUi>>,
+    text_q: Query<Entity, With<DialogText>>,
+    response_container_q: Query<(Entity, Option<&Children>), With<ResponseContainer>>,
+    identity_q: Query<&NpcIdentity>,
+    images: Res<Assets<Image>>,
+    locale: Res<Locale>,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    if !state.dirty {
+        return;
+    }
+    state.dirty = false;
+
+    if !state.active {
+        // Despawn dialog box
+        for entity in &dialog_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    // Collect data from current_node before mutating state
+    let Some(node) = state.current_node.map(|id| trees.0.node(id)) else {
+        return;
+    };
+    let identity = state.npc_entity.and_then(|e| identity_q.get(e).ok());
+    let talk_sound = identity
+        .and_then(|i| i.talk_sound.clone())
+        .unwrap_or_else(|| audio_assets.talk.clone());
+
+    if dialog_q.is_empty() {
+        // Spawn dialog box
+        spawn_dialog_box(
+            &mut commands,
+            line_text(node, &locale),
+            identity.and_then(|i| npc_name(i, &locale)),
+            portrait_image(identity, &images, &textures),
+            talk_sound.clone(),
+            &fonts,
+            &textures,
+        );
+        audio.play(talk_sound);
+    } else if state.anim_done && !state.responses_shown {
+        // Show response buttons
+        let player_responses: Vec<(usize, Markup)> = node
+            .visible_responses(&conditions.context(state.npc_entity))
+            .into_iter()
+            .map(|i| (i, Markup::parse_or_plain(response_text(node, i, &locale))))
+            .collect();
+
+        if player_responses.is_empty() {
+            // No player responses — dialog will end on next E press
+            return;
+        }
+
+        state.responses_shown = true;
+        state.timer = node.timer.map(|secs| Timer::from_seconds(secs, TimerMode::Once));
+        state.selected = None;
+
+        let font = text_font(&fonts);
+        let textbox = textures.textbox.clone();
+
+        for (container_entity, _) in &response_container_q {
+            commands.entity(container_entity).with_children(|parent| {
+                for (original_idx, markup) in &player_responses {
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                padding: UiRect::new(
+                                    Val::Px(16.0),
+                                    Val::Px(16.0),
+                                    Val::Px(8.0),
+                                    Val::Px(8.0),
+                                ),
+                                height: Val::Px(0.0),
+                                overflow: Overflow::clip(),
+                                ..default()
+                            },
+                            ImageNode {
+                                image: textbox.clone(),
+                                image_mode: NodeImageMode::Sliced(TextureSlicer {
+                                    border: BorderRect::all(16.0),
+                                    center_scale_mode: SliceScaleMode::Stretch,
+                                    sides_scale_mode: SliceScaleMode::Stretch,
+                                    max_corner_scale: 1.0,
+                                }),
+                                ..default()
+                            },
+                            ResponseButton(*original_idx),
+                            ResponseButtonAnim {
+                                timer: Timer::from_seconds(RESPONSE_ANIM_SECS, TimerMode::Once),
+                            },
+                        ))
+                        .with_children(|button| {
+                            insert_markup(&mut button.spawn_empty(), markup, &font, false);
+                            if node.timer.is_some() {
+                                button.spawn((
+                                    Node {
+                                        position_type: PositionType::Absolute,
+                                        left: Val::Px(0.0),
+                                        bottom: Val::Px(0.0),
+                                        width: Val::Percent(100.0),
+                                        height: Val::Px(TIMER_BAR_HEIGHT),
+                                        ..default()
+                                    },
+                                    BackgroundColor(Color::WHITE),
+                                    ResponseTimerBar,
+                                ));
+                            }
+                        });
+                }
+            });
+        }
+    } else if !state.anim_done {
+        // New NPC line — update text and clear response buttons
+        audio.play(talk_sound.clone());
+        let markup = Markup::parse_or_plain(line_text(node, &locale));
+        for entity in &text_q {
+            let mut text = commands.entity(entity);
+            insert_markup(&mut text, &markup, &text_font(&fonts), true);
+            text.insert(Typewriter::new(markup.clone(), TEXT_SPEED, talk_sound.clone()));
+        }
+        for (_, children) in &response_container_q {
+            if let Some(children) = children {
+                for child in children.iter() {
+                    commands.entity(child).despawn();
+                }
+            }
+        }
+    }
+}
+
+/// The NPC's own portrait, or the unknown face for NPCs without one. Comes with the portrait
+/// to cut out once its sheet has loaded, if it hasn't yet.
+fn portrait_image(
+    identity: Option<&NpcIdentity>,
+    images: &Assets<Image>,
+    textures: &TextureAssets,
+) -> (ImageNode, Option<PendingPortrait>) {
+    match identity.and_then(|i| i.portrait.as_ref()) {
+        Some(portrait) => {
+            let rect = portrait.first_frame(images);
+            let pending = rect.is_none().then(|| PendingPortrait(portrait.clone()));
+            let image = ImageNode {
+                image: portrait.texture.clone(),
+                rect,
+                ..default()
+            };
+            (image, pending)
+        }
+        None => {
+            let image = ImageNode {
+                image: textures.unknown.clone(),
+                ..default()
+            };
+            (image, None)
+        }
+    }
+}
+
+/// Show portraits whose sheet has finished loading, cropped to their first frame.
+fn show_loaded_portraits(
+    mut commands: Commands,
+    images: Res<Assets<Image>>,
+    mut portrait_q: Query<(Entity, &PendingPortrait, &mut ImageNode, &mut Visibility)>,
+) {
+    for (entity, pending, mut image, mut visibility) in &mut portrait_q {
+        if let Some(rect) = pending.0.first_frame(&images) {
+            image.rect = Some(rect);
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<PendingPortrait>();
+        }
+    }
+}
+
+/// The NPC's name in the current language.
+fn npc_name<'a>(identity: &'a NpcIdentity, locale: &'a Locale) -> Option<&'a str> {
+    let name = identity.name.as_deref()?;
+    Some(locale.get_or(&format!("npc.{name}"), name))
+}
+
+/// A node's line in the current language.
+fn line_text<'a>(node: &'a NodeDef, locale: &'a Locale) -> &'a str {
+    locale.dialog(&node.id, &node.text)
+}
+
+/// One of a node's responses in the current language.
+fn response_text<'a>(node: &'a NodeDef, index: usize, locale: &'a Locale) -> &'a str {
+    let response = &node.responses[index];
+    locale.dialog(&format!("{}.{}", node.id, response.id), &response.text)
+}
+
+fn spawn_dialog_box(
+    commands: &mut Commands,
+    line: &str,
+    name: Option<&str>,
+    portrait: (ImageNode, Option<PendingPortrait>),
+    voice: Handle<AudioSource>,
+    fonts: &Res<FontAssets>,
+    textures: &Res<TextureAssets>,
+) {
+    let font = fonts.main.clone();
+    let markup = Markup::parse_or_plain(line);
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::FlexEnd,
+                padding: UiRect::bottom(Val::Px(20.0)),
+                ..default()
+            },
+            GlobalZIndex(80),
+            DialogUi,
+        ))
+        .with_children(|parent| {
+            // Row: NPC portrait + text box
+            parent
+                .spawn(Node {
+                    width: Val::Percent(80.0),
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::FlexEnd,
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    // NPC portrait
+                    let (image, pending) = portrait;
+                    let mut portrait = row.spawn((
+                        Node {
+                            width: Val::Px(64.0),
+                            height: Val::Px(64.0),
+                            ..default()
+                        },
+                        image,
+                    ));
+                    if let Some(pending) = pending {
+                        portrait.insert((pending, Visibility::Hidden));
+                    }
+
+                    // Name plate above the NPC text box
+                    row.spawn(Node {
+                        flex_grow: 1.0,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::FlexStart,
+                        row_gap: Val::Px(4.0),
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        if let Some(name) = name {
+                            column
+                                .spawn((
+                                    Node {
+                                        padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)),
+                                        ..default()
+                                    },
+                                    ImageNode {
+                                        image: textures.textbox.clone(),
+                                        image_mode: NodeImageMode::Sliced(TextureSlicer {
+                                            border: BorderRect::all(16.0),
+                                            center_scale_mode: SliceScaleMode::Stretch,
+                                            sides_scale_mode: SliceScaleMode::Stretch,
+                                            max_corner_scale: 1.0,
+                                        }),
+                                        ..default()
+                                    },
+                                    NamePlate,
+                                ))
+                                .with_child((
+                                    Text::new(name),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 24.0,
+                                        font_smoothing: FontSmoothing::None,
+                                        ..default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                ));
+                        }
+
+                        // NPC text box
+                        column
+                            .spawn((
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    min_height: Val::Px(80.0),
+                                    padding: UiRect::all(Val::Px(16.0)),
+                                    ..default()
+                                },
+                                ImageNode {
+                                    image: textures.textbox.clone(),
+                                    image_mode: NodeImageMode::Sliced(TextureSlicer {
+                                        border: BorderRect::all(16.0),
+                                        center_scale_mode: SliceScaleMode::Stretch,
+                                        sides_scale_mode: SliceScaleMode::Stretch,
+                                        max_corner_scale: 1.0,
+                                    }),
+                                    ..default()
+                                },
+                            ))
+                            .with_children(|text_box| {
+                                let mut text = text_box.spawn(DialogText);
+                                insert_markup(&mut text, &markup, &text_font(fonts), true);
+                                text.insert(Typewriter::new(markup.clone(), TEXT_SPEED, voice));
+                            });
+                    });
+                });
+
+            // Response button container (initially empty, indented)
+            parent.spawn((
+                Node {
+                    width: Val::Percent(80.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    margin: UiRect::top(Val::Px(8.0)),
+                    padding: UiRect::left(Val::Px(72.0)),
+                    ..default()
+                },
+                ResponseContainer,
+            ));
+        });
+}
+
+fn text_font(fonts: &FontAssets) -> TextFont {
+    TextFont {
+        font: fonts.main.clone(),
+        font_size: TEXT_SIZE,
+        font_smoothing: FontSmoothing::None,
+        ..default()
+    }
+}
+
+fn shrink_response_timers(
+    state: Res<DialogState>,
+    mut bar_q: Query<&mut Node, With<ResponseTimerBar>>,
+) {
+    let Some(timer) = &state.timer else {
+        return;
+    };
+    for mut node in &mut bar_q {
+        node.width = Val::Percent(timer.fraction_remaining() * 100.0);
+    }
+}
+
+fn animate_response_buttons(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut Node, &mut ResponseButtonAnim)>,
+) {
+    for (entity, mut node, mut anim) in &mut query {
+        anim.timer.tick(time.delta());
+        let t = anim.timer.fraction();
+        // Animate from 0 to Auto height using scale factor on a max height
+        node.height = Val::Px(t * 48.0);
+        if t >= 1.0 {
+            node.height = Val::Auto;
+            commands.entity(entity).remove::<ResponseButtonAnim>();
+        }
+    }
+}
+
+/// Hovering a response selects it, so mouse, keyboard and gamepad share one highlight.
+fn response_button_hover(
+    mut state: ResMut<DialogState>,
+    interaction_q: Query<(&Interaction, &ResponseButton), Changed<Interaction>>,
+    mut button_q: Query<(&ResponseButton, &mut ImageNode)>,
+) {
+    for (interaction, button) in &interaction_q {
+        if *interaction != Interaction::None {
+            state.selected = Some(button.0);
+        }
+    }
+    for (button, mut image_node) in &mut button_q {
+        let color = if state.selected == Some(button.0) {
+            SELECTED_COLOR
+        } else {
+            Color::WHITE
+        };
+        if image_node.color != color {
+            image_node.color = color;
+        }
+    }
+}
+
+fn manage_prompt_ui(
+    mut commands: Commands,
+    nearby: Res<NearbyNpc>,
+    state: Res<DialogState>,
+    prompt_q: Query<Entity, With<PromptUi>>,
+    locale: Res<Locale>,
+    fonts: Res<FontAssets>,
+) {
+    let should_show = nearby.0.is_some() && !state.active;
+
+    if should_show && prompt_q.is_empty() {
+        commands.spawn((
+            Text::new(locale.get("hud.talk")),
+            TextFont {
+                font: fonts.main.clone(),
+                font_size: 32.0,
+                font_smoothing: FontSmoothing::None,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(120.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            GlobalZIndex(80),
+            PromptUi,
+        ));
+    } else if !should_show {
+        for entity in &prompt_q {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn cleanup_dialog(
+    mut commands: Commands,
+    dialog_q: Query<Entity, With<DialogUi>>,
+    prompt_q: Query<Entity, With<PromptUi>>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+) {
+    for entity in &dialog_q {
+        commands.entity(entity).despawn();
+    }
+    for entity in &prompt_q {
+        commands.entity(entity).despawn();
+    }
+    // Ensure cursor is released (player.rs release_cursor handles re-lock on exit)
+    if let Ok(mut cursor) = cursor_q.single_mut() {
+        cursor.grab_mode = CursorGrabMode::None;
+        cursor.visible = true;
+    }
+    commands.remove_resource::<DialogState>();
+    commands.remove_resource::<DialogTrees>();
+    commands.remove_resource::<NearbyNpc>();
+    commands.remove_resource::<DialogVars>();
+    commands.remove_resource::<DialogLog>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale::Language;
+    use crate::pause::Paused;
+    use crate::testing::TestApp;
+    use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+    use markup::Highlight;
+    use typewriter::GlyphMotion;
+
+    /// A playing app with one NPC in range, talking through the dialog graph in `source`.
+    fn setup(source: &str) -> (TestApp, Entity) {
+        let mut app = TestApp::new().with_plugins(DialogPlugin);
+        app.app
+            .add_message::<SpawnAberration>()
+            .add_message::<SwitchEnvironment>()
+            .add_message::<Dispelled>();
+        app.start_playing();
+        app.resource_mut::<DialogTrees>().0 = DialogGraph::from_ron(source).unwrap();
+        app.spawn_player(Vec3::new(0.0, 1.7, 0.0));
+        let npc = app
+            .app
+            .world_mut()
+            .spawn((Transform::from_xyz(0.0, 1.7, -1.0), Npc { range: 3.0 }))
+            .id();
+        app.step();
+        (app, npc)
+    }
+
+    fn current_text(app: &TestApp) -> Option<String> {
+        let graph = &app.resource::<DialogTrees>().0;
+        app.resource::<DialogState>()
+            .current_node
+            .map(|id| graph.node(id).text.clone())
+    }
+
+    /// Skip the typewriter animation and wait for the response buttons to appear.
+    fn show_responses(app: &mut TestApp) {
+        app.tap_key(KeyCode::Space);
+        assert!(app.resource::<DialogState>().responses_shown);
+    }
+
+    fn shown_responses(app: &mut TestApp) -> Vec<usize> {
+        let mut shown: Vec<usize> = app
+            .app
+            .world_mut()
+            .query::<&ResponseButton>()
+            .iter(app.app.world())
+            .map(|b| b.0)
+            .collect();
+        shown.sort();
+        shown
+    }
+
+    fn click_response(app: &mut TestApp, index: usize) {
+        let button = app
+            .app
+            .world_mut()
+            .query::<(Entity, &ResponseButton)>()
+            .iter(app.app.world())
+            .find(|(_, b)| b.0 == index)
+            .map(|(e, _)| e)
+            .expect("response button should be spawned");
+        app.app
+            .world_mut()
+            .entity_mut(button)
+            .insert(Interaction::Pressed);
+        app.step();
+    }
+
+    #[test]
+    fn choosing_a_response_follows_the_tree() {
+        let (mut app, _) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "hello", text: "Hello?", responses: [
+                    (text: "Who's there?", next: [(goto: "nobody")]),
+                    (text: "Goodbye."),
+                ]),
+                (id: "nobody", text: "Nobody.", responses: [(text: "Again?", next: [(goto: "hello")])]),
+            ])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        assert!(app.resource::<DialogState>().active);
+        assert_eq!(current_text(&app).as_deref(), Some("Hello?"));
+
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+        assert_eq!(current_text(&app).as_deref(), Some("Nobody."));
+        assert!(!app.resource::<DialogState>().responses_shown);
+
+        // Loops back to the first line
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+        assert_eq!(current_text(&app).as_deref(), Some("Hello?"));
+    }
+
+    #[test]
+    fn response_without_continuation_ends_dialog() {
+        let (mut app, npc) = setup(
+            r#"(trees: [(id: "t", nodes: [(id: "a", text: "Hello?", responses: [(text: "Goodbye.")])])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+
+        assert!(!app.resource::<DialogState>().active);
+        assert!(app.app.world().get_entity(npc).is_ok());
+    }
+
+    #[test]
+    fn closing_on_a_win_line_despawns_the_npc() {
+        let (mut app, npc) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "Do you see me?", responses: [(text: "Yes.", next: [(goto: "b")])]),
+                (id: "b", text: "Thank you.", win: true),
+            ])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+        assert_eq!(current_text(&app).as_deref(), Some("Thank you."));
+
+        app.tap_key(KeyCode::KeyE);
+        assert!(!app.resource::<DialogState>().active);
+        assert!(app.app.world().get_entity(npc).is_err());
+    }
+
+    #[test]
+    fn winning_response_despawns_the_npc() {
+        let (mut app, npc) = setup(
+            r#"(trees: [(id: "t", nodes: [(id: "a", text: "...", responses: [(text: "Mine now.", win: true)])])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+
+        assert!(!app.resource::<DialogState>().active);
+        assert!(app.app.world().get_entity(npc).is_err());
+    }
+
+    #[test]
+    fn entering_a_line_applies_its_effects() {
+        let (mut app, _) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "Hold still.", effects: [SetFlag("met"), AddVar("visits", 2)], responses: [
+                    (text: "Why?", next: [(goto: "b")]),
+                ]),
+                (id: "b", text: "There.", effects: [Heal(0.25), SpawnAberration, SwitchEnvironment(Dissociation)]),
+            ])])"#,
+        );
+        app.app.insert_resource(Health {
+            current: 0.5,
+            ..default()
+        });
+
+        app.tap_key(KeyCode::KeyE);
+        let vars = app.resource::<DialogVars>();
+        assert!(vars.flag("met"));
+        assert_eq!(vars.var("visits"), 2);
+
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+        assert!((app.resource::<Health>().current - 0.75).abs() < 0.01);
+        let spawns = app.resource::<Messages<SpawnAberration>>();
+        assert_eq!(spawns.iter_current_update_messages().count(), 1);
+        let switches: Vec<Environment> = app
+            .resource::<Messages<SwitchEnvironment>>()
+            .iter_current_update_messages()
+            .map(|s| s.0.clone())
+            .collect();
+        assert_eq!(switches, vec![Environment::Dissociation]);
+    }
+
+    #[test]
+    fn conditional_responses_are_hidden_until_they_hold() {
+        let source = r#"
+            #![enable(implicit_some)]
+            (trees: [(id: "t", nodes: [(id: "a", text: "Well?", responses: [
+                (id: "met", text: "We've met.", when: Flag("met")),
+                (id: "losing_it", text: "I'm losing it.", when: SanityBelow(0.5)),
+                (id: "got_them", text: "I got them.", when: DispelledAtLeast(2)),
+                (text: "Hello."),
+            ])])])
+        "#;
+        let (mut app, _) = setup(source);
+        app.app.insert_resource(Health::default());
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        assert_eq!(shown_responses(&mut app), vec![3]);
+        app.tap_key(KeyCode::Escape);
+
+        app.resource_mut::<DialogVars>().set_flag("met", true);
+        app.resource_mut::<Health>().current = 0.2;
+        app.app.world_mut().write_message(Dispelled::new(2, None));
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        assert_eq!(shown_responses(&mut app), vec![0, 1, 2, 3]);
+    }
+
+    const TWO_TREES: &str = r#"(trees: [
+        (id: "a", nodes: [(id: "start", text: "From a.")]),
+        (id: "b", nodes: [(id: "start", text: "From b.")]),
+    ])"#;
+
+    #[test]
+    fn npc_talks_through_its_own_trees() {
+        let (mut app, npc) = setup(TWO_TREES);
+        app.app.world_mut().entity_mut(npc).insert(NpcIdentity {
+            dialog: vec!["b".into(), "missing".into()],
+            ..default()
+        });
+
+        for _ in 0..8 {
+            app.tap_key(KeyCode::KeyE);
+            assert_eq!(current_text(&app).as_deref(), Some("From b."));
+            app.tap_key(KeyCode::Escape);
+        }
+    }
+
+    #[test]
+    fn name_plate_shows_only_for_named_npcs() {
+        let (mut app, npc) = setup(TWO_TREES);
+        app.tap_key(KeyCode::KeyE);
+        let plates = app.app.world_mut().query::<&NamePlate>().iter(app.app.world()).count();
+        assert_eq!(plates, 0);
+        app.tap_key(KeyCode::Escape);
+
+        app.app.world_mut().entity_mut(npc).insert(NpcIdentity {
+            name: Some("Green".into()),
+            ..default()
+        });
+        app.tap_key(KeyCode::KeyE);
+        let names: Vec<String> = app
+            .app
+            .world_mut()
+            .query_filtered::<&Children, With<NamePlate>>()
+            .iter(app.app.world())
+            .flat_map(|children| children.iter())
+            .filter_map(|child| app.app.world().get::<Text>(child))
+            .map(|text| text.0.clone())
+            .collect();
+        assert_eq!(names, vec!["Green".to_string()]);
+    }
+
+    #[test]
+    fn portraits_stay_hidden_until_their_sheet_loads() {
+        let (mut app, npc) = setup(TWO_TREES);
+        let texture = app.resource::<Assets<Image>>().reserve_handle();
+        app.app.world_mut().entity_mut(npc).insert(NpcIdentity {
+            portrait: Some(Portrait {
+                texture: texture.clone(),
+                columns: 4,
+            }),
+            ..default()
+        });
+        app.tap_key(KeyCode::KeyE);
+
+        let portrait = |app: &mut TestApp| {
+            app.app
+                .world_mut()
+                .query::<(&ImageNode, &Visibility)>()
+                .iter(app.app.world())
+                .find(|(image, _)| image.image == texture)
+                .map(|(image, visibility)| (image.rect, *visibility))
+                .expect("portrait should be spawned")
+        };
+        assert_eq!(portrait(&mut app), (None, Visibility::Hidden));
+
+        let sheet = Image::new_fill(
+            Extent3d {
+                width: 64,
+                height: 16,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0; 4],
+            TextureFormat::Rgba8UnormSrgb,
+            default(),
+        );
+        app.resource_mut::<Assets<Image>>().insert(&texture, sheet).unwrap();
+        app.step();
+        let frame = Rect::new(0.0, 0.0, 16.0, 16.0);
+        assert_eq!(portrait(&mut app), (Some(frame), Visibility::Inherited));
+    }
+
+    #[test]
+    fn npc_remembers_and_greets_returning_players() {
+        let (mut app, npc) = setup(TWO_TREES);
+        app.app.world_mut().entity_mut(npc).insert(NpcIdentity {
+            dialog: vec!["a".into()],
+            returning: vec!["b".into()],
+            ..default()
+        });
+
+        app.tap_key(KeyCode::KeyE);
+        assert_eq!(current_text(&app).as_deref(), Some("From a."));
+        app.tap_key(KeyCode::Escape);
+
+        let memory = app.app.world().get::<NpcMemory>(npc).unwrap();
+        assert_eq!(memory.times_spoken, 1);
+        assert_eq!(memory.last_node.as_deref(), Some("a/start"));
+        assert!(memory.visited.contains("a/start"));
+
+        app.tap_key(KeyCode::KeyE);
+        assert_eq!(current_text(&app).as_deref(), Some("From b."));
+    }
+
+    #[test]
+    fn dialog_log_records_both_sides_in_order() {
+        let (mut app, npc) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "Hello?", responses: [(text: "Hi.", next: [(goto: "b")])]),
+                (id: "b", text: "Oh. You."),
+            ])])"#,
+        );
+        app.app.world_mut().entity_mut(npc).insert(NpcIdentity {
+            name: Some("Green".into()),
+            ..default()
+        });
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        click_response(&mut app, 0);
+
+        let lines: Vec<(String, String)> = app
+            .resource::<DialogLog>()
+            .entries()
+            .map(|e| (e.speaker.clone(), e.text.clone()))
+            .collect();
+        let expected = [("Green", "Hello?"), ("You", "Hi."), ("Green", "Oh. You.")];
+        assert_eq!(
+            lines,
+            expected.map(|(s, t)| (s.to_string(), t.to_string())).to_vec()
+        );
+    }
+
+    #[test]
+    fn timed_lines_pick_the_timeout_response() {
+        let (mut app, _) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "Quick.", timer: Some(1.0), responses: [
+                    (text: "Wait!", next: [(goto: "b")]),
+                    (text: "...", next: [(goto: "c")], timeout: true),
+                ]),
+                (id: "b", text: "Too slow."),
+                (id: "c", text: "Nothing to say?"),
+            ])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        app.advance(0.5);
+        assert_eq!(current_text(&app).as_deref(), Some("Quick."));
+        app.advance(0.6);
+        assert_eq!(current_text(&app).as_deref(), Some("Nothing to say?"));
+        let last = app.resource::<DialogLog>().entries().nth(1).cloned().unwrap();
+        assert_eq!((last.speaker.as_str(), last.text.as_str()), ("You", "..."));
+    }
+
+    #[test]
+    fn timers_stop_while_paused_and_silence_ends_the_talk() {
+        let (mut app, _) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "Well?", timer: Some(1.0), responses: [(text: "Hm.")]),
+            ])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        app.app.insert_resource(Paused(true));
+        app.advance(2.0);
+        assert!(app.resource::<DialogState>().active);
+
+        app.app.insert_resource(Paused(false));
+        app.advance(1.1);
+        assert!(!app.resource::<DialogState>().active);
+    }
+
+    #[test]
+    fn responses_can_be_chosen_from_the_keyboard() {
+        let (mut app, _) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "Which?", responses: [
+                    (text: "This.", next: [(goto: "b")]),
+                    (text: "That.", next: [(goto: "c")]),
+                ]),
+                (id: "b", text: "Sure.", responses: [(text: "Bye."), (text: "Wait.", next: [(goto: "a")])]),
+                (id: "c", text: "Really?"),
+            ])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        // Up from nothing wraps to the last response, down wraps back to the first
+        app.tap_key(KeyCode::ArrowUp);
+        assert_eq!(app.resource::<DialogState>().selected, Some(1));
+        app.tap_key(KeyCode::ArrowDown);
+        assert_eq!(app.resource::<DialogState>().selected, Some(0));
+        app.tap_key(KeyCode::Enter);
+        assert_eq!(current_text(&app).as_deref(), Some("Sure."));
+
+        show_responses(&mut app);
+        assert_eq!(app.resource::<DialogState>().selected, None);
+        app.tap_key(KeyCode::Digit2);
+        assert_eq!(current_text(&app).as_deref(), Some("Which?"));
+
+        // Never left mouse-look
+        let cursor = app
+            .app
+            .world_mut()
+            .query::<&CursorOptions>()
+            .single(app.app.world())
+            .unwrap();
+        assert_eq!(cursor.grab_mode, CursorGrabMode::Locked);
+    }
+
+    #[test]
+    fn translations_cover_the_shipped_dialog() {
+        let graph = DialogGraph::from_ron(DIALOG_RON).unwrap();
+        for language in Language::ALL.into_iter().filter(|&l| l != Language::English) {
+            let locale = Locale::new(language);
+            let keys: HashSet<&str> = locale.keys(language).collect();
+            for key in keys.iter().filter_map(|k| k.strip_prefix("dialog.")) {
+                let (node, response) = match key.split_once('.') {
+                    Some((node, response)) => (node, Some(response)),
+                    None => (key, None),
+                };
+                let node = graph.find(node).map(|id| graph.node(id));
+                assert!(
+                    node.is_some_and(|n| {
+                        response.is_none_or(|id| n.responses.iter().any(|r| r.id == id))
+                    }),
+                    "`{key}` has no line in dialog.ron"
+                );
+            }
+            for node in graph.nodes() {
+                let mut ids = vec![node.id.clone()];
+                ids.extend(node.responses.iter().map(|r| format!("{}.{}", node.id, r.id)));
+                for id in ids {
+                    let key = format!("dialog.{id}");
+                    assert!(keys.contains(key.as_str()), "{language:?} is missing `{key}`");
+                    assert!(Markup::parse(locale.get(&key)).is_ok(), "bad markup in `{key}`");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dialog_is_shown_and_logged_in_the_selected_language() {
+        let (mut app, npc) = setup(
+            r#"(trees: [(id: "how_long", nodes: [
+                (id: "start", text: "How long have you been here?", responses: [(id: Some("why_stay"), text: "I think I never left.")]),
+            ])])"#,
+        );
+        app.app.world_mut().entity_mut(npc).insert(NpcIdentity {
+            name: Some("Green".into()),
+            ..default()
+        });
+        app.resource_mut::<Locale>().language = Language::German;
+
+        app.tap_key(KeyCode::KeyE);
+        show_responses(&mut app);
+        let typed: String = dialog_spans(&mut app).into_iter().map(|(t, _)| t).collect();
+        assert_eq!(typed, "Wie lange bist du schon hier?");
+        click_response(&mut app, 0);
+
+        let lines: Vec<(String, String)> = app
+            .resource::<DialogLog>()
+            .entries()
+            .map(|e| (e.speaker.clone(), e.text.clone()))
+            .collect();
+        let expected = [
+            ("Grün", "Wie lange bist du schon hier?"),
+            ("Du", "Ich glaube, ich bin nie gegangen."),
+        ];
+        assert_eq!(
+            lines,
+            expected.map(|(s, t)| (s.to_string(), t.to_string())).to_vec()
+        );
+    }
+
+    fn dialog_spans(app: &mut TestApp) -> Vec<(String, Color)> {
+        let text = app
+            .app
+            .world_mut()
+            .query_filtered::<&Children, With<DialogText>>()
+            .single(app.app.world())
+            .expect("dialog text should be spawned")
+            .to_vec();
+        text.iter()
+            .map(|&span| {
+                let world = app.app.world();
+                (
+                    world.get::<TextSpan>(span).unwrap().0.clone(),
+                    world.get::<TextColor>(span).unwrap().0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn marked_up_lines_type_into_styled_spans() {
+        let (mut app, _) = setup(
+            r#"(trees: [(id: "t", nodes: [
+                (id: "a", text: "I [color=yellow]see[/color] [shake]you[/shake].", responses: [(text: "[wave]Hi[/wave]")]),
+            ])])"#,
+        );
+
+        app.tap_key(KeyCode::KeyE);
+        let spans = dialog_spans(&mut app);
+        assert_eq!(spans.len(), 5);
+        assert_eq!(spans[1].1, Highlight::Yellow.color());
+        assert!(spans.iter().map(|(t, _)| t.len()).sum::<usize>() < "I see you.".len());
+
+        show_responses(&mut app);
+        let typed: String = dialog_spans(&mut app).into_iter().map(|(t, _)| t).collect();
+        assert_eq!(typed, "I see you.");
+        // The shaking line and the waving response
+        let moving = app
+            .app
+            .world_mut()
+            .query::<&GlyphMotion>()
+            .iter(app.app.world())
+            .count();
+        assert_eq!(moving, 2);
+    }
+}
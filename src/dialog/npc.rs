@@ -0,0 +1,88 @@
+//! Who an NPC is: the dialog trees they talk through, their name, portrait, voice and barks.
+
+use super::bark::Barks;
+use bevy::prelude::*;
+use bevy_kira_audio::AudioSource;
+use serde::Deserialize;
+
+/// Mark an entity as an interactable NPC.
+#[derive(Component)]
+pub struct Npc {
+    pub range: f32,
+}
+
+/// Identity of an NPC, shared by `npcs.ron` entries and NPC aberration types.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct NpcIdentityRon {
+    /// Shown on the name plate above the dialog box.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Dialog tree IDs this NPC picks from. Empty means any tree.
+    #[serde(default)]
+    pub dialog: Vec<String>,
+    /// Trees used instead of `dialog` once the player has talked to this NPC before.
+    #[serde(default)]
+    pub returning: Vec<String>,
+    #[serde(default)]
+    pub portrait: Option<PortraitRon>,
+    /// Played when the NPC starts a line, instead of the default talk sound.
+    #[serde(default)]
+    pub talk_sound: Option<String>,
+    /// Lines said unprompted while the player is near.
+    #[serde(default)]
+    pub barks: Option<Barks>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PortraitRon {
+    pub texture: String,
+    /// Sprite sheet columns; the portrait shows the first frame.
+    #[serde(default = "default_columns")]
+    pub columns: u32,
+}
+
+fn default_columns() -> u32 {
+    1
+}
+
+/// Runtime identity of an NPC. NPCs without one talk through a random tree as an unnamed
+/// stranger.
+#[derive(Component, Debug, Clone, Default)]
+pub struct NpcIdentity {
+    pub name: Option<String>,
+    pub dialog: Vec<String>,
+    pub returning: Vec<String>,
+    pub portrait: Option<Portrait>,
+    pub talk_sound: Option<Handle<AudioSource>>,
+    pub barks: Option<Barks>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Portrait {
+    pub texture: Handle<Image>,
+    pub columns: u32,
+}
+
+impl NpcIdentityRon {
+    pub fn load(&self, asset_server: &AssetServer) -> NpcIdentity {
+        NpcIdentity {
+            name: self.name.clone(),
+            dialog: self.dialog.clone(),
+            returning: self.returning.clone(),
+            portrait: self.portrait.as_ref().map(|p| Portrait {
+                texture: asset_server.load(&p.texture),
+                columns: p.columns.max(1),
+            }),
+            talk_sound: self.talk_sound.as_ref().map(|path| asset_server.load(path)),
+            barks: self.barks.clone(),
+        }
+    }
+}
+
+impl Portrait {
+    /// The first frame of the sheet, once the texture has loaded.
+    pub fn first_frame(&self, images: &Assets<Image>) -> Option<Rect> {
+        let size = images.get(&self.texture)?.size_f32();
+        Some(Rect::new(0.0, 0.0, size.x / self.columns as f32, size.y))
+    }
+}
@@ -0,0 +1,260 @@
+//! Typewriter animation and per-character motion for marked-up dialog text.
+//!
+//! A marked-up line is a `Text` with an empty root and one `TextSpan` child per segment, so
+//! glyph `span_index` N belongs to segment N - 1. The typewriter fills the spans in as time
+//! passes; motion is applied after UI layout by nudging the laid-out glyph positions.
+
+use super::markup::{Markup, Motion};
+use bevy::prelude::*;
+use bevy::text::TextLayoutInfo;
+use bevy_kira_audio::{Audio, AudioControl, AudioSource};
+
+/// Minimum time between voice blips, so fast text doesn't turn into a drone.
+const BLIP_INTERVAL_SECS: f32 = 0.07;
+const SHAKE_PX: f32 = 1.5;
+/// Shake offsets change this many times a second.
+const SHAKE_RATE: f32 = 20.0;
+const WAVE_PX: f32 = 3.0;
+const WAVE_SPEED: f32 = 6.0;
+/// Phase step between neighbouring characters of a wave.
+const WAVE_PHASE: f32 = 0.5;
+
+/// Sent when a typewriter has typed its whole line, including any trailing pause.
+#[derive(Message, Debug, Clone, Copy)]
+pub struct TypewriterFinished {
+    pub entity: Entity,
+}
+
+/// Types a `Markup` line into the `TextSpan` children of its entity.
+#[derive(Component)]
+pub struct Typewriter {
+    markup: Markup,
+    /// When each character appears, in seconds from the start, across all segments.
+    reveal_at: Vec<f32>,
+    /// When the line counts as finished.
+    duration: f32,
+    elapsed: f32,
+    shown: usize,
+    finished: bool,
+    voice: Handle<AudioSource>,
+    since_blip: f32,
+}
+
+impl Typewriter {
+    /// Type `markup` at `speed` characters per second, blipping with `voice` where marked.
+    pub fn new(markup: Markup, speed: f32, voice: Handle<AudioSource>) -> Self {
+        let mut reveal_at = Vec::new();
+        let mut t = 0.0;
+        for segment in &markup.segments {
+            t += segment.pause;
+            for _ in segment.text.chars() {
+                t += 1.0 / (speed * segment.speed);
+                reveal_at.push(t);
+            }
+        }
+        Self {
+            markup,
+            reveal_at,
+            duration: t,
+            elapsed: 0.0,
+            shown: 0,
+            finished: false,
+            voice,
+            since_blip: BLIP_INTERVAL_SECS,
+        }
+    }
+
+    /// Show the rest of the line on the next update.
+    pub fn skip(&mut self) {
+        self.elapsed = self.duration;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advance by `dt`, returning how many characters are now visible.
+    fn advance(&mut self, dt: f32) -> usize {
+        self.elapsed += dt;
+        self.since_blip += dt;
+        self.reveal_at.partition_point(|&t| t <= self.elapsed)
+    }
+
+    /// Whether any newly shown character should blip.
+    fn blips_between(&self, from: usize, to: usize) -> bool {
+        let mut index = 0;
+        for segment in &self.markup.segments {
+            for c in segment.text.chars() {
+                if (from..to).contains(&index) && segment.blip && !c.is_whitespace() {
+                    return true;
+                }
+                index += 1;
+            }
+        }
+        false
+    }
+
+    /// Text of each span with the first `shown` characters typed.
+    fn span_texts(&self, shown: usize) -> impl Iterator<Item = String> + '_ {
+        let mut remaining = shown;
+        self.markup.segments.iter().map(move |segment| {
+            let text: String = segment.text.chars().take(remaining).collect();
+            remaining -= text.chars().count();
+            text
+        })
+    }
+}
+
+/// Offsets the glyphs of moving segments every frame.
+#[derive(Component)]
+pub struct GlyphMotion {
+    /// Motion of each segment.
+    motions: Vec<Motion>,
+    /// Glyph positions as laid out, before any offset.
+    base: Vec<Vec2>,
+}
+
+impl GlyphMotion {
+    /// `None` when nothing in `markup` moves.
+    pub fn for_markup(markup: &Markup) -> Option<Self> {
+        let motions: Vec<Motion> = markup.segments.iter().map(|s| s.motion).collect();
+        motions.iter().any(|m| *m != Motion::Still).then_some(Self {
+            motions,
+            base: Vec::new(),
+        })
+    }
+}
+
+/// Give `text` one styled span per segment of `markup`. Typed spans start empty for a
+/// `Typewriter` to fill in.
+pub fn insert_markup(text: &mut EntityCommands, markup: &Markup, font: &TextFont, typed: bool) {
+    text.despawn_children()
+        .insert((Text::default(), font.clone(), TextColor(Color::WHITE)))
+        .remove::<GlyphMotion>()
+        .with_children(|spans| {
+            for segment in &markup.segments {
+                spans.spawn((
+                    TextSpan::new(if typed { String::new() } else { segment.text.clone() }),
+                    font.clone(),
+                    TextColor(segment.color.map_or(Color::WHITE, |c| c.color())),
+                ));
+            }
+        });
+    if let Some(motion) = GlyphMotion::for_markup(markup) {
+        text.insert(motion);
+    }
+}
+
+pub(super) fn type_text(
+    time: Res<Time>,
+    mut typewriter_q: Query<(Entity, &mut Typewriter, Option<&Children>)>,
+    mut span_q: Query<&mut TextSpan>,
+    mut finished: MessageWriter<TypewriterFinished>,
+    audio: Res<Audio>,
+) {
+    for (entity, mut typewriter, children) in &mut typewriter_q {
+        if typewriter.finished {
+            continue;
+        }
+        let before = typewriter.shown;
+        let shown = typewriter.advance(time.delta_secs());
+
+        if shown != before {
+            if typewriter.since_blip >= BLIP_INTERVAL_SECS && typewriter.blips_between(before, shown) {
+                audio.play(typewriter.voice.clone());
+                typewriter.since_blip = 0.0;
+            }
+            let spans = children.into_iter().flatten();
+            for (child, text) in spans.zip(typewriter.span_texts(shown)) {
+                if let Ok(mut span) = span_q.get_mut(*child) {
+                    span.0 = text;
+                }
+            }
+            typewriter.shown = shown;
+        }
+
+        if typewriter.elapsed >= typewriter.duration {
+            typewriter.finished = true;
+            finished.write(TypewriterFinished { entity });
+        }
+    }
+}
+
+/// Runs after UI layout, so it only ever offsets freshly laid-out positions.
+pub(super) fn animate_glyphs(
+    time: Res<Time>,
+    mut text_q: Query<(&mut GlyphMotion, &mut TextLayoutInfo)>,
+) {
+    let t = time.elapsed_secs();
+    for (mut motion, mut layout) in &mut text_q {
+        if layout.is_changed() {
+            motion.base = layout.glyphs.iter().map(|g| g.position).collect();
+        }
+        // Offsets don't count as a change, so the next relayout is still noticed above
+        let layout = layout.bypass_change_detection();
+        if motion.base.len() != layout.glyphs.len() {
+            continue;
+        }
+        for (i, glyph) in layout.glyphs.iter_mut().enumerate() {
+            let segment_motion = glyph
+                .span_index
+                .checked_sub(1)
+                .and_then(|segment| motion.motions.get(segment))
+                .copied()
+                .unwrap_or_default();
+            glyph.position = motion.base[i] + glyph_offset(segment_motion, i, t) * layout.scale_factor;
+        }
+    }
+}
+
+fn glyph_offset(motion: Motion, index: usize, t: f32) -> Vec2 {
+    match motion {
+        Motion::Still => Vec2::ZERO,
+        Motion::Shake => {
+            let tick = (t * SHAKE_RATE).floor();
+            let jitter = |salt: f32| {
+                let x = (index as f32 * 12.9898 + tick * 78.233 + salt).sin() * 43_758.547;
+                x.fract()
+            };
+            Vec2::new(jitter(0.0), jitter(17.0)) * SHAKE_PX
+        }
+        Motion::Wave => Vec2::new(0.0, (t * WAVE_SPEED - index as f32 * WAVE_PHASE).sin() * WAVE_PX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typewriter(source: &str) -> Typewriter {
+        Typewriter::new(Markup::parse(source).unwrap(), 10.0, Handle::default())
+    }
+
+    #[test]
+    fn pauses_and_speed_shape_the_reveal_timing() {
+        let mut tw = typewriter("ab[pause=0.5][speed=2]cd[/speed]");
+        assert_eq!(tw.advance(0.25), 2);
+        // Held by the pause
+        assert_eq!(tw.advance(0.4), 2);
+        // Then twice as fast: 0.05s a character
+        assert_eq!(tw.advance(0.2), 4);
+        assert!((tw.duration - 0.8).abs() < 1e-5);
+    }
+
+    #[test]
+    fn span_texts_fill_segments_in_order() {
+        let tw = typewriter("ab[shake]cd[/shake]e");
+        let texts: Vec<String> = tw.span_texts(3).collect();
+        assert_eq!(texts, vec!["ab", "c", ""]);
+        let texts: Vec<String> = tw.span_texts(5).collect();
+        assert_eq!(texts, vec!["ab", "cd", "e"]);
+    }
+
+    #[test]
+    fn only_marked_characters_blip() {
+        let tw = typewriter("a [blip]b c[/blip]");
+        assert!(!tw.blips_between(0, 2));
+        assert!(tw.blips_between(0, 3));
+        assert!(!tw.blips_between(3, 4));
+    }
+}
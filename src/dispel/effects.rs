@@ -0,0 +1,461 @@
+//! Visual feedback for dispel, drawn into the half-resolution canvas.
+//!
+//! The lasso and its closure glow are meshes parented to the `FpsCamera`, a fixed distance in
+//! front of it, so they go through the same palette pass as the rest of the world instead of
+//! being overlaid as gizmos. Dispelled aberrations shatter and dissolve instead of vanishing,
+//! and abandoned or empty loops fizzle out.
+
+use super::{CLOSURE_DISTANCE, DispelFailed, DispelState, MIN_POINTS};
+use crate::aberration::{Aberration, SpawnAnimation};
+use crate::dialog::Npc;
+use crate::player::FpsCamera;
+use crate::scaling::CanvasLayout;
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use rand::Rng;
+
+/// Distance in front of the camera the stroke is drawn at (world units).
+const LASSO_DEPTH: f32 = 0.5;
+/// Half the stroke thickness, in window pixels.
+const STROKE_HALF_WIDTH: f32 = 3.0;
+/// Window pixels of stroke covered by one repeat of the stroke texture.
+const STROKE_TEXTURE_LENGTH: f32 = 32.0;
+/// Size of the closure glow once the cursor is on the start point, in window pixels.
+const GLOW_MAX_SIZE: f32 = 48.0;
+/// How far from the start point the glow starts to grow, as a multiple of `CLOSURE_DISTANCE`.
+const GLOW_RANGE: f32 = 4.0;
+/// How long a dispelled aberration takes to shatter and fade.
+pub(super) const DISSOLVE_SECS: f32 = 0.6;
+/// How fast shattered sprite layers fly apart (world units per second).
+const SHARD_SPEED: f32 = 2.5;
+/// How long a failed stroke lingers while it fizzles out.
+const FIZZLE_SECS: f32 = 0.4;
+/// Maximum jitter of a fizzling stroke, in window pixels.
+const FIZZLE_JITTER: f32 = 6.0;
+
+/// Shared meshes, textures and materials for the dispel effects.
+#[derive(Resource)]
+pub(super) struct DispelVisuals {
+    stroke_material: Handle<StandardMaterial>,
+    glow_material: Handle<StandardMaterial>,
+    /// Rewritten for each failed stroke rather than adding a mesh per failure.
+    fizzle_mesh: Handle<Mesh>,
+}
+
+/// The live lasso stroke. Its mesh is rebuilt in place from `DispelState::points` every frame.
+#[derive(Component)]
+pub(super) struct LassoStroke;
+
+/// Glow at the start point that grows as the cursor closes the loop.
+#[derive(Component)]
+pub(super) struct ClosureGlow;
+
+/// A failed stroke breaking up before it disappears. There is only ever one, since they share
+/// `DispelVisuals::fizzle_mesh`.
+#[derive(Component)]
+pub(super) struct Fizzle {
+    stroke: Vec<Vec2>,
+    timer: Timer,
+}
+
+/// A dispelled aberration shattering. Its `Aberration` marker is removed so gameplay ignores it.
+#[derive(Component)]
+pub(super) struct Dissolving {
+    timer: Timer,
+}
+
+/// Per-layer drift direction while an aberration shatters.
+#[derive(Component)]
+pub(super) struct Shard {
+    velocity: Vec3,
+}
+
+pub(super) fn init_visuals(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let stroke_material = materials.add(StandardMaterial {
+        base_color_texture: Some(images.add(stroke_texture())),
+        unlit: true,
+        cull_mode: None,
+        alpha_mode: AlphaMode::Mask(0.5),
+        ..default()
+    });
+    let glow_material = materials.add(StandardMaterial {
+        base_color_texture: Some(images.add(glow_texture())),
+        unlit: true,
+        cull_mode: None,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+    commands.insert_resource(DispelVisuals {
+        stroke_material,
+        glow_material,
+        fizzle_mesh: meshes.add(empty_mesh()),
+    });
+}
+
+/// Turn an aberration into a shattering husk that despawns after `DISSOLVE_SECS`.
+pub(super) fn dissolve(commands: &mut Commands, entity: Entity) {
+    commands
+        .entity(entity)
+        .remove::<(Aberration, SpawnAnimation, Npc)>()
+        .insert(Dissolving {
+            timer: Timer::from_seconds(DISSOLVE_SECS, TimerMode::Once),
+        });
+}
+
+/// Keep the lasso and glow meshes in sync with the stroke being drawn.
+pub(super) fn update_lasso(
+    mut commands: Commands,
+    state: Res<DispelState>,
+    visuals: Res<DispelVisuals>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    layout: Res<CanvasLayout>,
+    camera_q: Query<(Entity, &Camera), With<FpsCamera>>,
+    mut stroke_q: Query<(&Mesh3d, &mut Visibility), (With<LassoStroke>, Without<ClosureGlow>)>,
+    mut glow_q: Query<(&mut Transform, &mut Visibility), (With<ClosureGlow>, Without<LassoStroke>)>,
+) {
+    let Ok((camera_entity, camera)) = camera_q.single() else {
+        return;
+    };
+
+    let Ok((stroke_mesh, mut stroke_visibility)) = stroke_q.single_mut() else {
+        // First frame with a camera: create both meshes under it
+        commands.entity(camera_entity).with_children(|parent| {
+            parent.spawn((
+                Mesh3d(meshes.add(empty_mesh())),
+                MeshMaterial3d(visuals.stroke_material.clone()),
+                Transform::default(),
+                Visibility::Hidden,
+                LassoStroke,
+            ));
+            parent.spawn((
+                Mesh3d(meshes.add(Rectangle::from_length(1.0))),
+                MeshMaterial3d(visuals.glow_material.clone()),
+                Transform::default(),
+                Visibility::Hidden,
+                ClosureGlow,
+            ));
+        });
+        return;
+    };
+
+    let drawing = state.active && state.points.len() >= 2;
+    *stroke_visibility = if drawing {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    if drawing
+        && let Some(mesh) = meshes.get_mut(&stroke_mesh.0)
+    {
+        // Trail the stroke to the cursor so it doesn't lag a segment behind
+        let mut points = state.points.clone();
+        if let Ok(window) = window_q.single()
+            && let Some(cursor) = window.cursor_position()
+        {
+            points.push(cursor);
+        }
+        *mesh = ribbon_mesh(camera, &layout, &points, Vec2::ZERO);
+    }
+
+    let Ok((mut glow_transform, mut glow_visibility)) = glow_q.single_mut() else {
+        return;
+    };
+    let progress = closure_progress(&state, window_q.single().ok().and_then(Window::cursor_position));
+    match (progress, state.points.first()) {
+        (Some(progress), Some(start)) if progress > 0.0 => {
+            let Some(centre) = to_view_space(camera, &layout, *start) else {
+                return;
+            };
+            let size = pixel_size_at_depth(camera, &layout, *start).unwrap_or(0.0) * GLOW_MAX_SIZE * progress;
+            // Nudge the glow behind the stroke so the line stays readable on top
+            glow_transform.translation = centre * 1.01;
+            glow_transform.scale = Vec3::splat(size);
+            *glow_visibility = Visibility::Inherited;
+        }
+        _ => *glow_visibility = Visibility::Hidden,
+    }
+}
+
+/// 0.0 while the cursor is far from the start point, rising to 1.0 when it is close enough
+/// to close the loop. `None` when no closable stroke is being drawn.
+fn closure_progress(state: &DispelState, cursor: Option<Vec2>) -> Option<f32> {
+    if !state.active || !state.drawing || state.points.len() < MIN_POINTS {
+        return None;
+    }
+    let distance = cursor?.distance(*state.points.first()?);
+    let range = CLOSURE_DISTANCE * GLOW_RANGE;
+    Some((1.0 - (distance - CLOSURE_DISTANCE) / (range - CLOSURE_DISTANCE)).clamp(0.0, 1.0))
+}
+
+/// Leave the latest failed stroke behind to jitter and shrink away, replacing any still fizzling.
+pub(super) fn spawn_fizzle(
+    mut commands: Commands,
+    mut failed: MessageReader<DispelFailed>,
+    visuals: Res<DispelVisuals>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    layout: Res<CanvasLayout>,
+    camera_q: Query<(Entity, &Camera), With<FpsCamera>>,
+    fizzle_q: Query<Entity, With<Fizzle>>,
+) {
+    let Ok((camera_entity, camera)) = camera_q.single() else {
+        return;
+    };
+    let Some(event) = failed.read().filter(|event| event.stroke.len() >= 2).last() else {
+        return;
+    };
+    let Some(mesh) = meshes.get_mut(&visuals.fizzle_mesh) else {
+        return;
+    };
+    *mesh = ribbon_mesh(camera, &layout, &event.stroke, Vec2::ZERO);
+    for entity in &fizzle_q {
+        commands.entity(entity).despawn();
+    }
+    commands.entity(camera_entity).with_children(|parent| {
+        parent.spawn((
+            Mesh3d(visuals.fizzle_mesh.clone()),
+            MeshMaterial3d(visuals.stroke_material.clone()),
+            Transform::default(),
+            Fizzle {
+                stroke: event.stroke.clone(),
+                timer: Timer::from_seconds(FIZZLE_SECS, TimerMode::Once),
+            },
+        ));
+    });
+}
+
+pub(super) fn animate_fizzle(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    layout: Res<CanvasLayout>,
+    camera_q: Query<&Camera, With<FpsCamera>>,
+    mut fizzle_q: Query<(Entity, &Mesh3d, &mut Fizzle)>,
+) {
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+    let mut rng = rand::rng();
+    for (entity, mesh, mut fizzle) in &mut fizzle_q {
+        fizzle.timer.tick(time.delta());
+        if fizzle.timer.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        // Drop more of the stroke and shake harder as it dies
+        let t = fizzle.timer.fraction();
+        let keep = ((1.0 - t) * fizzle.stroke.len() as f32).ceil() as usize;
+        let jitter = Vec2::new(
+            rng.random_range(-1.0..1.0),
+            rng.random_range(-1.0..1.0),
+        ) * FIZZLE_JITTER
+            * t;
+        let start = (fizzle.stroke.len() - keep.max(2)) / 2;
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = ribbon_mesh(
+                camera,
+                &layout,
+                &fizzle.stroke[start..start + keep.max(2)],
+                jitter,
+            );
+        }
+    }
+}
+
+/// Fly the sprite layers of dispelled aberrations apart while the whole thing collapses.
+pub(super) fn animate_dissolve(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dissolving_q: Query<(Entity, &mut Transform, &mut Dissolving, Option<&Children>)>,
+    mut shard_q: Query<(&mut Transform, Option<&Shard>), Without<Dissolving>>,
+) {
+    let mut rng = rand::rng();
+    for (entity, mut transform, mut dissolving, children) in &mut dissolving_q {
+        let first_frame = dissolving.timer.elapsed_secs() == 0.0;
+        dissolving.timer.tick(time.delta());
+        if dissolving.timer.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let t = dissolving.timer.fraction();
+        transform.scale.y = transform.scale.y.min(1.0 - t * t);
+        for &child in children.into_iter().flatten() {
+            let Ok((mut child_transform, shard)) = shard_q.get_mut(child) else {
+                continue;
+            };
+            match shard {
+                Some(shard) => child_transform.translation += shard.velocity * time.delta_secs(),
+                None if first_frame => {
+                    let dir = Vec2::from_angle(rng.random_range(0.0..std::f32::consts::TAU));
+                    commands.entity(child).insert(Shard {
+                        velocity: dir.extend(0.0) * SHARD_SPEED,
+                    });
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+pub(super) fn cleanup_effects(
+    mut commands: Commands,
+    dissolving_q: Query<Entity, Or<(With<Dissolving>, With<Fizzle>)>>,
+) {
+    commands.remove_resource::<DispelVisuals>();
+    for entity in &dissolving_q {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Camera-local position of a window pixel on the plane the lasso is drawn on.
+fn to_view_space(camera: &Camera, layout: &CanvasLayout, px: Vec2) -> Option<Vec3> {
+    let ray = camera
+        .viewport_to_world(&GlobalTransform::IDENTITY, layout.window_to_canvas(px))
+        .ok()?;
+    Some(ray.get_point(LASSO_DEPTH))
+}
+
+/// World size of one window pixel on the lasso plane.
+fn pixel_size_at_depth(camera: &Camera, layout: &CanvasLayout, px: Vec2) -> Option<f32> {
+    Some(to_view_space(camera, layout, px + Vec2::X)?.distance(to_view_space(camera, layout, px)?))
+}
+
+/// A flat ribbon `STROKE_HALF_WIDTH` pixels either side of the window-space polyline, in the
+/// camera's local space. `u` runs along the stroke so the texture repeats instead of stretching.
+fn ribbon_mesh(camera: &Camera, layout: &CanvasLayout, points: &[Vec2], offset: Vec2) -> Mesh {
+    let mut positions = Vec::with_capacity(points.len() * 2);
+    let mut uvs = Vec::with_capacity(points.len() * 2);
+    let mut length = 0.0;
+    for (i, point) in points.iter().enumerate() {
+        let prev = points[i.saturating_sub(1)];
+        let next = points[(i + 1).min(points.len() - 1)];
+        let normal = (next - prev).normalize_or_zero().perp() * STROKE_HALF_WIDTH;
+        if i > 0 {
+            length += point.distance(prev);
+        }
+        let u = length / STROKE_TEXTURE_LENGTH;
+        for (side, v) in [(normal, 0.0), (-normal, 1.0)] {
+            let Some(pos) = to_view_space(camera, layout, *point + offset + side) else {
+                return empty_mesh();
+            };
+            positions.push(pos.to_array());
+            uvs.push([u, v]);
+        }
+    }
+
+    let mut indices = Vec::with_capacity(points.len().saturating_sub(1) * 6);
+    for i in 0..points.len().saturating_sub(1) as u32 {
+        let (a, b, c, d) = (i * 2, i * 2 + 1, i * 2 + 2, i * 2 + 3);
+        indices.extend_from_slice(&[a, b, c, b, d, c]);
+    }
+
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+        .with_inserted_indices(Indices::U32(indices))
+}
+
+fn empty_mesh() -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, Vec::<[f32; 3]>::new())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, Vec::<[f32; 2]>::new())
+        .with_inserted_indices(Indices::U32(Vec::new()))
+}
+
+/// A rough ink line: solid through the middle with ragged, gappy edges. Sampled with nearest
+/// filtering and alpha-masked so it stays crisp on the canvas.
+fn stroke_texture() -> Image {
+    const W: u32 = 16;
+    const H: u32 = 6;
+    let mut data = Vec::with_capacity((W * H * 4) as usize);
+    for y in 0..H {
+        for x in 0..W {
+            let edge = y == 0 || y == H - 1;
+            // Fixed hash so the raggedness doesn't change between runs
+            let hash = (x.wrapping_mul(73) ^ y.wrapping_mul(151)) % 5;
+            let alpha = if edge && hash < 3 { 0 } else { 255 };
+            let shade = if edge { 200 } else { 255 };
+            data.extend_from_slice(&[shade, shade, shade, alpha]);
+        }
+    }
+    pixel_image(W, H, data, ImageAddressMode::Repeat)
+}
+
+/// Soft radial falloff for the closure glow.
+fn glow_texture() -> Image {
+    const SIZE: u32 = 16;
+    let mut data = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    let centre = Vec2::splat((SIZE - 1) as f32 / 2.0);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let d = Vec2::new(x as f32, y as f32).distance(centre) / centre.x;
+            let alpha = ((1.0 - d).clamp(0.0, 1.0).powi(2) * 255.0) as u8;
+            data.extend_from_slice(&[255, 255, 255, alpha]);
+        }
+    }
+    pixel_image(SIZE, SIZE, data, ImageAddressMode::ClampToEdge)
+}
+
+fn pixel_image(width: u32, height: u32, data: Vec<u8>, address_mode: ImageAddressMode) -> Image {
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: address_mode,
+        address_mode_v: address_mode,
+        ..ImageSamplerDescriptor::nearest()
+    });
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with_stroke(points: Vec<Vec2>) -> DispelState {
+        DispelState {
+            active: true,
+            drawing: true,
+            points,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn glow_grows_as_the_cursor_nears_the_start() {
+        let stroke: Vec<Vec2> = (0..MIN_POINTS).map(|i| Vec2::new(i as f32 * 20.0, 0.0)).collect();
+        let state = state_with_stroke(stroke);
+
+        let far = closure_progress(&state, Some(Vec2::new(0.0, CLOSURE_DISTANCE * GLOW_RANGE)));
+        let near = closure_progress(&state, Some(Vec2::new(0.0, CLOSURE_DISTANCE * 2.0)));
+        let closed = closure_progress(&state, Some(Vec2::new(0.0, CLOSURE_DISTANCE)));
+        assert_eq!(far, Some(0.0));
+        assert!(near.unwrap() > 0.0 && near.unwrap() < 1.0);
+        assert_eq!(closed, Some(1.0));
+
+        let short = state_with_stroke(vec![Vec2::ZERO; 2]);
+        assert_eq!(closure_progress(&short, Some(Vec2::ZERO)), None);
+    }
+}
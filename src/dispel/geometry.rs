@@ -0,0 +1,373 @@
+//! Pure 2D geometry behind the dispel lasso.
+//!
+//! Everything here works on plain `Vec2` slices in window pixel coordinates (y pointing down)
+//! and knows nothing about the ECS, so it can be tested in isolation.
+
+use bevy::math::{Rect, Vec2};
+
+/// Orientation of a closed polygon as seen on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+    /// Zero area: fewer than three points, or all of them collinear.
+    Degenerate,
+}
+
+/// Whether `pos` is far enough from the last recorded sample to be added to the stroke.
+/// Prevents over-sampling when the player holds the cursor still.
+pub fn should_sample(points: &[Vec2], pos: Vec2, min_distance: f32) -> bool {
+    points
+        .last()
+        .is_none_or(|last| pos.distance(*last) > min_distance)
+}
+
+/// Whether the stroke has enough samples and the cursor has come back to its first point.
+pub fn is_closed(points: &[Vec2], cursor: Vec2, closure_distance: f32, min_points: usize) -> bool {
+    points.len() >= min_points
+        && points
+            .first()
+            .is_some_and(|first| cursor.distance(*first) <= closure_distance)
+}
+
+/// Shoelace area of the closed polygon. Positive when the points run clockwise on screen.
+pub fn signed_area(polygon: &[Vec2]) -> f32 {
+    edges(polygon).map(|(a, b)| a.perp_dot(b)).sum::<f32>() * 0.5
+}
+
+pub fn area(polygon: &[Vec2]) -> f32 {
+    signed_area(polygon).abs()
+}
+
+pub fn winding(polygon: &[Vec2]) -> Winding {
+    let area = signed_area(polygon);
+    if area > f32::EPSILON {
+        Winding::Clockwise
+    } else if area < -f32::EPSILON {
+        Winding::CounterClockwise
+    } else {
+        Winding::Degenerate
+    }
+}
+
+/// Ramer–Douglas–Peucker simplification of an open polyline. Both endpoints are always kept and
+/// every dropped point lies within `epsilon` of the result.
+pub fn simplify(points: &[Vec2], epsilon: f32) -> Vec<Vec2> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let (a, b) = (points[start], points[end]);
+        let farthest = (start + 1..end)
+            .map(|i| (i, distance_to_segment(points[i], a, b)))
+            .max_by(|(_, da), (_, db)| da.total_cmp(db));
+
+        if let Some((i, dist)) = farthest
+            && dist > epsilon
+        {
+            keep[i] = true;
+            stack.push((start, i));
+            stack.push((i, end));
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(p, k)| k.then_some(*p))
+        .collect()
+}
+
+/// Shortest distance from `p` to the segment `a`–`b`.
+pub fn distance_to_segment(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len_sq = ab.length_squared();
+    if len_sq <= f32::EPSILON {
+        return p.distance(a);
+    }
+    let t = ((p - a).dot(ab) / len_sq).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+/// Crossing point of segments `a`–`b` and `c`–`d`, if they properly intersect.
+/// Touching at an endpoint and overlapping collinear segments don't count.
+pub fn segment_intersection(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> Option<Vec2> {
+    let r = b - a;
+    let s = d - c;
+    let denom = r.perp_dot(s);
+    if denom.abs() <= f32::EPSILON {
+        return None;
+    }
+    let t = (c - a).perp_dot(s) / denom;
+    let u = (c - a).perp_dot(r) / denom;
+    (t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0).then(|| a + r * t)
+}
+
+/// Every point where two non-adjacent edges of the closed polygon cross each other.
+pub fn self_intersections(polygon: &[Vec2]) -> Vec<Vec2> {
+    let n = polygon.len();
+    let mut crossings = Vec::new();
+    if n < 4 {
+        return crossings;
+    }
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        // Skip the neighbouring edges, which always share an endpoint with this one
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (c, d) = (polygon[j], polygon[(j + 1) % n]);
+            if let Some(p) = segment_intersection(a, b, c, d) {
+                crossings.push(p);
+            }
+        }
+    }
+    crossings
+}
+
+/// True when no two edges of the closed polygon cross.
+pub fn is_simple(polygon: &[Vec2]) -> bool {
+    self_intersections(polygon).is_empty()
+}
+
+/// How many times the closed polygon winds around `point`. The sign follows `Winding`:
+/// positive for clockwise loops on screen.
+pub fn winding_number(point: Vec2, polygon: &[Vec2]) -> i32 {
+    let mut wn = 0;
+    for (a, b) in edges(polygon) {
+        let side = (b - a).perp_dot(point - a);
+        if a.y <= point.y {
+            if b.y > point.y && side > 0.0 {
+                wn += 1;
+            }
+        } else if b.y <= point.y && side < 0.0 {
+            wn -= 1;
+        }
+    }
+    wn
+}
+
+/// Non-zero rule containment. Unlike even-odd ray casting, regions a looping stroke wraps
+/// twice still count as inside.
+pub fn contains_point(polygon: &[Vec2], point: Vec2) -> bool {
+    winding_number(point, polygon) != 0
+}
+
+/// True when `rect` lies entirely inside the polygon: every corner is enclosed and no edge of
+/// the polygon cuts through it.
+pub fn contains_rect(polygon: &[Vec2], rect: Rect) -> bool {
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+    if !corners.iter().all(|c| contains_point(polygon, *c)) {
+        return false;
+    }
+    let rect_edges = [
+        (corners[0], corners[1]),
+        (corners[1], corners[2]),
+        (corners[2], corners[3]),
+        (corners[3], corners[0]),
+    ];
+    !edges(polygon).any(|(a, b)| {
+        rect.contains(a)
+            || rect_edges
+                .iter()
+                .any(|(c, d)| segment_intersection(a, b, *c, *d).is_some())
+    })
+}
+
+/// Estimated fraction (0.0–1.0) of `rect` enclosed by the polygon, sampled on a
+/// `samples` × `samples` grid of cell centres.
+pub fn rect_coverage(polygon: &[Vec2], rect: Rect, samples: u32) -> f32 {
+    let samples = samples.max(1);
+    let size = rect.size();
+    let mut inside = 0;
+    for y in 0..samples {
+        for x in 0..samples {
+            let uv = (Vec2::new(x as f32, y as f32) + 0.5) / samples as f32;
+            if contains_point(polygon, rect.min + uv * size) {
+                inside += 1;
+            }
+        }
+    }
+    inside as f32 / (samples * samples) as f32
+}
+
+/// Axis-aligned bounding box of the points, `None` when there are none.
+pub fn bounds(points: &[Vec2]) -> Option<Rect> {
+    let (first, rest) = points.split_first()?;
+    Some(rest.iter().fold(Rect::from_corners(*first, *first), |r, p| r.union_point(*p)))
+}
+
+/// Edges of the closed polygon, including the one from the last point back to the first.
+fn edges(polygon: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let n = polygon.len();
+    (0..n).map(move |i| (polygon[i], polygon[(i + 1) % n]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Regular `n`-gon around `centre`, clockwise on screen.
+    fn ngon(centre: Vec2, radius: f32, n: usize) -> Vec<Vec2> {
+        (0..n)
+            .map(|i| centre + Vec2::from_angle(i as f32 / n as f32 * std::f32::consts::TAU) * radius)
+            .collect()
+    }
+
+    fn bowtie() -> Vec<Vec2> {
+        vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(0.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn square_area_and_winding() {
+        let square = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+        ];
+        assert_eq!(signed_area(&square), 100.0);
+        assert_eq!(winding(&square), Winding::Clockwise);
+        assert_eq!(winding(&square[..2]), Winding::Degenerate);
+    }
+
+    #[test]
+    fn bounds_span_every_point() {
+        assert_eq!(bounds(&[]), None);
+        assert_eq!(
+            bounds(&bowtie()),
+            Some(Rect::from_corners(Vec2::ZERO, Vec2::splat(10.0)))
+        );
+    }
+
+    #[test]
+    fn bowtie_crosses_once() {
+        let crossings = self_intersections(&bowtie());
+        assert_eq!(crossings.len(), 1);
+        assert!(crossings[0].distance(Vec2::splat(5.0)) < 1e-4);
+        assert!(!is_simple(&bowtie()));
+    }
+
+    #[test]
+    fn double_loop_encloses_centre_twice() {
+        let mut stroke = ngon(Vec2::ZERO, 10.0, 16);
+        stroke.extend(ngon(Vec2::ZERO, 12.0, 16));
+        assert_eq!(winding_number(Vec2::ZERO, &stroke), 2);
+        assert!(contains_point(&stroke, Vec2::ZERO));
+    }
+
+    #[test]
+    fn rect_straddling_the_edge_is_partially_covered() {
+        let square = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+        ];
+        let rect = Rect::new(5.0, 2.0, 15.0, 8.0);
+        assert!(!contains_rect(&square, rect));
+        assert!((rect_coverage(&square, rect, 8) - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn closure_needs_enough_points_near_the_start() {
+        let points: Vec<Vec2> = (0..10).map(|i| Vec2::new(i as f32 * 10.0, 0.0)).collect();
+        assert!(is_closed(&points, Vec2::new(3.0, 4.0), 30.0, 10));
+        assert!(!is_closed(&points, Vec2::new(31.0, 0.0), 30.0, 10));
+        assert!(!is_closed(&points[..9], Vec2::ZERO, 30.0, 10));
+        assert!(!should_sample(&points, Vec2::new(92.0, 0.0), 5.0));
+        assert!(should_sample(&points, Vec2::new(96.0, 0.0), 5.0));
+    }
+
+    /// Whole-pixel coordinates, so translating them stays exact in `f32`.
+    fn point() -> impl Strategy<Value = Vec2> {
+        (-500i32..500, -500i32..500).prop_map(|(x, y)| Vec2::new(x as f32, y as f32))
+    }
+
+    fn polyline() -> impl Strategy<Value = Vec<Vec2>> {
+        prop::collection::vec(point(), 0..40)
+    }
+
+    proptest! {
+        #[test]
+        fn simplify_keeps_endpoints_and_stays_within_epsilon(
+            points in polyline(),
+            epsilon in 0.5f32..50.0,
+        ) {
+            let simplified = simplify(&points, epsilon);
+            prop_assert!(simplified.len() <= points.len());
+            prop_assert_eq!(simplified.first(), points.first());
+            prop_assert_eq!(simplified.last(), points.last());
+            if simplified.len() >= 2 {
+                for p in &points {
+                    let dist = simplified
+                        .windows(2)
+                        .map(|w| distance_to_segment(*p, w[0], w[1]))
+                        .fold(f32::INFINITY, f32::min);
+                    prop_assert!(dist <= epsilon + 1e-3);
+                }
+            }
+        }
+
+        #[test]
+        fn reversing_flips_area_and_winding_number(polygon in polyline(), p in point()) {
+            let reversed: Vec<Vec2> = polygon.iter().rev().copied().collect();
+            let scale = 1.0 + area(&polygon);
+            prop_assert!((signed_area(&polygon) + signed_area(&reversed)).abs() <= scale * 1e-4);
+            prop_assert_eq!(winding_number(p, &polygon), -winding_number(p, &reversed));
+        }
+
+        #[test]
+        fn translation_preserves_area_and_containment(
+            polygon in polyline(),
+            p in point(),
+            offset in point(),
+        ) {
+            let moved: Vec<Vec2> = polygon.iter().map(|v| *v + offset).collect();
+            let scale = 1.0 + area(&polygon) + offset.length() * polygon.len() as f32 * 1000.0;
+            prop_assert!((area(&polygon) - area(&moved)).abs() <= scale * 1e-5);
+            prop_assert_eq!(contains_point(&polygon, p), contains_point(&moved, p + offset));
+        }
+
+        #[test]
+        fn convex_ngon_contains_its_interior(
+            centre in point(),
+            radius in 20.0f32..300.0,
+            n in 3usize..32,
+            angle in 0.0f32..std::f32::consts::TAU,
+            t in 0.0f32..0.45,
+        ) {
+            let polygon = ngon(centre, radius, n);
+            // The inscribed circle of a regular n-gon has radius r·cos(π/n) ≥ r/2
+            let inner = centre + Vec2::from_angle(angle) * radius * t;
+            let outer = centre + Vec2::from_angle(angle) * radius * 1.01;
+            prop_assert!(contains_point(&polygon, inner));
+            prop_assert!(!contains_point(&polygon, outer));
+            prop_assert!(is_simple(&polygon));
+            prop_assert_eq!(winding(&polygon), Winding::Clockwise);
+
+            let half = Vec2::splat(radius * 0.25);
+            let rect = Rect::from_corners(centre - half, centre + half);
+            prop_assert!(contains_rect(&polygon, rect));
+            prop_assert_eq!(rect_coverage(&polygon, rect, 4), 1.0);
+        }
+    }
+}
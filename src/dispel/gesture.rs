@@ -0,0 +1,306 @@
+//! Sigil recognition for dispel strokes.
+//!
+//! A template matcher after the $1 unistroke recognizer (Wobbrock, Wilson & Li, 2007): strokes
+//! are resampled, rotated by their indicative angle, scaled to a square and compared point by
+//! point against every template at the best rotation within ±`ANGLE_RANGE`.
+
+use super::geometry;
+use bevy::math::Vec2;
+use serde::Deserialize;
+use std::f32::consts::FRAC_PI_4;
+
+/// Number of equidistant points each stroke is resampled to.
+const RESAMPLE_POINTS: usize = 64;
+/// Side of the reference square strokes are scaled into.
+const SQUARE_SIZE: f32 = 250.0;
+/// Rotation search range around the indicative angle.
+const ANGLE_RANGE: f32 = FRAC_PI_4;
+/// Golden-section search stops once the bracket is narrower than this.
+const ANGLE_PRECISION: f32 = 0.035;
+/// Golden ratio minus one, for the golden-section search.
+const PHI: f32 = 0.618_034;
+
+// --- RON data ---
+
+#[derive(Deserialize)]
+struct SigilsRon {
+    sigils: Vec<SigilRon>,
+}
+
+#[derive(Deserialize)]
+struct SigilRon {
+    name: String,
+    points: Vec<(f32, f32)>,
+}
+
+/// Best template for a stroke, with a score from 0.0 (nothing alike) to 1.0 (identical).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigilMatch {
+    pub name: String,
+    pub score: f32,
+}
+
+struct Template {
+    name: String,
+    points: Vec<Vec2>,
+}
+
+/// A set of normalized sigil templates. Each template is stored in both drawing directions,
+/// so a circle traced clockwise or counter-clockwise matches the same sigil.
+pub struct SigilRecognizer {
+    templates: Vec<Template>,
+}
+
+impl SigilRecognizer {
+    pub fn new<'a>(templates: impl IntoIterator<Item = (&'a str, &'a [Vec2])>) -> Self {
+        let mut normalized = Vec::new();
+        for (name, points) in templates {
+            let Some(forward) = normalize(points) else {
+                continue;
+            };
+            let reversed: Vec<Vec2> = points.iter().rev().copied().collect();
+            normalized.push(Template {
+                name: name.to_string(),
+                points: forward,
+            });
+            if let Some(backward) = normalize(&reversed) {
+                normalized.push(Template {
+                    name: name.to_string(),
+                    points: backward,
+                });
+            }
+        }
+        Self {
+            templates: normalized,
+        }
+    }
+
+    /// Parse a sigil template file (see `assets/defs/sigils.ron`).
+    pub fn from_ron(source: &str) -> Result<Self, ron::error::SpannedError> {
+        let data: SigilsRon = ron::from_str(source)?;
+        let sigils: Vec<(String, Vec<Vec2>)> = data
+            .sigils
+            .into_iter()
+            .map(|s| (s.name, s.points.into_iter().map(Vec2::from).collect()))
+            .collect();
+        Ok(Self::new(
+            sigils.iter().map(|(name, points)| (name.as_str(), points.as_slice())),
+        ))
+    }
+
+    /// Classify `stroke`, returning the closest template. `None` when the stroke is too short or
+    /// too small to say anything about, or no templates are loaded.
+    pub fn recognize(&self, stroke: &[Vec2]) -> Option<SigilMatch> {
+        let candidate = normalize(stroke)?;
+        let half_diagonal = 0.5 * (2.0 * SQUARE_SIZE * SQUARE_SIZE).sqrt();
+
+        self.templates
+            .iter()
+            .map(|t| (t, distance_at_best_angle(&candidate, &t.points)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(t, dist)| SigilMatch {
+                name: t.name.clone(),
+                score: 1.0 - dist / half_diagonal,
+            })
+    }
+}
+
+/// Resample, rotate, scale and translate `points` into the reference frame templates live in.
+fn normalize(points: &[Vec2]) -> Option<Vec<Vec2>> {
+    let resampled = resample(points, RESAMPLE_POINTS)?;
+    let rotated = rotate_by(&resampled, -indicative_angle(&resampled));
+    let bounds = geometry::bounds(&rotated)?;
+    let size = bounds.size();
+    if size.max_element() <= f32::EPSILON {
+        return None;
+    }
+    // Scale uniformly by the longer side so straight-ish sigils like zigzags don't blow up
+    let scale = SQUARE_SIZE / size.max_element();
+    let scaled: Vec<Vec2> = rotated.iter().map(|p| (*p - bounds.min) * scale).collect();
+    let centre = centroid(&scaled);
+    Some(scaled.iter().map(|p| *p - centre).collect())
+}
+
+/// Resample a polyline into `n` points evenly spaced along its length.
+fn resample(points: &[Vec2], n: usize) -> Option<Vec<Vec2>> {
+    let length: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+    if points.len() < 2 || length <= f32::EPSILON {
+        return None;
+    }
+
+    let interval = length / (n - 1) as f32;
+    let mut out = vec![points[0]];
+    let mut carried = 0.0;
+    let mut prev = points[0];
+    let mut i = 1;
+    while i < points.len() {
+        let next = points[i];
+        let d = prev.distance(next);
+        if carried + d >= interval && d > 0.0 {
+            let q = prev + (next - prev) * ((interval - carried) / d);
+            out.push(q);
+            // The new point becomes the start of the remaining segment
+            prev = q;
+            carried = 0.0;
+        } else {
+            carried += d;
+            prev = next;
+            i += 1;
+        }
+    }
+    // Rounding can leave us one short
+    while out.len() < n {
+        out.push(*points.last().unwrap());
+    }
+    out.truncate(n);
+    Some(out)
+}
+
+fn centroid(points: &[Vec2]) -> Vec2 {
+    points.iter().copied().sum::<Vec2>() / points.len() as f32
+}
+
+/// Angle from the centroid to the first point.
+fn indicative_angle(points: &[Vec2]) -> f32 {
+    let c = centroid(points);
+    let d = points[0] - c;
+    d.y.atan2(d.x)
+}
+
+fn rotate_by(points: &[Vec2], angle: f32) -> Vec<Vec2> {
+    let c = centroid(points);
+    let rot = Vec2::from_angle(angle);
+    points.iter().map(|p| c + rot.rotate(*p - c)).collect()
+}
+
+fn path_distance(a: &[Vec2], b: &[Vec2]) -> f32 {
+    a.iter().zip(b).map(|(p, q)| p.distance(*q)).sum::<f32>() / a.len() as f32
+}
+
+/// Golden-section search for the rotation of `candidate` that best matches `template`.
+fn distance_at_best_angle(candidate: &[Vec2], template: &[Vec2]) -> f32 {
+    let dist_at = |angle: f32| path_distance(&rotate_by(candidate, angle), template);
+
+    let (mut lo, mut hi) = (-ANGLE_RANGE, ANGLE_RANGE);
+    let mut x1 = PHI * lo + (1.0 - PHI) * hi;
+    let mut x2 = (1.0 - PHI) * lo + PHI * hi;
+    let mut f1 = dist_at(x1);
+    let mut f2 = dist_at(x2);
+    while (hi - lo).abs() > ANGLE_PRECISION {
+        if f1 < f2 {
+            hi = x2;
+            x2 = x1;
+            f2 = f1;
+            x1 = PHI * lo + (1.0 - PHI) * hi;
+            f1 = dist_at(x1);
+        } else {
+            lo = x1;
+            x1 = x2;
+            f1 = f2;
+            x2 = (1.0 - PHI) * lo + PHI * hi;
+            f2 = dist_at(x2);
+        }
+    }
+    f1.min(f2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SIGILS_RON: &str = include_str!("../../assets/defs/sigils.ron");
+
+    fn recognizer() -> SigilRecognizer {
+        SigilRecognizer::from_ron(SIGILS_RON).expect("sigils.ron parses")
+    }
+
+    /// A wobbly hand-drawn take on the polyline through `corners`.
+    fn hand_drawn(corners: &[Vec2], per_edge: usize) -> Vec<Vec2> {
+        let mut out = Vec::new();
+        for (e, pair) in corners.windows(2).enumerate() {
+            for i in 0..per_edge {
+                let t = i as f32 / per_edge as f32;
+                let wobble = Vec2::new(((i + e) as f32 * 1.7).sin(), ((i * 3 + e) as f32 * 0.9).sin()) * 4.0;
+                out.push(pair[0].lerp(pair[1], t) + wobble);
+            }
+        }
+        out.push(*corners.last().unwrap());
+        out
+    }
+
+    fn best(stroke: &[Vec2]) -> SigilMatch {
+        recognizer().recognize(stroke).expect("stroke is long enough")
+    }
+
+    #[test]
+    fn recognizes_each_sigil_when_drawn_by_hand() {
+        let centre = Vec2::new(640.0, 360.0);
+        let circle: Vec<Vec2> = (0..=40)
+            .map(|i| centre + Vec2::from_angle(i as f32 / 40.0 * TAU) * 150.0)
+            .collect();
+        let triangle = hand_drawn(
+            &[
+                centre + Vec2::new(0.0, -120.0),
+                centre + Vec2::new(-104.0, 60.0),
+                centre + Vec2::new(104.0, 60.0),
+                centre + Vec2::new(0.0, -120.0),
+            ],
+            10,
+        );
+        let spiral: Vec<Vec2> = (0..=60)
+            .map(|i| {
+                let t = i as f32 / 60.0;
+                centre + Vec2::from_angle(t * 2.0 * TAU) * (10.0 + 140.0 * t)
+            })
+            .collect();
+        let zigzag = hand_drawn(
+            &[
+                Vec2::new(300.0, 300.0),
+                Vec2::new(360.0, 400.0),
+                Vec2::new(420.0, 300.0),
+                Vec2::new(480.0, 400.0),
+                Vec2::new(540.0, 300.0),
+                Vec2::new(600.0, 400.0),
+            ],
+            8,
+        );
+
+        for (name, stroke) in [
+            ("circle", circle),
+            ("triangle", triangle),
+            ("spiral", spiral),
+            ("zigzag", zigzag),
+        ] {
+            let found = best(&stroke);
+            assert_eq!(found.name, name, "{found:?}");
+            assert!(found.score > 0.8, "{found:?}");
+        }
+    }
+
+    #[test]
+    fn matching_ignores_direction_scale_and_position() {
+        let circle: Vec<Vec2> = (0..=32)
+            .map(|i| Vec2::new(40.0, 900.0) + Vec2::from_angle(-(i as f32) / 32.0 * TAU) * 12.0)
+            .collect();
+        let found = best(&circle);
+        assert_eq!(found.name, "circle");
+        assert!(found.score > 0.9, "{found:?}");
+    }
+
+    #[test]
+    fn degenerate_strokes_are_not_classified() {
+        let r = recognizer();
+        assert_eq!(r.recognize(&[]), None);
+        assert_eq!(r.recognize(&[Vec2::ONE]), None);
+        assert_eq!(r.recognize(&[Vec2::ONE; 10]), None);
+    }
+
+    #[test]
+    fn scribbles_score_lower_than_clean_sigils() {
+        let scribble: Vec<Vec2> = (0..50)
+            .map(|i| Vec2::new((i * 37 % 101) as f32, (i * 53 % 89) as f32) * 3.0)
+            .collect();
+        assert!(best(&scribble).score < 0.8);
+    }
+}
@@ -0,0 +1,831 @@
+use crate::GameState;
+use crate::aberration::{Aberration, AberrationSize, RequiredSigil};
+use crate::dialog::dialog_not_active;
+use crate::loading::{AudioAssets, TextureAssets};
+use crate::health::Health;
+use crate::pause::game_not_paused;
+use crate::player::FpsCamera;
+use crate::scaling::CanvasLayout;
+use bevy::prelude::*;
+use bevy::window::{
+    CursorGrabMode, CursorIcon, CursorOptions, CustomCursor, CustomCursorImage, PrimaryWindow,
+    SystemCursorIcon,
+};
+use bevy_kira_audio::{Audio, AudioControl};
+
+mod effects;
+pub mod geometry;
+pub mod gesture;
+
+use gesture::SigilRecognizer;
+
+const SIGILS_RON: &str = include_str!("../../assets/defs/sigils.ron");
+
+pub struct DispelPlugin;
+
+impl Plugin for DispelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Dispelled>()
+            .add_message::<DispelFailed>()
+            .add_systems(OnEnter(GameState::Playing), (init_dispel, effects::init_visuals))
+            .add_systems(
+                Update,
+                (
+                    tick_cooldown,
+                    toggle_dispel,
+                    banish_with_sigil,
+                    dispel_draw,
+                    check_closure_and_dispel,
+                    exit_dispel_on_right_click,
+                    drain_while_active.run_if(resource_exists::<Health>),
+                    effects::update_lasso,
+                    effects::spawn_fizzle,
+                    effects::animate_fizzle,
+                    effects::animate_dissolve,
+                    reward_dispel.run_if(resource_exists::<Health>),
+                    play_dispel_sound,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing).and(game_not_paused).and(dialog_not_active)),
+            )
+            .add_systems(
+                Update,
+                cancel_dispel_on_keypress.run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                (cleanup_dispel, effects::cleanup_effects),
+            );
+    }
+}
+
+/// How long the player must wait between adding points to the current polygon.
+const SEGMENT_INTERVAL: f32 = 0.05;
+/// Maximum distance from the starting point for the polygon to be considered closed (in viewport pixels).
+const CLOSURE_DISTANCE: f32 = 30.0;
+/// Minimum number of points required to attempt closure and dispel. This prevents accidental clicks from doing anything.
+const MIN_POINTS: usize = 10;
+/// Minimum distance between points to add a new one, to prevent over-sampling when the player holds still.
+const MIN_POINT_DISTANCE: f32 = 5.0;
+/// Tolerance when simplifying the finished lasso before testing it, in window pixels.
+const SIMPLIFY_EPSILON: f32 = 2.0;
+/// Fraction of an aberration's projected sprite the lasso must cover when it misses the centre.
+const MIN_COVERAGE: f32 = 0.5;
+/// Grid resolution used to estimate sprite coverage.
+const COVERAGE_SAMPLES: u32 = 8;
+/// Lowest recognizer score (0.0–1.0) that still counts as drawing a sigil.
+const MIN_SIGIL_SCORE: f32 = 0.8;
+/// Sanity spent on entering dispel mode.
+const ENTER_COST: f32 = 0.02;
+/// Sanity drained per second while dispel mode is held.
+const HOLD_DRAIN: f32 = 0.03;
+/// How long dispel mode is locked out after a loop that catches nothing.
+const FAILED_COOLDOWN_SECS: f32 = 2.0;
+/// Sanity restored per aberration dispelled, before the combo multiplier.
+const DISPEL_HEAL: f32 = 0.05;
+/// Extra multiplier for each aberration caught beyond the first in a single loop.
+const COMBO_STEP: f32 = 0.5;
+
+/// Sent when a lasso or sigil stroke dispels one or more aberrations.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct Dispelled {
+    pub count: usize,
+    /// Reward multiplier for catching several aberrations at once; 1.0 for a single one.
+    pub multiplier: f32,
+    /// Sigil the stroke was recognized as, if any.
+    pub sigil: Option<String>,
+}
+
+impl Dispelled {
+    pub fn new(count: usize, sigil: Option<String>) -> Self {
+        Self {
+            count,
+            multiplier: 1.0 + COMBO_STEP * count.saturating_sub(1) as f32,
+            sigil,
+        }
+    }
+}
+
+/// Why a dispel stroke came to nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailReason {
+    /// The loop closed around nothing; dispel mode goes on cooldown.
+    Empty,
+    /// The stroke was released or cancelled before closing.
+    Abandoned,
+}
+
+/// Sent when a dispel stroke fails, carrying the stroke in window pixels.
+#[derive(Message, Debug, Clone, PartialEq)]
+pub struct DispelFailed {
+    pub reason: FailReason,
+    pub stroke: Vec<Vec2>,
+}
+
+impl DispelFailed {
+    /// The stroke currently being drawn, as an abandoned attempt. `None` when not drawing.
+    fn abandoned(state: &DispelState) -> Option<Self> {
+        (state.drawing && !state.points.is_empty()).then(|| Self {
+            reason: FailReason::Abandoned,
+            stroke: state.points.clone(),
+        })
+    }
+}
+
+#[derive(Resource)]
+pub struct DispelState {
+    pub active: bool,
+    /// Seconds until dispel mode can be entered again.
+    pub cooldown: f32,
+    drawing: bool,
+    points: Vec<Vec2>,
+    segment_timer: Timer,
+}
+
+impl Default for DispelState {
+    fn default() -> Self {
+        Self {
+            active: false,
+            cooldown: 0.0,
+            drawing: false,
+            points: Vec::new(),
+            segment_timer: Timer::from_seconds(SEGMENT_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+fn cancel_dispel_on_keypress(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<DispelState>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    window_entity_q: Query<Entity, With<PrimaryWindow>>,
+    mut failed: MessageWriter<DispelFailed>,
+) {
+    if state.active && keyboard.get_just_pressed().len() > 0 {
+        if let Some(abandoned) = DispelFailed::abandoned(&state) {
+            failed.write(abandoned);
+        }
+        deactivate_dispel(&mut commands, &mut state, &mut cursor_q, &window_entity_q);
+    }
+}
+
+/// Templates the player's strokes are classified against.
+#[derive(Resource)]
+struct Sigils(SigilRecognizer);
+
+impl Sigils {
+    /// Name of the sigil `stroke` was drawn as, if it is a confident match.
+    fn recognize(&self, stroke: &[Vec2]) -> Option<String> {
+        self.0
+            .recognize(stroke)
+            .filter(|m| m.score >= MIN_SIGIL_SCORE)
+            .map(|m| m.name)
+    }
+}
+
+fn init_dispel(mut commands: Commands) {
+    commands.insert_resource(DispelState::default());
+    let sigils = SigilRecognizer::from_ron(SIGILS_RON).expect("Failed to parse sigils.ron");
+    commands.insert_resource(Sigils(sigils));
+}
+
+fn toggle_dispel(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<DispelState>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    window_q: Query<(Entity, &Window), With<PrimaryWindow>>,
+    textures: Res<TextureAssets>,
+    health: Option<ResMut<Health>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    if !state.active {
+        if state.cooldown > 0.0 {
+            return;
+        }
+        // Enter dispel mode, costing health if there is any to lose
+        if let Some(mut health) = health {
+            health.damage(ENTER_COST);
+        }
+        state.active = true;
+        state.drawing = false;
+        state.points.clear();
+        if let Ok(mut cursor) = cursor_q.single_mut() {
+            cursor.grab_mode = CursorGrabMode::None;
+            cursor.visible = true;
+        }
+        // Set custom cursor
+        if let Ok((window_entity, _)) = window_q.single() {
+            commands.entity(window_entity).insert(CursorIcon::Custom(
+                CustomCursor::Image(CustomCursorImage {
+                    handle: textures.feather_cursor.clone(),
+                    texture_atlas: None,
+                    flip_x: false,
+                    flip_y: false,
+                    rect: None,
+                    hotspot: (0, 0),
+                }),
+            ));
+        }
+    } else if !state.drawing {
+        // Start drawing
+        state.drawing = true;
+        state.points.clear();
+        state.segment_timer.reset();
+        if let Ok((_, window)) = window_q.single()
+            && let Some(pos) = window.cursor_position()
+        {
+            state.points.push(pos);
+        }
+    }
+}
+
+/// An open stroke drawn as a sigil banishes the aberrations requiring it that it was drawn over.
+fn banish_with_sigil(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<DispelState>,
+    window_entity_q: Query<Entity, With<PrimaryWindow>>,
+    aberration_q: Query<(Entity, &GlobalTransform, &RequiredSigil), With<Aberration>>,
+    camera_q: Query<(&Camera, &GlobalTransform), With<FpsCamera>>,
+    layout: Res<CanvasLayout>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    sigils: Res<Sigils>,
+    mut dispelled: MessageWriter<Dispelled>,
+) {
+    if !state.active
+        || !state.drawing
+        || state.points.len() < MIN_POINTS
+        || !mouse.just_released(MouseButton::Left)
+    {
+        return;
+    }
+
+    let Some(sigil) = sigils.recognize(&state.points) else {
+        return;
+    };
+    let Some(stroke_bounds) = geometry::bounds(&state.points) else {
+        return;
+    };
+    let Ok((camera, cam_transform)) = camera_q.single() else {
+        return;
+    };
+
+    let mut banished = 0;
+    for (entity, ab_transform, required) in &aberration_q {
+        if required.0 == sigil
+            && let Ok(viewport_pos) =
+                camera.world_to_viewport(cam_transform, ab_transform.translation())
+            && stroke_bounds.contains(layout.canvas_to_window(viewport_pos))
+        {
+            effects::dissolve(&mut commands, entity);
+            banished += 1;
+        }
+    }
+
+    // A sigil that hits nothing is just a released stroke; `dispel_draw` discards it
+    if banished > 0 {
+        dispelled.write(Dispelled::new(banished, Some(sigil)));
+        deactivate_dispel(&mut commands, &mut state, &mut cursor_q, &window_entity_q);
+    }
+}
+
+fn dispel_draw(
+    mut state: ResMut<DispelState>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut failed: MessageWriter<DispelFailed>,
+) {
+    if !state.active || !state.drawing {
+        return;
+    }
+
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(abandoned) = DispelFailed::abandoned(&state) {
+            failed.write(abandoned);
+        }
+        state.drawing = false;
+        state.points.clear();
+        return;
+    }
+
+    if !mouse.pressed(MouseButton::Left) {
+        return;
+    }
+
+    state.segment_timer.tick(time.delta());
+    if state.segment_timer.just_finished()
+        && let Ok(window) = window_q.single()
+        && let Some(pos) = window.cursor_position()
+        && geometry::should_sample(&state.points, pos, MIN_POINT_DISTANCE)
+    {
+        state.points.push(pos);
+    }
+}
+
+fn check_closure_and_dispel(
+    mut commands: Commands,
+    mut state: ResMut<DispelState>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    window_entity_q: Query<Entity, With<PrimaryWindow>>,
+    aberration_q: Query<
+        (Entity, &GlobalTransform, Option<&AberrationSize>, Option<&RequiredSigil>),
+        With<Aberration>,
+    >,
+    camera_q: Query<(&Camera, &GlobalTransform), With<FpsCamera>>,
+    layout: Res<CanvasLayout>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    sigils: Res<Sigils>,
+    mut dispelled: MessageWriter<Dispelled>,
+    mut failed: MessageWriter<DispelFailed>,
+) {
+    if !state.active || !state.drawing {
+        return;
+    }
+
+    let Ok(window) = window_q.single() else {
+        return;
+    };
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+
+    if !geometry::is_closed(&state.points, cursor_pos, CLOSURE_DISTANCE, MIN_POINTS) {
+        return;
+    }
+
+    // Loop closed — push final point to complete the polygon
+    state.points.push(cursor_pos);
+    let lasso = geometry::simplify(&state.points, SIMPLIFY_EPSILON);
+    let sigil = sigils.recognize(&state.points);
+
+    // Check each aberration against the polygon
+    let mut caught_count = 0;
+    if let Ok((camera, cam_transform)) = camera_q.single() {
+        for (entity, ab_transform, size, required) in &aberration_q {
+            if let Some(required) = required
+                && sigil.as_deref() != Some(required.0.as_str())
+            {
+                continue;
+            }
+            let Ok(viewport_pos) =
+                camera.world_to_viewport(cam_transform, ab_transform.translation())
+            else {
+                continue;
+            };
+            let caught = geometry::contains_point(&lasso, layout.canvas_to_window(viewport_pos))
+                || size
+                    .and_then(|size| projected_bounds(camera, cam_transform, &layout, ab_transform, size.0))
+                    .is_some_and(|rect| {
+                        geometry::rect_coverage(&lasso, rect, COVERAGE_SAMPLES) >= MIN_COVERAGE
+                    });
+            if caught {
+                effects::dissolve(&mut commands, entity);
+                caught_count += 1;
+            }
+        }
+    }
+
+    if caught_count > 0 {
+        dispelled.write(Dispelled::new(caught_count, sigil));
+    } else {
+        state.cooldown = FAILED_COOLDOWN_SECS;
+        failed.write(DispelFailed {
+            reason: FailReason::Empty,
+            stroke: state.points.clone(),
+        });
+    }
+
+    // Exit dispel mode
+    deactivate_dispel(&mut commands, &mut state, &mut cursor_q, &window_entity_q);
+}
+
+fn tick_cooldown(time: Res<Time>, mut state: ResMut<DispelState>) {
+    state.cooldown = (state.cooldown - time.delta_secs()).max(0.0);
+}
+
+/// Holding dispel mode open wears on the player's sanity.
+fn drain_while_active(time: Res<Time>, state: Res<DispelState>, mut health: ResMut<Health>) {
+    if state.active {
+        health.damage(HOLD_DRAIN * time.delta_secs());
+    }
+}
+
+fn reward_dispel(mut dispelled: MessageReader<Dispelled>, mut health: ResMut<Health>) {
+    for event in dispelled.read() {
+        health.heal(DISPEL_HEAL * event.count as f32 * event.multiplier);
+    }
+}
+
+fn play_dispel_sound(
+    mut dispelled: MessageReader<Dispelled>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    if dispelled.read().count() > 0 {
+        audio.play(audio_assets.dispel.clone());
+    }
+}
+
+fn exit_dispel_on_right_click(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut state: ResMut<DispelState>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    window_entity_q: Query<Entity, With<PrimaryWindow>>,
+    mut failed: MessageWriter<DispelFailed>,
+) {
+    if state.active && mouse.just_pressed(MouseButton::Right) {
+        if let Some(abandoned) = DispelFailed::abandoned(&state) {
+            failed.write(abandoned);
+        }
+        deactivate_dispel(&mut commands, &mut state, &mut cursor_q, &window_entity_q);
+    }
+}
+
+fn deactivate_dispel(
+    commands: &mut Commands,
+    state: &mut DispelState,
+    cursor_q: &mut Query<&mut CursorOptions, With<PrimaryWindow>>,
+    window_q: &Query<Entity, With<PrimaryWindow>>,
+) {
+    state.active = false;
+    state.drawing = false;
+    state.points.clear();
+    if let Ok(mut cursor) = cursor_q.single_mut() {
+        cursor.grab_mode = CursorGrabMode::Locked;
+        cursor.visible = false;
+    }
+    // Restore default cursor
+    if let Ok(window_entity) = window_q.single() {
+        commands
+            .entity(window_entity)
+            .insert(CursorIcon::System(SystemCursorIcon::Default));
+    }
+}
+
+/// Window-pixel bounds of an aberration's sprite quad. The sprite faces the player, so its
+/// corners are offset along the camera's right and up axes.
+fn projected_bounds(
+    camera: &Camera,
+    cam_transform: &GlobalTransform,
+    layout: &CanvasLayout,
+    ab_transform: &GlobalTransform,
+    size: f32,
+) -> Option<Rect> {
+    let half = ab_transform.scale().truncate() * size * 0.5;
+    let right = cam_transform.right() * half.x;
+    let up = cam_transform.up() * half.y;
+    let centre = ab_transform.translation();
+    let mut bounds: Option<Rect> = None;
+    for corner in [centre - right - up, centre + right - up, centre + right + up, centre - right + up] {
+        let px = layout.canvas_to_window(camera.world_to_viewport(cam_transform, corner).ok()?);
+        bounds = Some(bounds.map_or(Rect::from_corners(px, px), |r| r.union_point(px)));
+    }
+    bounds
+}
+
+fn cleanup_dispel(mut commands: Commands) {
+    commands.remove_resource::<DispelState>();
+    commands.remove_resource::<Sigils>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health::HealthPlugin;
+    use crate::testing::{TestApp, WINDOW_SIZE};
+
+    const PLAYER_POS: Vec3 = Vec3::new(0.0, 1.7, 5.0);
+    const CIRCLE_STEPS: usize = 24;
+    const CIRCLE_RADIUS: f32 = 200.0;
+
+    fn setup() -> (TestApp, Entity) {
+        let mut app = TestApp::new().with_plugins((DispelPlugin, HealthPlugin));
+        app.start_playing();
+        app.spawn_player(PLAYER_POS);
+        // Straight ahead of the camera, so it projects to the middle of the window
+        let aberration = app
+            .app
+            .world_mut()
+            .spawn((Transform::from_xyz(0.0, 1.7, 0.0), Aberration))
+            .id();
+        app.step();
+        (app, aberration)
+    }
+
+    /// Whether the entity lost its `Aberration` marker; it lingers while it dissolves.
+    fn dispelled(app: &TestApp, entity: Entity) -> bool {
+        app.app.world().get::<Aberration>(entity).is_none()
+    }
+
+    /// Click once to enter dispel mode, then press and hold to start a stroke at `start`.
+    fn begin_stroke(app: &mut TestApp, start: Vec2) {
+        app.set_cursor(start);
+        app.press_mouse(MouseButton::Left);
+        app.step();
+        app.release_mouse(MouseButton::Left);
+        app.step();
+        assert!(app.resource::<DispelState>().active);
+
+        app.press_mouse(MouseButton::Left);
+        app.step();
+        assert!(app.resource::<DispelState>().drawing);
+    }
+
+    /// Trace `steps` of a `CIRCLE_STEPS`-gon around the window centre, one sample per segment
+    /// interval, starting from the rightmost point.
+    fn trace_circle(app: &mut TestApp, steps: usize) {
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+        for i in 1..=steps {
+            let angle = i as f32 / CIRCLE_STEPS as f32 * std::f32::consts::TAU;
+            app.set_cursor(centre + Vec2::from_angle(angle) * CIRCLE_RADIUS);
+            app.advance(SEGMENT_INTERVAL);
+        }
+    }
+
+    /// Move the cursor along straight lines through `corners`, sampling every `step` pixels.
+    fn trace_path(app: &mut TestApp, corners: &[Vec2], step: f32) {
+        for pair in corners.windows(2) {
+            let n = (pair[0].distance(pair[1]) / step).ceil() as usize;
+            for i in 1..=n {
+                app.set_cursor(pair[0].lerp(pair[1], i as f32 / n as f32));
+                app.advance(SEGMENT_INTERVAL);
+            }
+        }
+    }
+
+    fn require_sigil(app: &mut TestApp, aberration: Entity, name: &str) {
+        app.app
+            .world_mut()
+            .entity_mut(aberration)
+            .insert(RequiredSigil(name.to_string()));
+    }
+
+    #[derive(Resource, Default)]
+    struct Received(Vec<Dispelled>);
+
+    fn record_dispelled(mut dispelled: MessageReader<Dispelled>, mut received: ResMut<Received>) {
+        received.0.extend(dispelled.read().cloned());
+    }
+
+    #[derive(Resource, Default)]
+    struct Failures(Vec<DispelFailed>);
+
+    fn record_failed(mut failed: MessageReader<DispelFailed>, mut failures: ResMut<Failures>) {
+        failures.0.extend(failed.read().cloned());
+    }
+
+    #[test]
+    fn closed_loop_dispels_enclosed_aberration() {
+        let (mut app, aberration) = setup();
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, centre + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS);
+
+        assert!(dispelled(&app, aberration));
+        let state = app.resource::<DispelState>();
+        assert!(!state.active);
+        assert!(state.points.is_empty());
+    }
+
+    #[test]
+    fn releasing_before_closure_keeps_aberration() {
+        let (mut app, aberration) = setup();
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, centre + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS * 2 / 3);
+        app.release_mouse(MouseButton::Left);
+        app.step();
+
+        assert!(!dispelled(&app, aberration));
+        let state = app.resource::<DispelState>();
+        assert!(state.active);
+        assert!(!state.drawing);
+        assert!(state.points.is_empty());
+    }
+
+    #[test]
+    fn lasso_covering_most_of_the_sprite_dispels_without_the_centre() {
+        let (mut app, aberration) = setup();
+        app.app.world_mut().entity_mut(aberration).insert(AberrationSize(2.0));
+        let c = WINDOW_SIZE.as_vec2() / 2.0;
+
+        // A square around the sprite with a notch cut in from the right, leaving the centre out
+        let start = c + Vec2::new(250.0, 40.0);
+        begin_stroke(&mut app, start);
+        trace_path(
+            &mut app,
+            &[
+                start,
+                c + Vec2::new(250.0, 250.0),
+                c + Vec2::new(-250.0, 250.0),
+                c + Vec2::new(-250.0, -250.0),
+                c + Vec2::new(250.0, -250.0),
+                c + Vec2::new(250.0, -40.0),
+                c + Vec2::new(-20.0, -40.0),
+                c + Vec2::new(-20.0, 40.0),
+                start,
+            ],
+            25.0,
+        );
+
+        assert!(dispelled(&app, aberration));
+        assert!(!app.resource::<DispelState>().active);
+    }
+
+    #[test]
+    fn sigil_aberration_needs_the_matching_loop() {
+        let (mut app, aberration) = setup();
+        require_sigil(&mut app, aberration, "triangle");
+        let c = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, c + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS);
+        assert!(!dispelled(&app, aberration));
+        assert!(!app.resource::<DispelState>().active);
+        app.release_mouse(MouseButton::Left);
+        // A loop that catches nothing locks dispel out for a moment
+        app.advance(FAILED_COOLDOWN_SECS);
+
+        let top = c + Vec2::new(0.0, -220.0);
+        begin_stroke(&mut app, top);
+        trace_path(
+            &mut app,
+            &[top, c + Vec2::new(200.0, 130.0), c + Vec2::new(-200.0, 130.0), top],
+            20.0,
+        );
+        assert!(dispelled(&app, aberration));
+    }
+
+    #[test]
+    fn open_sigil_drawn_over_aberration_banishes_it() {
+        let (mut app, aberration) = setup();
+        require_sigil(&mut app, aberration, "zigzag");
+        let c = WINDOW_SIZE.as_vec2() / 2.0;
+
+        let start = c + Vec2::new(-200.0, -60.0);
+        begin_stroke(&mut app, start);
+        trace_path(
+            &mut app,
+            &[
+                start,
+                c + Vec2::new(-120.0, 60.0),
+                c + Vec2::new(-40.0, -60.0),
+                c + Vec2::new(40.0, 60.0),
+                c + Vec2::new(120.0, -60.0),
+                c + Vec2::new(200.0, 60.0),
+            ],
+            20.0,
+        );
+        assert!(!dispelled(&app, aberration));
+
+        app.release_mouse(MouseButton::Left);
+        app.step();
+        assert!(dispelled(&app, aberration));
+        assert!(!app.resource::<DispelState>().active);
+    }
+
+    #[test]
+    fn entering_costs_sanity_and_holding_drains_it() {
+        let (mut app, _) = setup();
+        app.resource_mut::<Health>().drain_rate = 0.0;
+
+        app.press_mouse(MouseButton::Left);
+        app.step();
+        app.release_mouse(MouseButton::Left);
+        let entered = app.resource::<Health>().current;
+        assert!(entered < 1.0 - ENTER_COST * 0.99);
+
+        app.advance(1.0);
+        let held = app.resource::<Health>().current;
+        assert!((entered - held - HOLD_DRAIN).abs() < 0.005, "{entered} -> {held}");
+    }
+
+    #[test]
+    fn empty_loop_puts_dispel_on_cooldown() {
+        let (mut app, aberration) = setup();
+        app.app.world_mut().entity_mut(aberration).despawn();
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, centre + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS);
+        app.release_mouse(MouseButton::Left);
+        app.step();
+        assert!(!app.resource::<DispelState>().active);
+        assert!(app.resource::<DispelState>().cooldown > 0.0);
+
+        app.press_mouse(MouseButton::Left);
+        app.step();
+        app.release_mouse(MouseButton::Left);
+        app.step();
+        assert!(!app.resource::<DispelState>().active);
+
+        app.advance(FAILED_COOLDOWN_SECS);
+        begin_stroke(&mut app, centre);
+    }
+
+    #[test]
+    fn catching_several_aberrations_is_a_combo() {
+        let (mut app, first) = setup();
+        let second = app
+            .app
+            .world_mut()
+            .spawn((Transform::from_xyz(0.3, 1.7, 0.0), Aberration))
+            .id();
+        app.app
+            .init_resource::<Received>()
+            .add_systems(Update, record_dispelled.after(check_closure_and_dispel));
+        app.resource_mut::<Health>().current = 0.5;
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, centre + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS);
+
+        assert!(dispelled(&app, first));
+        assert!(dispelled(&app, second));
+        let received = &app.resource::<Received>().0;
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].count, 2);
+        assert_eq!(received[0].multiplier, 1.0 + COMBO_STEP);
+        // Dispelling pays back more than entering and holding cost
+        assert!(app.resource::<Health>().current > 0.5);
+    }
+
+    #[test]
+    fn dispelled_aberration_dissolves_then_despawns() {
+        let (mut app, aberration) = setup();
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, centre + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS);
+        assert!(app.app.world().get::<effects::Dissolving>(aberration).is_some());
+
+        app.advance(effects::DISSOLVE_SECS);
+        assert!(app.app.world().get_entity(aberration).is_err());
+    }
+
+    #[test]
+    fn abandoned_stroke_is_reported_as_failed() {
+        let (mut app, aberration) = setup();
+        app.app
+            .init_resource::<Failures>()
+            .add_systems(Update, record_failed.after(dispel_draw));
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+
+        begin_stroke(&mut app, centre + Vec2::X * CIRCLE_RADIUS);
+        trace_circle(&mut app, CIRCLE_STEPS / 2);
+        app.release_mouse(MouseButton::Left);
+        app.step();
+
+        assert!(!dispelled(&app, aberration));
+        let failures = &app.resource::<Failures>().0;
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].reason, FailReason::Abandoned);
+        assert!(failures[0].stroke.len() > CIRCLE_STEPS / 4);
+        // Abandoning doesn't lock dispel out, only empty loops do
+        assert_eq!(app.resource::<DispelState>().cooldown, 0.0);
+    }
+
+    #[test]
+    fn failed_strokes_reuse_one_fizzle_mesh() {
+        let (mut app, _) = setup();
+        let centre = WINDOW_SIZE.as_vec2() / 2.0;
+        let abandon = |app: &mut TestApp| {
+            begin_stroke(app, centre + Vec2::X * CIRCLE_RADIUS);
+            trace_circle(app, CIRCLE_STEPS / 2);
+            app.release_mouse(MouseButton::Left);
+            app.step();
+            app.app
+                .world_mut()
+                .query_filtered::<&Mesh3d, With<effects::Fizzle>>()
+                .iter(app.app.world())
+                .map(|mesh| mesh.0.id())
+                .collect::<Vec<_>>()
+        };
+
+        let first = abandon(&mut app);
+        let meshes = app.resource::<Assets<Mesh>>().len();
+        let second = abandon(&mut app);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first, second);
+        assert_eq!(app.resource::<Assets<Mesh>>().len(), meshes);
+    }
+
+    #[test]
+    fn dispel_works_without_health() {
+        let mut app = TestApp::new().with_plugins(DispelPlugin);
+        app.start_playing();
+        app.spawn_player(PLAYER_POS);
+        begin_stroke(&mut app, WINDOW_SIZE.as_vec2() / 2.0);
+        app.advance(0.5);
+        assert!(app.resource::<DispelState>().active);
+    }
+}
@@ -1,18 +1,26 @@
 use crate::GameState;
+use crate::locale::Locale;
 use crate::palette::PaletteDarken;
 use crate::pause::game_not_paused;
-use crate::transition::SceneTransition;
+use crate::transition::{Ease, SceneTransition, Transition, TransitionStep};
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 pub struct EnvironmentPlugin;
 
 impl Plugin for EnvironmentPlugin {
     fn build(&self, app: &mut App) {
         app.add_sub_state::<Environment>()
+            .add_message::<SwitchEnvironment>()
             .add_systems(OnEnter(GameState::Playing), init_timers)
             .add_systems(
                 Update,
-                (tick_run_timer, tick_cycle_and_transition, update_label)
+                (
+                    tick_run_timer,
+                    tick_cycle_and_transition,
+                    switch_on_request,
+                    update_label,
+                )
                     .chain()
                     .run_if(in_state(GameState::Playing).and(game_not_paused)),
             )
@@ -21,7 +29,7 @@ impl Plugin for EnvironmentPlugin {
 }
 
 /// The three fever-dream environments the player cycles through.
-#[derive(SubStates, Clone, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(SubStates, Clone, Eq, PartialEq, Debug, Hash, Default, Serialize, Deserialize)]
 #[source(GameState = GameState::Playing)]
 pub enum Environment {
     #[default]
@@ -31,6 +39,12 @@ pub enum Environment {
 }
 
 impl Environment {
+    pub const ALL: [Environment; 3] = [
+        Environment::Delirium,
+        Environment::Dissociation,
+        Environment::Hypervigilance,
+    ];
+
     fn next(&self) -> Self {
         match self {
             Environment::Delirium => Environment::Dissociation,
@@ -39,15 +53,20 @@ impl Environment {
         }
     }
 
-    pub fn label(&self) -> &'static str {
+    /// Locale key of the environment's name.
+    pub fn label_key(&self) -> &'static str {
         match self {
-            Environment::Delirium => "DELIRIUM",
-            Environment::Dissociation => "DISSOCIATION",
-            Environment::Hypervigilance => "HYPERVIGILANCE",
+            Environment::Delirium => "environment.delirium",
+            Environment::Dissociation => "environment.dissociation",
+            Environment::Hypervigilance => "environment.hypervigilance",
         }
     }
 }
 
+/// Request to switch environment ahead of the regular cycle. The cycle restarts from here.
+#[derive(Message)]
+pub struct SwitchEnvironment(pub Environment);
+
 /// Total elapsed time for the run. Game ends at RUN_DURATION.
 #[derive(Resource)]
 pub struct RunTimer {
@@ -68,6 +87,11 @@ const RUN_DURATION: f32 = 300.0;
 const CYCLE_INTERVAL: f32 = 60.0;
 const TRANSITION_LEAD_SECS: f32 = 5.0;
 
+/// Environment switches dissolve out and back in.
+fn switch_transition(duration: f32) -> Transition {
+    Transition::dissolve(duration).eased(Ease::InOutCubic)
+}
+
 fn init_timers(mut commands: Commands) {
     commands.insert_resource(RunTimer { elapsed: 0.0 });
     commands.insert_resource(CycleTimer {
@@ -96,32 +120,57 @@ fn tick_cycle_and_transition(
     cycle_timer.timer.tick(time.delta());
 
     let Some(transition) = transition.as_mut() else {
+        // Nothing to hide the switch behind: cut straight to the next environment
+        if cycle_timer.timer.just_finished() {
+            next_env.set(environment.get().next());
+        }
         return;
     };
 
-    // Pre-switch darkening: last TRANSITION_LEAD_SECS of the cycle
+    // Last TRANSITION_LEAD_SECS of the cycle: dissolve out, switch in the dark, dissolve back in
     let remaining_frac = 1.0 - cycle_timer.timer.fraction();
     let transition_threshold = TRANSITION_LEAD_SECS / CYCLE_INTERVAL;
 
     if remaining_frac <= transition_threshold && transition.is_idle() {
-        transition.fade_out(TRANSITION_LEAD_SECS * remaining_frac / transition_threshold);
+        transition.play([
+            TransitionStep::FadeOut(switch_transition(
+                TRANSITION_LEAD_SECS * remaining_frac / transition_threshold,
+            )),
+            TransitionStep::run(|world| {
+                let next = world.resource::<State<Environment>>().get().next();
+                world.resource_mut::<NextState<Environment>>().set(next);
+            }),
+            TransitionStep::FadeIn(switch_transition(TRANSITION_LEAD_SECS)),
+        ]);
     }
+}
 
-    // Cycle fired: switch environment, start recovery fade-in
-    if cycle_timer.timer.just_finished() {
-        let next = environment.get().next();
-        next_env.set(next);
-        transition.fade_in(TRANSITION_LEAD_SECS);
+fn switch_on_request(
+    mut requests: MessageReader<SwitchEnvironment>,
+    mut cycle_timer: ResMut<CycleTimer>,
+    mut next_env: ResMut<NextState<Environment>>,
+    mut transition: Option<ResMut<SceneTransition>>,
+) {
+    if let Some(SwitchEnvironment(environment)) = requests.read().last() {
+        next_env.set(environment.clone());
+        cycle_timer.timer.reset();
+        // Don't leave a pre-switch darkening hanging now the cycle has restarted
+        if let Some(transition) = transition.as_mut()
+            && !transition.is_idle()
+        {
+            transition.fade_in(switch_transition(TRANSITION_LEAD_SECS));
+        }
     }
 }
 
 fn update_label(
     environment: Res<State<Environment>>,
+    locale: Res<Locale>,
     mut label_query: Query<&mut Text, With<EnvironmentLabel>>,
 ) {
-    if environment.is_changed() {
+    if environment.is_changed() || locale.is_changed() {
         for mut text in &mut label_query {
-            **text = environment.get().label().to_string();
+            **text = locale.get(environment.get().label_key()).to_string();
         }
     }
 }
@@ -135,3 +184,51 @@ fn cleanup_environment(mut commands: Commands, label_query: Query<Entity, With<E
         commands.entity(entity).despawn();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pause::Paused;
+    use crate::testing::TestApp;
+    use crate::transition::TransitionPlugin;
+
+    #[test]
+    fn environment_cycles_while_run_timer_counts_up() {
+        let mut app = TestApp::new().with_plugins((EnvironmentPlugin, TransitionPlugin));
+        app.start_playing();
+        assert_eq!(*app.resource::<State<Environment>>().get(), Environment::Delirium);
+
+        app.advance(CYCLE_INTERVAL + 1.0);
+
+        let elapsed = app.resource::<RunTimer>().elapsed;
+        assert!((elapsed - (CYCLE_INTERVAL + 1.0)).abs() < 0.1, "elapsed {elapsed}");
+        assert_eq!(
+            *app.resource::<State<Environment>>().get(),
+            Environment::Dissociation
+        );
+    }
+
+    #[test]
+    fn pausing_mid_fade_holds_the_switch() {
+        let mut app = TestApp::new().with_plugins((EnvironmentPlugin, TransitionPlugin));
+        app.start_playing();
+        app.app.insert_resource(Paused(false));
+
+        // Partway into the fade out ahead of the switch
+        app.advance(CYCLE_INTERVAL - TRANSITION_LEAD_SECS / 2.0);
+        let darken = app.resource::<SceneTransition>().darken_value();
+        assert!(darken > 0.0 && darken < 1.0, "darken {darken}");
+
+        app.resource_mut::<Paused>().0 = true;
+        app.advance(TRANSITION_LEAD_SECS * 2.0);
+        assert_eq!(*app.resource::<State<Environment>>().get(), Environment::Delirium);
+        assert_eq!(app.resource::<SceneTransition>().darken_value(), darken);
+
+        app.resource_mut::<Paused>().0 = false;
+        app.advance(TRANSITION_LEAD_SECS);
+        assert_eq!(
+            *app.resource::<State<Environment>>().get(),
+            Environment::Dissociation
+        );
+    }
+}
@@ -71,3 +71,25 @@ fn cleanup_health(mut commands: Commands, query: Query<Entity, With<HealthVignet
         commands.entity(entity).despawn();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pause::PausePlugin;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn sanity_drains_only_while_unpaused() {
+        let mut app = TestApp::new().with_plugins((HealthPlugin, PausePlugin));
+        app.start_playing();
+
+        app.advance(10.0);
+        let drained = app.resource::<Health>().current;
+        assert!((drained - 0.8).abs() < 0.01, "expected ~0.8, got {drained}");
+
+        app.tap_key(KeyCode::Escape);
+        let paused_at = app.resource::<Health>().current;
+        app.advance(10.0);
+        assert_eq!(app.resource::<Health>().current, paused_at);
+    }
+}
@@ -4,34 +4,44 @@ mod aberration;
 mod actions;
 pub mod actor;
 pub mod audio;
+mod capture;
 mod death;
-mod dialog;
-mod dispel;
-mod environment;
+pub mod dialog;
+pub mod dispel;
+pub mod environment;
 mod health;
 mod loading;
+pub mod locale;
 mod menu;
-mod palette;
+pub mod palette;
 mod pause;
+mod photo_mode;
 mod player;
+pub mod post_process;
 pub mod scaling;
-mod transition;
+#[cfg(test)]
+mod testing;
+pub mod transition;
 mod world;
 
 use crate::aberration::AberrationPlugin;
 use crate::actions::ActionsPlugin;
 use crate::actor::ActorPlugin;
 use crate::audio::GameAudioPlugin;
+use crate::capture::CapturePlugin;
 use crate::death::DeathPlugin;
 use crate::dialog::DialogPlugin;
 use crate::dispel::DispelPlugin;
 use crate::environment::EnvironmentPlugin;
 use crate::health::HealthPlugin;
 use crate::loading::LoadingPlugin;
+use crate::locale::LocalePlugin;
 use crate::menu::MenuPlugin;
 use crate::palette::PalettePlugin;
 use crate::pause::PausePlugin;
+use crate::photo_mode::PhotoModePlugin;
 use crate::player::PlayerPlugin;
+use crate::post_process::PostProcessPlugin;
 use crate::scaling::ScalingPlugin;
 use crate::transition::TransitionPlugin;
 use crate::world::WorldPlugin;
@@ -57,6 +67,7 @@ impl Plugin for GamePlugin {
                 ScalingPlugin,
                 GameAudioPlugin,
                 LoadingPlugin,
+                LocalePlugin,
                 MenuPlugin,
                 ActionsPlugin,
                 ActorPlugin,
@@ -65,11 +76,14 @@ impl Plugin for GamePlugin {
                 DialogPlugin,
             ))
             .add_plugins((
+                CapturePlugin,
                 DispelPlugin,
                 HealthPlugin,
                 EnvironmentPlugin,
+                PostProcessPlugin,
                 PalettePlugin,
                 PausePlugin,
+                PhotoModePlugin,
                 PlayerPlugin,
                 TransitionPlugin,
                 WorldPlugin,
@@ -38,6 +38,8 @@ pub struct TextureAssets {
     pub splash: Handle<Image>,
     #[asset(path = "textures/death.png")]
     pub death: Handle<Image>,
+    #[asset(path = "textures/TalkBubble.png")]
+    pub talk_bubble: Handle<Image>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -0,0 +1,198 @@
+//! Localization: per-language string tables looked up by key.
+//!
+//! Each language has a flat `{ "key": "text" }` table in `assets/defs/locale/<code>.ron`.
+//! English is the reference: a key missing from the selected language falls back to English,
+//! with a warning the first time. Dialog lines are keyed by node ID (`dialog.tree/node`) and
+//! responses by node and response ID (`dialog.tree/node.end`), NPC names by `npc.<name>` and their
+//! barks by position (`npc.<name>.bark.0`). Their English text is the one in the defs, so the
+//! English table doesn't repeat them.
+
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Locale>()
+            .add_systems(Update, relabel_localized_text);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+        }
+    }
+
+    /// The language's name in itself, as shown in the language selector.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    /// The language after this one in the selector, wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&l| l == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn table_ron(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../assets/defs/locale/en.ron"),
+            Language::German => include_str!("../assets/defs/locale/de.ron"),
+        }
+    }
+}
+
+type Table = HashMap<String, String>;
+
+/// The selected language and every language's strings. Changing `language` relabels any
+/// `LocalizedText` on screen.
+#[derive(Resource)]
+pub struct Locale {
+    pub language: Language,
+    tables: HashMap<Language, Table>,
+    /// Missing keys already reported, so each is only warned about once.
+    warned: Mutex<HashSet<(Language, String)>>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new(Language::default())
+    }
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        let tables = Language::ALL
+            .iter()
+            .map(|&l| {
+                let table: Table = ron::from_str(l.table_ron())
+                    .unwrap_or_else(|e| panic!("Failed to parse locale/{}.ron: {e}", l.code()));
+                (l, table)
+            })
+            .collect();
+        Self {
+            language,
+            tables,
+            warned: Mutex::default(),
+        }
+    }
+
+    /// A UI string in the current language. Keys missing from every table come back as is.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        if let Some(text) = self.lookup(self.language, key) {
+            return text;
+        }
+        if self.language != Language::English {
+            self.warn_missing(self.language, key);
+        }
+        self.lookup(Language::English, key).unwrap_or_else(|| {
+            self.warn_missing(Language::English, key);
+            key
+        })
+    }
+
+    /// `key` in the current language, or `english` if it has no translation.
+    pub fn get_or<'a>(&'a self, key: &str, english: &'a str) -> &'a str {
+        if self.language == Language::English {
+            return english;
+        }
+        self.lookup(self.language, key).unwrap_or_else(|| {
+            self.warn_missing(self.language, key);
+            english
+        })
+    }
+
+    /// A UI string with `{name}` placeholders filled in from `args`.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+
+    /// A dialog line or response, keyed by node ID, in the current language.
+    pub fn dialog<'a>(&'a self, id: &str, english: &'a str) -> &'a str {
+        self.get_or(&format!("dialog.{id}"), english)
+    }
+
+    /// Keys of `language`'s own table.
+    pub fn keys(&self, language: Language) -> impl Iterator<Item = &str> {
+        self.tables[&language].keys().map(String::as_str)
+    }
+
+    fn lookup(&self, language: Language, key: &str) -> Option<&str> {
+        self.tables[&language].get(key).map(String::as_str)
+    }
+
+    fn warn_missing(&self, language: Language, key: &str) {
+        let mut warned = self.warned.lock().unwrap_or_else(|e| e.into_inner());
+        if warned.insert((language, key.to_string())) {
+            warn!("No {} text for `{key}`", language.native_name());
+        }
+    }
+}
+
+/// Text showing a UI string, kept up to date when the language changes.
+#[derive(Component)]
+pub struct LocalizedText(pub &'static str);
+
+fn relabel_localized_text(locale: Res<Locale>, mut text_q: Query<(&LocalizedText, &mut Text)>) {
+    if !locale.is_changed() {
+        return;
+    }
+    for (key, mut text) in &mut text_q {
+        **text = locale.get(key.0).to_string();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_language_covers_the_english_keys() {
+        let locale = Locale::default();
+        for language in Language::ALL {
+            for key in locale.keys(Language::English) {
+                assert!(
+                    locale.lookup(language, key).is_some(),
+                    "{} is missing `{key}`",
+                    language.native_name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english() {
+        let mut locale = Locale::new(Language::German);
+        locale.tables.get_mut(&Language::German).unwrap().remove("menu.play");
+        assert_eq!(locale.get("menu.play"), "Play");
+        assert_eq!(locale.get("pause.continue"), "Weiter");
+        assert_eq!(locale.get("no.such.key"), "no.such.key");
+        assert_eq!(locale.dialog("nowhere/start", "Hello?"), "Hello?");
+
+        locale.language = Language::English;
+        assert_eq!(
+            locale.format("death.survived", &[("time", "1:05")]),
+            "You survived 1:05"
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use crate::GameState;
 use crate::audio::GameVolume;
 use crate::loading::{AudioAssets, FontAssets, TextureAssets};
-use crate::palette::PaletteSqueeze;
-use crate::scaling::CanvasImage;
+use crate::locale::{Locale, LocalizedText};
+use crate::palette::{PaletteSqueeze, ReducedFlashing};
+use crate::scaling::{CanvasImage, CanvasResolution, CanvasScaling, CanvasSettings};
 use bevy::prelude::*;
 use bevy::text::FontSmoothing;
 use bevy_kira_audio::{Audio, AudioControl};
@@ -14,7 +15,16 @@ impl Plugin for MenuPlugin {
         app.add_systems(OnEnter(GameState::Menu), setup_menu)
             .add_systems(
                 Update,
-                (click_play_button, handle_volume_buttons)
+                (
+                    click_play_button,
+                    handle_volume_buttons,
+                    handle_language_button,
+                    handle_canvas_buttons,
+                    update_canvas_labels,
+                    handle_flashing_button,
+                    update_flashing_label,
+                )
+                    .chain()
                     .run_if(in_state(GameState::Menu)),
             )
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
@@ -40,6 +50,9 @@ fn setup_menu(
     fonts: Res<FontAssets>,
     canvas: Res<CanvasImage>,
     vol: Res<GameVolume>,
+    locale: Res<Locale>,
+    canvas_settings: Res<CanvasSettings>,
+    reduced_flashing: Res<ReducedFlashing>,
 ) {
     let font = fonts.main.clone();
     info!("menu");
@@ -118,7 +131,8 @@ fn setup_menu(
                     ChangeState(GameState::Playing),
                 ))
                 .with_child((
-                    Text::new("Play"),
+                    Text::new(locale.get("menu.play")),
+                    LocalizedText("menu.play"),
                     textfont.clone(),
                     TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                 ));
@@ -197,6 +211,99 @@ fn setup_menu(
                         TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                     ));
                 });
+            // Language selector, cycling through the languages
+            children
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(140.0),
+                        height: Val::Px(50.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ImageNode {
+                        image: textbox_image.clone(),
+                        image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                        ..default()
+                    },
+                    LanguageButton,
+                ))
+                .with_child((
+                    Text::new(locale.language.native_name()),
+                    textfont.clone(),
+                    TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                    LanguageDisplay,
+                ));
+            // Canvas row: resolution, scaling and filtering, each cycling on click
+            let small_font = TextFont {
+                font_size: 16.0,
+                ..textfont.clone()
+            };
+            children
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    margin: UiRect::top(Val::Px(10.0)),
+                    column_gap: Val::Px(4.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for option in [
+                        CanvasOption::Resolution,
+                        CanvasOption::Scaling,
+                        CanvasOption::PixelPerfect,
+                    ] {
+                        row.spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(96.0),
+                                height: Val::Px(40.0),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ImageNode {
+                                image: textbox_image.clone(),
+                                image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                                ..default()
+                            },
+                            option,
+                        ))
+                        .with_child((
+                            Text::new(option.label(&canvas_settings, &locale)),
+                            small_font.clone(),
+                            TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                            CanvasOptionDisplay(option),
+                        ));
+                    }
+                });
+            // Reduced flashing toggle
+            children
+                .spawn((
+                    Button,
+                    Node {
+                        width: Val::Px(200.0),
+                        height: Val::Px(40.0),
+                        margin: UiRect::top(Val::Px(10.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ImageNode {
+                        image: textbox_image.clone(),
+                        image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                        ..default()
+                    },
+                    FlashingButton,
+                ))
+                .with_child((
+                    Text::new(flashing_label(*reduced_flashing, &locale)),
+                    small_font.clone(),
+                    TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                    FlashingDisplay,
+                ));
             // Exit button
             children
                 .spawn((
@@ -217,7 +324,8 @@ fn setup_menu(
                     ExitApp,
                 ))
                 .with_child((
-                    Text::new("Exit"),
+                    Text::new(locale.get("menu.exit")),
+                    LocalizedText("menu.exit"),
                     textfont.clone(),
                     TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                 ));
@@ -251,7 +359,8 @@ fn setup_menu(
                 ))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Made with Bevy"),
+                        Text::new(locale.get("menu.made_with_bevy")),
+                        LocalizedText("menu.made_with_bevy"),
                         textfont.clone(),
                         TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                     ));
@@ -281,7 +390,8 @@ fn setup_menu(
                 ))
                 .with_children(|parent| {
                     parent.spawn((
-                        Text::new("Open source"),
+                        Text::new(locale.get("menu.open_source")),
+                        LocalizedText("menu.open_source"),
                         textfont.clone(),
                         TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                     ));
@@ -314,6 +424,73 @@ struct VolumeDown;
 #[derive(Component)]
 struct VolumeDisplay;
 
+#[derive(Component)]
+struct LanguageButton;
+
+#[derive(Component)]
+struct LanguageDisplay;
+
+#[derive(Component)]
+struct FlashingButton;
+
+#[derive(Component)]
+struct FlashingDisplay;
+
+/// A button cycling one of the `CanvasSettings`.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum CanvasOption {
+    Resolution,
+    Scaling,
+    PixelPerfect,
+}
+
+impl CanvasOption {
+    /// Step the setting on to its next choice.
+    fn cycle(self, settings: &mut CanvasSettings) {
+        match self {
+            CanvasOption::Resolution => {
+                settings.resolution = next_of(&CanvasResolution::PRESETS, settings.resolution);
+            }
+            CanvasOption::Scaling => {
+                settings.scaling = next_of(&CanvasScaling::ALL, settings.scaling);
+            }
+            CanvasOption::PixelPerfect => settings.pixel_perfect = !settings.pixel_perfect,
+        }
+    }
+
+    fn label(self, settings: &CanvasSettings, locale: &Locale) -> String {
+        match self {
+            CanvasOption::Resolution => match settings.resolution {
+                CanvasResolution::Window(_) => locale.get("menu.resolution.window").to_string(),
+                CanvasResolution::Fixed(size) => format!("{}x{}", size.x, size.y),
+            },
+            CanvasOption::Scaling => locale
+                .get(match settings.scaling {
+                    CanvasScaling::Stretch => "menu.scaling.stretch",
+                    CanvasScaling::Fit => "menu.scaling.fit",
+                    CanvasScaling::Integer => "menu.scaling.integer",
+                })
+                .to_string(),
+            CanvasOption::PixelPerfect => locale
+                .get(if settings.pixel_perfect {
+                    "menu.pixels.sharp"
+                } else {
+                    "menu.pixels.smooth"
+                })
+                .to_string(),
+        }
+    }
+}
+
+/// The choice after `current` in `choices`, wrapping round. Unlisted values go to the first.
+fn next_of<T: Copy + PartialEq>(choices: &[T], current: T) -> T {
+    let index = choices.iter().position(|choice| *choice == current);
+    choices[index.map_or(0, |index| (index + 1) % choices.len())]
+}
+
+#[derive(Component)]
+struct CanvasOptionDisplay(CanvasOption);
+
 fn click_play_button(
     mut next_state: ResMut<NextState<GameState>>,
     mut exit: MessageWriter<AppExit>,
@@ -388,6 +565,84 @@ fn handle_volume_buttons(
     }
 }
 
+fn handle_language_button(
+    mut locale: ResMut<Locale>,
+    button_q: Query<&Interaction, (Changed<Interaction>, With<LanguageButton>)>,
+    mut display_q: Query<&mut Text, With<LanguageDisplay>>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for interaction in &button_q {
+        if *interaction == Interaction::Pressed {
+            locale.language = locale.language.next();
+            audio.play(audio_assets.fx1.clone());
+            for mut text in &mut display_q {
+                **text = locale.language.native_name().to_string();
+            }
+        }
+    }
+}
+
+fn handle_canvas_buttons(
+    mut settings: ResMut<CanvasSettings>,
+    button_q: Query<(&Interaction, &CanvasOption), Changed<Interaction>>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for (interaction, option) in &button_q {
+        if *interaction == Interaction::Pressed {
+            option.cycle(&mut settings);
+            audio.play(audio_assets.fx1.clone());
+        }
+    }
+}
+
+fn update_canvas_labels(
+    settings: Res<CanvasSettings>,
+    locale: Res<Locale>,
+    mut display_q: Query<(&mut Text, &CanvasOptionDisplay)>,
+) {
+    if settings.is_changed() || locale.is_changed() {
+        for (mut text, display) in &mut display_q {
+            **text = display.0.label(&settings, &locale);
+        }
+    }
+}
+
+fn flashing_label(reduced: ReducedFlashing, locale: &Locale) -> &str {
+    locale.get(if reduced.0 {
+        "menu.flashing.reduced"
+    } else {
+        "menu.flashing.full"
+    })
+}
+
+fn handle_flashing_button(
+    mut reduced: ResMut<ReducedFlashing>,
+    button_q: Query<&Interaction, (Changed<Interaction>, With<FlashingButton>)>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for interaction in &button_q {
+        if *interaction == Interaction::Pressed {
+            reduced.0 = !reduced.0;
+            audio.play(audio_assets.fx1.clone());
+        }
+    }
+}
+
+fn update_flashing_label(
+    reduced: Res<ReducedFlashing>,
+    locale: Res<Locale>,
+    mut display_q: Query<&mut Text, With<FlashingDisplay>>,
+) {
+    if reduced.is_changed() || locale.is_changed() {
+        for mut text in &mut display_q {
+            **text = flashing_label(*reduced, &locale).to_string();
+        }
+    }
+}
+
 fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<Menu>>) {
     for entity in menu.iter() {
         commands.entity(entity).despawn();
@@ -0,0 +1,333 @@
+//! Palettes as assets: `.palette.ron`, Lospec-style `.hex` and GIMP `.gpl` files.
+//!
+//! A palette is a ramp of colours, dark to light, that the screen's luminance is quantized to,
+//! plus highlight colours that pass through untouched. Colours in files are sRGB. `.hex` files
+//! only hold a ramp; in `.gpl` files, colours whose name starts with `highlight` are highlights.
+//! A palette without highlights keeps the default ones, which the dialog markup colours use.
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fmt;
+
+/// Most ramp colours the shader takes.
+pub const MAX_RAMP: usize = 16;
+/// Most highlight colours the shader takes.
+pub const MAX_HIGHLIGHTS: usize = 8;
+
+const DEFAULT_RAMP: [LinearRgba; 4] = [
+    LinearRgba::rgb(0.0, 0.0, 0.0),
+    LinearRgba::rgb(0.1, 0.1, 0.1),
+    LinearRgba::rgb(0.278, 0.278, 0.278),
+    LinearRgba::rgb(1.0, 1.0, 1.0),
+];
+
+/// Same as `markup::Highlight::color`, so marked up dialog text keeps its colour.
+const DEFAULT_HIGHLIGHTS: [LinearRgba; 5] = [
+    LinearRgba::rgb(1.0, 0.0, 0.929),
+    LinearRgba::rgb(1.0, 1.0, 0.0),
+    LinearRgba::rgb(0.196, 1.0, 0.0),
+    LinearRgba::rgb(0.0, 0.906, 1.0),
+    LinearRgba::rgb(1.0, 0.263, 0.235),
+];
+
+#[derive(Asset, TypePath, Debug, Clone, PartialEq)]
+pub struct Palette {
+    /// Dark to light, 2 to `MAX_RAMP` colours.
+    pub ramp: Vec<LinearRgba>,
+    /// Up to `MAX_HIGHLIGHTS` colours.
+    pub highlights: Vec<LinearRgba>,
+}
+
+/// The four greys and five highlights the game was made with.
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            ramp: DEFAULT_RAMP.to_vec(),
+            highlights: DEFAULT_HIGHLIGHTS.to_vec(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PaletteRon {
+    ramp: Vec<String>,
+    #[serde(default)]
+    highlights: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub enum PaletteError {
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    /// A colour that doesn't parse, with its 1-based line (0 in RON palettes).
+    BadColor { line: usize, text: String },
+    /// A `.gpl` file without the `GIMP Palette` header.
+    NotGpl,
+    RampSize(usize),
+    TooManyHighlights(usize),
+    UnknownExtension(String),
+}
+
+impl fmt::Display for PaletteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaletteError::Io(e) => write!(f, "{e}"),
+            PaletteError::Ron(e) => write!(f, "{e}"),
+            PaletteError::BadColor { line: 0, text } => write!(f, "bad colour `{text}`"),
+            PaletteError::BadColor { line, text } => write!(f, "line {line}: bad colour `{text}`"),
+            PaletteError::NotGpl => write!(f, "missing the `GIMP Palette` header"),
+            PaletteError::RampSize(n) => {
+                write!(f, "ramp has {n} colours, needs 2 to {MAX_RAMP}")
+            }
+            PaletteError::TooManyHighlights(n) => {
+                write!(f, "{n} highlights, at most {MAX_HIGHLIGHTS} are allowed")
+            }
+            PaletteError::UnknownExtension(ext) => write!(f, "not a palette file: `{ext}`"),
+        }
+    }
+}
+
+impl std::error::Error for PaletteError {}
+
+impl From<std::io::Error> for PaletteError {
+    fn from(e: std::io::Error) -> Self {
+        PaletteError::Io(e)
+    }
+}
+
+impl Palette {
+    fn new(ramp: Vec<LinearRgba>, highlights: Option<Vec<LinearRgba>>) -> Result<Self, PaletteError> {
+        if !(2..=MAX_RAMP).contains(&ramp.len()) {
+            return Err(PaletteError::RampSize(ramp.len()));
+        }
+        let highlights = highlights.unwrap_or_else(|| DEFAULT_HIGHLIGHTS.to_vec());
+        if highlights.len() > MAX_HIGHLIGHTS {
+            return Err(PaletteError::TooManyHighlights(highlights.len()));
+        }
+        Ok(Self { ramp, highlights })
+    }
+
+    /// `(ramp: ["#000000", ...], highlights: Some([...]))`, highlights optional.
+    pub fn from_ron(source: &str) -> Result<Self, PaletteError> {
+        let ron: PaletteRon = ron::from_str(source).map_err(PaletteError::Ron)?;
+        let parse_all = |colors: &[String]| -> Result<Vec<LinearRgba>, PaletteError> {
+            colors.iter().map(|text| parse_color(text, 0)).collect()
+        };
+        let highlights = ron.highlights.as_deref().map(parse_all).transpose()?;
+        Self::new(parse_all(&ron.ramp)?, highlights)
+    }
+
+    /// One `rrggbb` colour per line, `#` optional.
+    pub fn from_hex(source: &str) -> Result<Self, PaletteError> {
+        let ramp = source
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| parse_color(line.trim(), i + 1))
+            .collect::<Result<_, _>>()?;
+        Self::new(ramp, None)
+    }
+
+    /// A GIMP palette: `R G B name` lines after a `GIMP Palette` header.
+    pub fn from_gpl(source: &str) -> Result<Self, PaletteError> {
+        let mut lines = source.lines().enumerate();
+        if lines.next().is_none_or(|(_, header)| header.trim() != "GIMP Palette") {
+            return Err(PaletteError::NotGpl);
+        }
+        let mut ramp = Vec::new();
+        let mut highlights = Vec::new();
+        for (i, line) in lines {
+            let line = line.trim();
+            if line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("Name:")
+                || line.starts_with("Columns:")
+            {
+                continue;
+            }
+            let mut words = line.split_whitespace();
+            let channels: Option<Vec<u8>> =
+                words.by_ref().take(3).map(|w| w.parse().ok()).collect();
+            let Some([r, g, b]) = channels.and_then(|c| <[u8; 3]>::try_from(c).ok()) else {
+                return Err(PaletteError::BadColor {
+                    line: i + 1,
+                    text: line.to_string(),
+                });
+            };
+            let color = LinearRgba::from(Srgba::rgb_u8(r, g, b));
+            let name = words.collect::<Vec<_>>().join(" ");
+            if name.to_lowercase().starts_with("highlight") {
+                highlights.push(color);
+            } else {
+                ramp.push(color);
+            }
+        }
+        Self::new(ramp, (!highlights.is_empty()).then_some(highlights))
+    }
+
+    /// The ramp at `x`, from 0.0 (darkest) to 1.0 (lightest), interpolated between entries.
+    pub fn sample(&self, x: f32) -> LinearRgba {
+        let position = x.clamp(0.0, 1.0) * (self.ramp.len() - 1) as f32;
+        let i = (position as usize).min(self.ramp.len() - 2);
+        self.ramp[i].mix(&self.ramp[i + 1], position - i as f32)
+    }
+
+    /// This palette mixed toward `other` by `t`. Ramps are resampled to the longer of the two;
+    /// highlights switch over halfway.
+    pub fn blend(&self, other: &Palette, t: f32) -> Palette {
+        if t <= 0.0 {
+            return self.clone();
+        }
+        if t >= 1.0 {
+            return other.clone();
+        }
+        let len = self.ramp.len().max(other.ramp.len());
+        let ramp = (0..len)
+            .map(|i| {
+                let x = i as f32 / (len - 1) as f32;
+                self.sample(x).mix(&other.sample(x), t)
+            })
+            .collect();
+        let highlights = if t < 0.5 { &self.highlights } else { &other.highlights };
+        Palette {
+            ramp,
+            highlights: highlights.clone(),
+        }
+    }
+}
+
+fn parse_color(text: &str, line: usize) -> Result<LinearRgba, PaletteError> {
+    Srgba::hex(text)
+        .map(LinearRgba::from)
+        .map_err(|_| PaletteError::BadColor {
+            line,
+            text: text.to_string(),
+        })
+}
+
+#[derive(Default, TypePath)]
+pub struct PaletteLoader;
+
+impl AssetLoader for PaletteLoader {
+    type Asset = Palette;
+    type Settings = ();
+    type Error = PaletteError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Palette, PaletteError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = String::from_utf8_lossy(&bytes);
+        match load_context.path().get_full_extension().as_deref() {
+            Some("palette.ron") => Palette::from_ron(&source),
+            Some("hex") => Palette::from_hex(&source),
+            Some("gpl") => Palette::from_gpl(&source),
+            other => Err(PaletteError::UnknownExtension(other.unwrap_or_default().to_string())),
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["palette.ron", "hex", "gpl"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialog::markup::Highlight;
+
+    fn srgb(hex: &str) -> LinearRgba {
+        Srgba::hex(hex).unwrap().into()
+    }
+
+    #[test]
+    fn the_three_formats_parse_to_the_same_palette() {
+        let hex = Palette::from_hex("000000\n#404040\n\nffffff\n").unwrap();
+        let gpl = Palette::from_gpl(
+            "GIMP Palette\nName: Test\nColumns: 3\n# comment\n  0   0   0 Black\n 64  64  64\n255 255 255 White\n",
+        )
+        .unwrap();
+        let ron = Palette::from_ron(r##"(ramp: ["#000000", "#404040", "#FFFFFF"])"##).unwrap();
+
+        assert_eq!(hex.ramp, vec![srgb("000000"), srgb("404040"), srgb("ffffff")]);
+        assert_eq!(hex.highlights, DEFAULT_HIGHLIGHTS.to_vec());
+        assert_eq!(gpl, hex);
+        assert_eq!(ron, hex);
+    }
+
+    #[test]
+    fn highlights_can_be_replaced() {
+        let gpl = Palette::from_gpl(
+            "GIMP Palette\n0 0 0\n255 255 255\n255 0 0 Highlight red\n",
+        )
+        .unwrap();
+        assert_eq!(gpl.ramp.len(), 2);
+        assert_eq!(gpl.highlights, vec![srgb("ff0000")]);
+
+        let ron = Palette::from_ron(r##"(ramp: ["000", "fff"], highlights: Some([]))"##).unwrap();
+        assert!(ron.highlights.is_empty());
+    }
+
+    #[test]
+    fn bad_palettes_are_rejected() {
+        assert!(matches!(
+            Palette::from_hex("000000\nnope\n"),
+            Err(PaletteError::BadColor { line: 2, .. })
+        ));
+        assert!(matches!(
+            Palette::from_hex("000000\n"),
+            Err(PaletteError::RampSize(1))
+        ));
+        assert!(matches!(
+            Palette::from_gpl("0 0 0\n255 255 255\n"),
+            Err(PaletteError::NotGpl)
+        ));
+        assert!(matches!(
+            Palette::from_gpl("GIMP Palette\n0 0\n"),
+            Err(PaletteError::BadColor { line: 2, .. })
+        ));
+        let many = ["\"fff\""; MAX_HIGHLIGHTS + 1].join(", ");
+        assert!(matches!(
+            Palette::from_ron(&format!(r#"(ramp: ["000", "fff"], highlights: Some([{many}]))"#)),
+            Err(PaletteError::TooManyHighlights(_))
+        ));
+    }
+
+    #[test]
+    fn blending_resamples_the_ramps() {
+        let a = Palette::default();
+        let b = Palette::new(
+            vec![LinearRgba::rgb(1.0, 0.0, 0.0), LinearRgba::rgb(0.0, 0.0, 1.0)],
+            Some(Vec::new()),
+        )
+        .unwrap();
+
+        assert_eq!(a.blend(&b, 0.0), a);
+        assert_eq!(a.blend(&b, 1.0), b);
+        let mid = a.blend(&b, 0.5);
+        assert_eq!(mid.ramp.len(), 4);
+        assert_eq!(mid.ramp[0], LinearRgba::rgb(0.5, 0.0, 0.0));
+        assert_eq!(mid.ramp[3], LinearRgba::rgb(0.5, 0.5, 1.0));
+        assert!(mid.highlights.is_empty());
+    }
+
+    #[test]
+    fn default_highlights_match_the_markup_colours() {
+        for highlight in [
+            Highlight::Pink,
+            Highlight::Yellow,
+            Highlight::Green,
+            Highlight::Cyan,
+            Highlight::Red,
+        ] {
+            let color = highlight.color().to_linear();
+            assert!(DEFAULT_HIGHLIGHTS.contains(&color), "{highlight:?} isn't a highlight");
+        }
+    }
+}
@@ -0,0 +1,217 @@
+//! Dev-only panel for tuning `PaletteSqueeze` live. F3 opens it, Page Up/Down pick a setting,
+//! `[` and `]` change it. Changes apply to every camera with the effect, until it is respawned.
+
+use super::{Dither, PaletteSqueeze};
+use bevy::prelude::*;
+use std::fmt::Write;
+
+pub struct PaletteInspectorPlugin;
+
+impl Plugin for PaletteInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Inspector>()
+            .add_systems(Update, (toggle_inspector, tune_settings, show_inspector).chain());
+    }
+}
+
+#[derive(Resource, Default)]
+struct Inspector {
+    open: bool,
+    /// Index into `SETTINGS`.
+    selected: usize,
+}
+
+#[derive(Component)]
+struct InspectorPanel;
+
+#[derive(Clone, Copy)]
+enum Setting {
+    Dither,
+    DownScale,
+    HighlightThreshold,
+}
+
+const SETTINGS: [Setting; 3] = [
+    Setting::Dither,
+    Setting::DownScale,
+    Setting::HighlightThreshold,
+];
+
+const MAX_DOWN_SCALE: f32 = 8.0;
+const THRESHOLD_STEP: f32 = 0.005;
+const MAX_THRESHOLD: f32 = 0.25;
+
+impl Setting {
+    fn label(self) -> &'static str {
+        match self {
+            Setting::Dither => "dither",
+            Setting::DownScale => "down scale",
+            Setting::HighlightThreshold => "highlight threshold",
+        }
+    }
+
+    fn value(self, squeeze: &PaletteSqueeze) -> String {
+        match self {
+            Setting::Dither => format!("{:?}", squeeze.dither),
+            Setting::DownScale => format!("{}", squeeze.down_scale),
+            Setting::HighlightThreshold => format!("{:.3}", squeeze.highlight_threshold),
+        }
+    }
+
+    /// Move the setting one step up (`direction` 1) or down (-1).
+    fn step(self, squeeze: &mut PaletteSqueeze, direction: i32) {
+        match self {
+            Setting::Dither => {
+                let count = Dither::ALL.len() as i32;
+                let index = Dither::ALL.iter().position(|&d| d == squeeze.dither).unwrap_or(0);
+                squeeze.dither = Dither::ALL[(index as i32 + direction).rem_euclid(count) as usize];
+            }
+            Setting::DownScale => {
+                squeeze.down_scale =
+                    (squeeze.down_scale + direction as f32).clamp(1.0, MAX_DOWN_SCALE);
+            }
+            Setting::HighlightThreshold => {
+                squeeze.highlight_threshold = (squeeze.highlight_threshold
+                    + THRESHOLD_STEP * direction as f32)
+                    .clamp(0.0, MAX_THRESHOLD);
+            }
+        }
+    }
+}
+
+fn toggle_inspector(keyboard: Res<ButtonInput<KeyCode>>, mut inspector: ResMut<Inspector>) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        inspector.open = !inspector.open;
+    }
+}
+
+fn tune_settings(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut inspector: ResMut<Inspector>,
+    mut squeeze_q: Query<&mut PaletteSqueeze>,
+) {
+    if !inspector.open {
+        return;
+    }
+    let count = SETTINGS.len();
+    if keyboard.just_pressed(KeyCode::PageDown) {
+        inspector.selected = (inspector.selected + 1) % count;
+    }
+    if keyboard.just_pressed(KeyCode::PageUp) {
+        inspector.selected = (inspector.selected + count - 1) % count;
+    }
+
+    let direction = if keyboard.just_pressed(KeyCode::BracketRight) {
+        1
+    } else if keyboard.just_pressed(KeyCode::BracketLeft) {
+        -1
+    } else {
+        return;
+    };
+    let setting = SETTINGS[inspector.selected];
+    // Step from the first camera so every camera ends up with the same value
+    let Some(mut tuned) = squeeze_q.iter().next().copied() else {
+        return;
+    };
+    setting.step(&mut tuned, direction);
+    for mut squeeze in &mut squeeze_q {
+        squeeze.dither = tuned.dither;
+        squeeze.down_scale = tuned.down_scale;
+        squeeze.highlight_threshold = tuned.highlight_threshold;
+    }
+}
+
+fn show_inspector(
+    mut commands: Commands,
+    inspector: Res<Inspector>,
+    squeeze_q: Query<&PaletteSqueeze>,
+    mut panel_q: Query<(Entity, &mut Text), With<InspectorPanel>>,
+) {
+    if !inspector.open {
+        for (entity, _) in &panel_q {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let mut text = String::from("palette squeeze\n");
+    match squeeze_q.iter().next() {
+        Some(squeeze) => {
+            for (i, setting) in SETTINGS.iter().enumerate() {
+                let marker = if i == inspector.selected { '>' } else { ' ' };
+                let _ = writeln!(text, "{marker} {}: {}", setting.label(), setting.value(squeeze));
+            }
+            let _ = writeln!(
+                text,
+                "  ramp: {} colours, {} highlights",
+                squeeze.ramp_size, squeeze.highlight_count
+            );
+        }
+        None => text.push_str("  no camera\n"),
+    }
+    text.push_str("PgUp/PgDn pick, [ ] change");
+
+    match panel_q.single_mut() {
+        Ok((_, mut panel)) => {
+            if panel.0 != text {
+                panel.0 = text;
+            }
+        }
+        Err(_) => {
+            commands.spawn((
+                Text::new(text),
+                TextFont {
+                    font_size: 10.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(4.0),
+                    right: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::BLACK.with_alpha(0.8)),
+                GlobalZIndex(200),
+                InspectorPanel,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn settings_are_tuned_on_every_camera() {
+        let mut app = TestApp::new().with_plugins(PaletteInspectorPlugin);
+        let cameras = [
+            app.app.world_mut().spawn(PaletteSqueeze::default()).id(),
+            app.app.world_mut().spawn(PaletteSqueeze::default()).id(),
+        ];
+
+        // Closed, the keys do nothing
+        app.tap_key(KeyCode::BracketRight);
+        assert_eq!(app.app.world().get::<PaletteSqueeze>(cameras[0]).unwrap().dither, Dither::BlueNoise);
+
+        app.tap_key(KeyCode::F3);
+        app.tap_key(KeyCode::BracketLeft);
+        app.tap_key(KeyCode::PageDown);
+        app.tap_key(KeyCode::BracketRight);
+        app.tap_key(KeyCode::BracketRight);
+        for camera in cameras {
+            let squeeze = app.app.world().get::<PaletteSqueeze>(camera).unwrap();
+            assert_eq!(squeeze.dither, Dither::None);
+            assert_eq!(squeeze.down_scale, 3.0);
+        }
+        let mut panel_q = app.app.world_mut().query_filtered::<&Text, With<InspectorPanel>>();
+        let panel = panel_q.single(app.app.world()).unwrap();
+        assert!(panel.0.contains("> down scale: 3"), "{}", panel.0);
+
+        app.tap_key(KeyCode::F3);
+        assert!(panel_q.iter(app.app.world()).next().is_none());
+    }
+}
@@ -0,0 +1,409 @@
+use crate::environment::Environment;
+use crate::health::Health;
+use crate::post_process::{Distortion, PostEffect, PostEffectPlugin};
+use crate::transition::TransitionStyle;
+use bevy::ecs::query::QueryItem;
+use bevy::prelude::*;
+use bevy::render::extract_component::ExtractComponent;
+use bevy::render::render_resource::ShaderType;
+use bevy::window::PrimaryWindow;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub mod asset;
+#[cfg(feature = "dev")]
+mod inspector;
+
+pub use asset::Palette;
+use asset::{MAX_HIGHLIGHTS, MAX_RAMP, PaletteLoader};
+
+const PALETTES_RON: &str = include_str!("../../assets/defs/palettes.ron");
+/// Seconds to blend into a new environment's palette.
+const PALETTE_BLEND_SECS: f32 = 2.0;
+/// Sanity below which the screen starts to come apart.
+const SANITY_STRAIN_START: f32 = 0.6;
+/// `Distortion::amount` at zero sanity.
+const MAX_SANITY_WARP: f32 = 0.012;
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PostEffectPlugin::<PaletteSqueeze>::default())
+            .init_asset::<Palette>()
+            .register_asset_loader(PaletteLoader)
+            .init_resource::<ActivePalette>()
+            .init_resource::<ReducedFlashing>()
+            .add_systems(Startup, load_environment_palettes)
+            .add_systems(Update, (follow_environment, update_palette_squeeze).chain());
+
+        #[cfg(feature = "dev")]
+        app.add_plugins(inspector::PaletteInspectorPlugin);
+    }
+}
+
+/// Fullscreen post-process that applies palette quantization with dithering.
+/// Add this component to a camera to enable the effect; it belongs last in its `PostStack`.
+#[derive(Component, Clone, Copy)]
+pub struct PaletteSqueeze {
+    pub resolution: Vec3,
+    pub time: f32,
+    /// 0.0 = normal, 1.0 = fully dark. Used for environment transitions.
+    pub darken: f32,
+    /// How `darken` is drawn.
+    pub darken_style: TransitionStyle,
+    pub dither: Dither,
+    /// Side of a dither cell, in screen pixels.
+    pub down_scale: f32,
+    /// How close a colour must be to a highlight to pass through unquantized.
+    pub highlight_threshold: f32,
+    /// Share of highlight pixels that bleed into the greyscale ramp, 0.0 to 1.0.
+    pub instability: f32,
+    /// How often the image flashes brighter or darker for a frame, 0.0 to 1.0. Kept at 0.0
+    /// while `ReducedFlashing` is on.
+    pub flicker: f32,
+    /// Number of `ramp` entries in use.
+    pub ramp_size: u32,
+    /// Number of `highlights` entries in use.
+    pub highlight_count: u32,
+    pub ramp: [Vec4; MAX_RAMP],
+    pub highlights: [Vec4; MAX_HIGHLIGHTS],
+}
+
+impl Default for PaletteSqueeze {
+    fn default() -> Self {
+        let mut squeeze = Self {
+            resolution: Vec3::new(1280.0, 720.0, 0.0),
+            time: 0.0,
+            darken: 0.0,
+            darken_style: TransitionStyle::default(),
+            dither: Dither::default(),
+            down_scale: 1.0,
+            highlight_threshold: 0.01,
+            instability: 0.0,
+            flicker: 0.0,
+            ramp_size: 0,
+            highlight_count: 0,
+            ramp: [Vec4::ZERO; MAX_RAMP],
+            highlights: [Vec4::ZERO; MAX_HIGHLIGHTS],
+        };
+        squeeze.set_palette(&Palette::default());
+        squeeze
+    }
+}
+
+impl PaletteSqueeze {
+    pub fn set_palette(&mut self, palette: &Palette) {
+        for (slot, color) in self.ramp.iter_mut().zip(&palette.ramp) {
+            *slot = color.to_vec4();
+        }
+        for (slot, color) in self.highlights.iter_mut().zip(&palette.highlights) {
+            *slot = color.to_vec4();
+        }
+        self.ramp_size = palette.ramp.len().min(MAX_RAMP) as u32;
+        self.highlight_count = palette.highlights.len().min(MAX_HIGHLIGHTS) as u32;
+    }
+}
+
+/// How colours between two ramp entries are split between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dither {
+    /// Thresholds from the `BlueNoise` texture: an even grain without patterns.
+    #[default]
+    BlueNoise,
+    /// Ordered 4×4 Bayer matrix: a regular crosshatch.
+    Bayer4,
+    /// Ordered 8×8 Bayer matrix: a finer crosshatch with more steps.
+    Bayer8,
+    /// Snap to the nearest entry, leaving flat bands.
+    None,
+}
+
+impl Dither {
+    pub const ALL: [Dither; 4] = [Dither::BlueNoise, Dither::Bayer4, Dither::Bayer8, Dither::None];
+}
+
+/// `PaletteSqueeze` as laid out in `palette_squeeze.wgsl`.
+#[derive(Component, Clone, Copy, ShaderType)]
+pub struct PaletteSqueezeUniform {
+    resolution: Vec3,
+    time: f32,
+    darken: f32,
+    darken_style: u32,
+    dither: u32,
+    ramp_size: u32,
+    highlight_count: u32,
+    down_scale: f32,
+    highlight_threshold: f32,
+    instability: f32,
+    flicker: f32,
+    ramp: [Vec4; MAX_RAMP],
+    highlights: [Vec4; MAX_HIGHLIGHTS],
+}
+
+impl ExtractComponent for PaletteSqueeze {
+    type QueryData = &'static Self;
+    type QueryFilter = ();
+    type Out = PaletteSqueezeUniform;
+
+    fn extract_component(squeeze: QueryItem<'_, '_, Self::QueryData>) -> Option<Self::Out> {
+        Some(PaletteSqueezeUniform {
+            resolution: squeeze.resolution,
+            time: squeeze.time,
+            darken: squeeze.darken,
+            darken_style: squeeze.darken_style as u32,
+            dither: squeeze.dither as u32,
+            ramp_size: squeeze.ramp_size,
+            highlight_count: squeeze.highlight_count,
+            down_scale: squeeze.down_scale.max(1.0),
+            highlight_threshold: squeeze.highlight_threshold,
+            instability: squeeze.instability,
+            flicker: squeeze.flicker,
+            ramp: squeeze.ramp,
+            highlights: squeeze.highlights,
+        })
+    }
+}
+
+impl PostEffect for PaletteSqueeze {
+    const SHADER: &'static str = "shaders/palette_squeeze.wgsl";
+}
+
+/// Keeps low sanity from flashing the whole screen brighter and darker. On by default, for
+/// players sensitive to flashing; the main menu switches it.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReducedFlashing(pub bool);
+
+impl Default for ReducedFlashing {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Resource that other systems can write to control the palette darken effect.
+#[derive(Resource, Default)]
+pub struct PaletteDarken {
+    pub value: f32,
+    pub style: TransitionStyle,
+}
+
+#[derive(Deserialize)]
+struct PalettesRon {
+    environments: HashMap<Environment, String>,
+}
+
+/// The palette each environment is shown in. Environments without one use the default.
+#[derive(Resource, Default)]
+pub struct EnvironmentPalettes(pub HashMap<Environment, Handle<Palette>>);
+
+/// The palette on screen, blending from the previous one toward `to`.
+#[derive(Resource, Default)]
+pub struct ActivePalette {
+    from: Palette,
+    to: Palette,
+    /// 0.0 = `from`, 1.0 = `to`.
+    blend: f32,
+    /// Shown instead while set, e.g. picked in photo mode. Blending carries on underneath.
+    pub pinned: Option<Palette>,
+}
+
+impl ActivePalette {
+    /// The palette as currently shown.
+    pub fn current(&self) -> Palette {
+        self.pinned.clone().unwrap_or_else(|| self.blended())
+    }
+
+    /// Start blending from what is shown now to `palette`.
+    pub fn blend_to(&mut self, palette: Palette) {
+        self.from = self.blended();
+        self.to = palette;
+        self.blend = 0.0;
+    }
+
+    fn blended(&self) -> Palette {
+        self.from.blend(&self.to, self.blend)
+    }
+}
+
+fn load_environment_palettes(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let ron: PalettesRon = ron::from_str(PALETTES_RON).expect("Failed to parse palettes.ron");
+    let handles = ron
+        .environments
+        .into_iter()
+        .map(|(environment, path)| (environment, asset_server.load(path)))
+        .collect();
+    commands.insert_resource(EnvironmentPalettes(handles));
+}
+
+/// Blend to the current environment's palette once it has loaded, or back to the default
+/// outside of a run. Also picks up palette files changed on disk.
+fn follow_environment(
+    time: Res<Time>,
+    environment: Option<Res<State<Environment>>>,
+    palettes: Option<Res<EnvironmentPalettes>>,
+    assets: Res<Assets<Palette>>,
+    mut active: ResMut<ActivePalette>,
+) {
+    let fallback = Palette::default();
+    let target = environment
+        .zip(palettes)
+        .and_then(|(environment, palettes)| palettes.0.get(environment.get()).cloned())
+        .and_then(|handle| assets.get(&handle))
+        .unwrap_or(&fallback);
+    if *target != active.to {
+        active.blend_to(target.clone());
+    }
+    active.blend = (active.blend + time.delta_secs() / PALETTE_BLEND_SECS).min(1.0);
+}
+
+/// How far gone the player is, from 0.0 at `SANITY_STRAIN_START` sanity or above to 1.0 at none.
+fn sanity_strain(health: Option<&Health>) -> f32 {
+    health.map_or(0.0, |health| {
+        ((SANITY_STRAIN_START - health.fraction()) / SANITY_STRAIN_START).clamp(0.0, 1.0)
+    })
+}
+
+/// Feed the per-frame inputs of the post chain: screen size, time, darkening, the palette, and
+/// how badly low sanity warps, destabilises and flickers the picture.
+fn update_palette_squeeze(
+    time: Res<Time>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    darken: Option<Res<PaletteDarken>>,
+    health: Option<Res<Health>>,
+    active: Res<ActivePalette>,
+    reduced_flashing: Res<ReducedFlashing>,
+    mut squeeze_q: Query<&mut PaletteSqueeze>,
+    mut distortion_q: Query<&mut Distortion>,
+) {
+    let Ok(window) = window_q.single() else {
+        return;
+    };
+    let resolution = Vec3::new(window.width(), window.height(), 0.0);
+    let elapsed = time.elapsed_secs();
+    let (darken_val, darken_style) = darken.map_or((0.0, TransitionStyle::default()), |d| {
+        (d.value, d.style)
+    });
+    let palette = active.current();
+    let strain = sanity_strain(health.as_deref());
+
+    for mut squeeze in &mut squeeze_q {
+        squeeze.resolution = resolution;
+        squeeze.time = elapsed;
+        squeeze.darken = darken_val;
+        squeeze.darken_style = darken_style;
+        squeeze.instability = strain;
+        squeeze.flicker = if reduced_flashing.0 {
+            0.0
+        } else {
+            strain * strain.sqrt()
+        };
+        squeeze.set_palette(&palette);
+    }
+    for mut distortion in &mut distortion_q {
+        distortion.amount = strain * strain * MAX_SANITY_WARP;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameState;
+    use crate::environment::EnvironmentPlugin;
+    use crate::testing::TestApp;
+
+    #[test]
+    fn shipped_palettes_load() {
+        let ron: PalettesRon = ron::from_str(PALETTES_RON).unwrap();
+        for path in ron.environments.values() {
+            let file = format!("{}/assets/{path}", env!("CARGO_MANIFEST_DIR"));
+            let source = std::fs::read_to_string(&file).unwrap();
+            let palette = match path.rsplit_once('.') {
+                Some((_, "hex")) => Palette::from_hex(&source),
+                Some((_, "gpl")) => Palette::from_gpl(&source),
+                _ => Palette::from_ron(&source),
+            };
+            assert!(palette.is_ok(), "{path}: {}", palette.unwrap_err());
+        }
+    }
+
+    #[test]
+    fn each_environment_blends_into_its_own_palette() {
+        let mut app = TestApp::new().with_plugins((PalettePlugin, EnvironmentPlugin));
+        app.start_playing();
+        let red = Palette::from_hex("000000\nff0000\n").unwrap();
+        let handle = app.resource_mut::<Assets<Palette>>().add(red.clone());
+        app.app.insert_resource(EnvironmentPalettes(HashMap::from([(
+            Environment::Delirium,
+            handle,
+        )])));
+        let camera = app.app.world_mut().spawn(PaletteSqueeze::default()).id();
+
+        app.step();
+        assert_ne!(app.resource::<ActivePalette>().current(), red, "switched without blending");
+        app.advance(PALETTE_BLEND_SECS);
+        assert_eq!(app.resource::<ActivePalette>().current(), red);
+        let squeeze = app.app.world().get::<PaletteSqueeze>(camera).unwrap();
+        assert_eq!(squeeze.ramp_size, 2);
+        assert_eq!(squeeze.ramp[1], red.ramp[1].to_vec4());
+        assert_eq!(squeeze.highlight_count, 5);
+
+        app.resource_mut::<NextState<GameState>>().set(GameState::Menu);
+        app.advance(PALETTE_BLEND_SECS + 0.1);
+        assert_eq!(app.resource::<ActivePalette>().current(), Palette::default());
+    }
+
+    #[test]
+    fn low_sanity_strains_the_picture() {
+        let mut app = TestApp::new().with_plugins(PalettePlugin);
+        app.start_playing();
+        app.app
+            .insert_resource(Health::default())
+            .insert_resource(ReducedFlashing(false));
+        let camera = app
+            .app
+            .world_mut()
+            .spawn((PaletteSqueeze::default(), Distortion::default()))
+            .id();
+        let strain = |app: &mut TestApp| {
+            app.step();
+            let world = app.app.world();
+            let squeeze = world.get::<PaletteSqueeze>(camera).unwrap();
+            let distortion = world.get::<Distortion>(camera).unwrap();
+            (squeeze.instability, squeeze.flicker, distortion.amount)
+        };
+
+        assert_eq!(strain(&mut app), (0.0, 0.0, 0.0));
+        app.resource_mut::<Health>().current = SANITY_STRAIN_START;
+        assert_eq!(strain(&mut app), (0.0, 0.0, 0.0), "strained at the threshold");
+
+        app.resource_mut::<Health>().current = SANITY_STRAIN_START / 2.0;
+        let (instability, flicker, warp) = strain(&mut app);
+        assert!((instability - 0.5).abs() < 1e-5);
+        assert!(flicker > 0.0 && flicker < instability, "flicker should lag behind");
+        assert!(warp > 0.0 && warp < MAX_SANITY_WARP / 2.0);
+
+        app.resource_mut::<Health>().current = 0.0;
+        assert_eq!(strain(&mut app), (1.0, 1.0, MAX_SANITY_WARP));
+    }
+
+    #[test]
+    fn reduced_flashing_stops_the_flicker() {
+        let mut app = TestApp::new().with_plugins(PalettePlugin);
+        app.start_playing();
+        app.app.insert_resource(Health {
+            current: SANITY_STRAIN_START / 2.0,
+            ..default()
+        });
+        let camera = app.app.world_mut().spawn(PaletteSqueeze::default()).id();
+        let flicker = |app: &mut TestApp| {
+            app.step();
+            app.app.world().get::<PaletteSqueeze>(camera).unwrap().flicker
+        };
+
+        assert!(app.resource::<ReducedFlashing>().0, "should be on by default");
+        assert_eq!(flicker(&mut app), 0.0);
+
+        app.resource_mut::<ReducedFlashing>().0 = false;
+        assert!(flicker(&mut app) > 0.0);
+    }
+}
@@ -1,9 +1,14 @@
 use crate::GameState;
 use crate::dialog::DialogState;
+use crate::dialog::history::DialogLog;
 use crate::dispel::DispelState;
 use crate::environment::{Environment, RunTimer};
 use crate::health::Health;
 use crate::loading::{AudioAssets, FontAssets, TextureAssets};
+use crate::locale::Locale;
+use crate::photo_mode::PhotoMode;
+use crate::transition::{Ease, GameStateTransition, Transition};
+use bevy::input::mouse::{AccumulatedMouseScroll, MouseScrollUnit};
 use bevy::prelude::*;
 use bevy::text::FontSmoothing;
 use bevy::window::{CursorGrabMode, CursorOptions, PrimaryWindow};
@@ -16,7 +21,14 @@ impl Plugin for PausePlugin {
         app.add_systems(OnEnter(GameState::Playing), init_paused)
             .add_systems(
                 Update,
-                (pause_on_cursor_unlock, toggle_pause, manage_pause_menu, handle_pause_buttons)
+                (
+                    pause_on_cursor_unlock,
+                    toggle_pause,
+                    manage_pause_menu,
+                    handle_pause_buttons,
+                    handle_dialog_log_buttons,
+                    scroll_dialog_log,
+                )
                     .chain()
                     .run_if(in_state(GameState::Playing)),
             )
@@ -32,7 +44,7 @@ pub fn game_not_paused(paused: Option<Res<Paused>>) -> bool {
 }
 
 #[derive(Component)]
-struct PauseMenu;
+pub(crate) struct PauseMenu;
 
 #[derive(Component)]
 struct PauseContinue;
@@ -40,6 +52,27 @@ struct PauseContinue;
 #[derive(Component)]
 struct PauseExit;
 
+#[derive(Component)]
+pub(crate) struct PausePhotoMode;
+
+#[derive(Component)]
+struct PauseDialogLog;
+
+/// Overlay listing past dialog lines, spawned over the pause menu.
+#[derive(Component)]
+struct DialogLogPanel;
+
+#[derive(Component)]
+struct DialogLogScroll;
+
+#[derive(Component)]
+struct DialogLogBack;
+
+/// Pixels scrolled per mouse wheel line.
+const LOG_SCROLL_LINE: f32 = 32.0;
+/// Quitting to the menu smears the run away.
+const EXIT_TRANSITION: Transition = Transition::smear(0.8).eased(Ease::InQuad);
+
 #[derive(Component)]
 struct PauseStatText;
 
@@ -73,19 +106,25 @@ fn pause_on_cursor_unlock(
     if dialog.is_some_and(|d| d.active) {
         return;
     }
-    if let Ok(cursor) = cursor_q.single() {
-        if cursor.grab_mode != CursorGrabMode::Locked {
-            paused.0 = true;
-        }
+    if let Ok(cursor) = cursor_q.single()
+        && cursor.grab_mode != CursorGrabMode::Locked
+    {
+        paused.0 = true;
     }
 }
 
 fn toggle_pause(
+    mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
     mut paused: ResMut<Paused>,
+    photo_mode: Option<Res<PhotoMode>>,
     mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
 ) {
-    if keyboard.just_pressed(KeyCode::Escape) {
+    if keyboard.just_pressed(KeyCode::Escape) && photo_mode.is_some() {
+        // Back to the pause menu rather than the game
+        commands.remove_resource::<PhotoMode>();
+        paused.set_changed();
+    } else if keyboard.just_pressed(KeyCode::Escape) {
         paused.0 = !paused.0;
         if let Ok(mut cursor) = cursor_q.single_mut() {
             if paused.0 {
@@ -101,22 +140,26 @@ fn toggle_pause(
 
 fn manage_pause_menu(
     paused: Res<Paused>,
+    photo_mode: Option<Res<PhotoMode>>,
     mut commands: Commands,
     menu_query: Query<Entity, With<PauseMenu>>,
     health: Option<Res<Health>>,
     run_timer: Option<Res<RunTimer>>,
     environment: Option<Res<State<Environment>>>,
+    locale: Res<Locale>,
     fonts: Res<FontAssets>,
     textures: Res<TextureAssets>,
 ) {
     let font = fonts.main.clone();
-    if !paused.is_changed() {
+    if !paused.is_changed() && !photo_mode.as_ref().is_some_and(|p| p.is_added()) {
         return;
     }
 
-    if paused.0 && menu_query.is_empty() {
+    // Photo mode takes the screen while the game stays paused
+    let show = paused.0 && photo_mode.is_none();
+    if show && menu_query.is_empty() {
         let health_pct = health.map_or(100.0, |h| h.fraction() * 100.0);
-        let env_name = environment.map_or("---", |e| e.get().label());
+        let env_name = environment.map_or("---", |e| locale.get(e.get().label_key()));
         let elapsed = run_timer.map_or(0.0, |t| t.elapsed);
         let minutes = (elapsed / 60.0) as u32;
         let seconds = (elapsed % 60.0) as u32;
@@ -166,7 +209,7 @@ fn manage_pause_menu(
                     .with_children(|modal| {
                         // Title
                         modal.spawn((
-                            Text::new("PAUSED"),
+                            Text::new(locale.get("pause.title")),
                             TextFont {
                                 font: font.clone(),
                                 font_size: 64.0,
@@ -181,9 +224,13 @@ fn manage_pause_menu(
                         ));
 
                         // Stats
-                        let stats = format!(
-                            "Sanity: {:.0}%\nEnvironment: {}\nTime: {}:{:02}",
-                            health_pct, env_name, minutes, seconds
+                        let stats = locale.format(
+                            "pause.stats",
+                            &[
+                                ("sanity", &format!("{health_pct:.0}")),
+                                ("environment", env_name),
+                                ("time", &format!("{minutes}:{seconds:02}")),
+                            ],
                         );
                         modal.spawn((
                             Text::new(stats),
@@ -221,7 +268,57 @@ fn manage_pause_menu(
                                 PauseContinue,
                             ))
                             .with_child((
-                                Text::new("Continue"),
+                                Text::new(locale.get("pause.continue")),
+                                textfont.clone(),
+                                TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                            ));
+
+                        // Dialog log button
+                        modal
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(200.0),
+                                    height: Val::Px(50.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect::bottom(Val::Px(10.0)),
+                                    ..default()
+                                },
+                                ImageNode {
+                                    image: textbox_image.clone(),
+                                    image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                                    ..default()
+                                },
+                                PauseDialogLog,
+                            ))
+                            .with_child((
+                                Text::new(locale.get("pause.dialog_log")),
+                                textfont.clone(),
+                                TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                            ));
+
+                        // Photo mode button
+                        modal
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(200.0),
+                                    height: Val::Px(50.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    margin: UiRect::bottom(Val::Px(10.0)),
+                                    ..default()
+                                },
+                                ImageNode {
+                                    image: textbox_image.clone(),
+                                    image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                                    ..default()
+                                },
+                                PausePhotoMode,
+                            ))
+                            .with_child((
+                                Text::new(locale.get("pause.photo_mode")),
                                 textfont.clone(),
                                 TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                             ));
@@ -245,13 +342,13 @@ fn manage_pause_menu(
                                 PauseExit,
                             ))
                             .with_child((
-                                Text::new("Exit to Menu"),
+                                Text::new(locale.get("pause.exit")),
                                 textfont.clone(),
                                 TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
                             ));
                     });
             });
-    } else if !paused.0 {
+    } else if !show {
         for entity in &menu_query {
             commands.entity(entity).despawn();
         }
@@ -259,10 +356,12 @@ fn manage_pause_menu(
 }
 
 fn handle_pause_buttons(
+    mut commands: Commands,
     mut paused: ResMut<Paused>,
-    mut next_state: ResMut<NextState<GameState>>,
+    mut states: GameStateTransition,
     continue_q: Query<&Interaction, (Changed<Interaction>, With<PauseContinue>)>,
     exit_q: Query<&Interaction, (Changed<Interaction>, With<PauseExit>)>,
+    photo_q: Query<&Interaction, (Changed<Interaction>, With<PausePhotoMode>)>,
     mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
@@ -277,17 +376,199 @@ fn handle_pause_buttons(
             }
         }
     }
+    for interaction in &photo_q {
+        if *interaction == Interaction::Pressed {
+            audio.play(audio_assets.fx1.clone());
+            commands.insert_resource(PhotoMode);
+        }
+    }
     for interaction in &exit_q {
         if *interaction == Interaction::Pressed {
             audio.play(audio_assets.fx1.clone());
-            paused.0 = false;
-            next_state.set(GameState::Menu);
+            // Stays paused while the screen fades; leaving Playing clears it
+            states.go_to(GameState::Menu, EXIT_TRANSITION);
+        }
+    }
+}
+
+fn handle_dialog_log_buttons(
+    mut commands: Commands,
+    open_q: Query<&Interaction, (Changed<Interaction>, With<PauseDialogLog>)>,
+    back_q: Query<&Interaction, (Changed<Interaction>, With<DialogLogBack>)>,
+    menu_query: Query<Entity, With<PauseMenu>>,
+    panel_query: Query<Entity, With<DialogLogPanel>>,
+    log: Option<Res<DialogLog>>,
+    locale: Res<Locale>,
+    fonts: Res<FontAssets>,
+    textures: Res<TextureAssets>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for interaction in &open_q {
+        if *interaction == Interaction::Pressed
+            && panel_query.is_empty()
+            && let Ok(menu) = menu_query.single()
+        {
+            audio.play(audio_assets.fx1.clone());
+            spawn_dialog_log(&mut commands, menu, log.as_deref(), &locale, &fonts, &textures);
+        }
+    }
+    for interaction in &back_q {
+        if *interaction == Interaction::Pressed {
+            audio.play(audio_assets.fx1.clone());
+            for entity in &panel_query {
+                commands.entity(entity).despawn();
+            }
         }
     }
 }
 
+/// Spawn the log as a child of the pause menu, so it closes along with it.
+fn spawn_dialog_log(
+    commands: &mut Commands,
+    menu: Entity,
+    log: Option<&DialogLog>,
+    locale: &Locale,
+    fonts: &FontAssets,
+    textures: &TextureAssets,
+) {
+    let textfont = TextFont {
+        font: fonts.main.clone(),
+        font_size: 24.0,
+        font_smoothing: FontSmoothing::None,
+        ..default()
+    };
+
+    commands.entity(menu).with_children(|parent| {
+        parent
+            .spawn((
+                Node {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    position_type: PositionType::Absolute,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                DialogLogPanel,
+            ))
+            .with_children(|overlay| {
+                overlay
+                    .spawn((
+                        Node {
+                            width: Val::Percent(70.0),
+                            height: Val::Percent(75.0),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            padding: UiRect::all(Val::Px(30.0)),
+                            row_gap: Val::Px(16.0),
+                            ..default()
+                        },
+                        ImageNode {
+                            image: textures.textbox.clone(),
+                            image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                            ..default()
+                        },
+                    ))
+                    .with_children(|modal| {
+                        modal.spawn((
+                            Text::new(locale.get("log.title")),
+                            TextFont {
+                                font_size: 48.0,
+                                ..textfont.clone()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+
+                        // Scrollable list, opened at the newest line
+                        modal
+                            .spawn((
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    flex_grow: 1.0,
+                                    flex_direction: FlexDirection::Column,
+                                    row_gap: Val::Px(8.0),
+                                    overflow: Overflow::scroll_y(),
+                                    ..default()
+                                },
+                                ScrollPosition(Vec2::new(0.0, f32::MAX)),
+                                DialogLogScroll,
+                            ))
+                            .with_children(|list| {
+                                let Some(log) = log.filter(|log| !log.is_empty()) else {
+                                    list.spawn((
+                                        Text::new(locale.get("log.empty")),
+                                        textfont.clone(),
+                                        TextColor(Color::srgba(0.8, 0.8, 0.8, 1.0)),
+                                    ));
+                                    return;
+                                };
+                                for entry in log.entries() {
+                                    list.spawn((
+                                        Text::new(format!("{}: ", entry.speaker)),
+                                        textfont.clone(),
+                                        TextColor(Color::srgba(0.6, 0.6, 0.6, 1.0)),
+                                    ))
+                                    .with_child((
+                                        TextSpan::new(entry.text.clone()),
+                                        textfont.clone(),
+                                        TextColor(Color::WHITE),
+                                    ));
+                                }
+                            });
+
+                        modal
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(200.0),
+                                    height: Val::Px(50.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ImageNode {
+                                    image: textures.textbox.clone(),
+                                    image_mode: NodeImageMode::Sliced(textbox_slicer()),
+                                    ..default()
+                                },
+                                DialogLogBack,
+                            ))
+                            .with_child((
+                                Text::new(locale.get("log.back")),
+                                TextFont {
+                                    font_size: 32.0,
+                                    ..textfont.clone()
+                                },
+                                TextColor(Color::linear_rgb(0.9, 0.9, 0.9)),
+                            ));
+                    });
+            });
+    });
+}
+
+fn scroll_dialog_log(
+    scroll: Res<AccumulatedMouseScroll>,
+    mut list_q: Query<(&mut ScrollPosition, &ComputedNode), With<DialogLogScroll>>,
+) {
+    if scroll.delta.y == 0.0 {
+        return;
+    }
+    let dy = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y * LOG_SCROLL_LINE,
+        MouseScrollUnit::Pixel => scroll.delta.y,
+    };
+    for (mut position, computed) in &mut list_q {
+        // Start from the laid-out position, which is clamped to the content
+        let current = computed.scroll_position.y * computed.inverse_scale_factor;
+        position.y = (current - dy).max(0.0);
+    }
+}
+
 fn cleanup_pause(mut commands: Commands, menu_query: Query<Entity, With<PauseMenu>>) {
     commands.remove_resource::<Paused>();
+    commands.remove_resource::<PhotoMode>();
     for entity in &menu_query {
         commands.entity(entity).despawn();
     }
@@ -0,0 +1,498 @@
+//! Photo mode, opened from the pause menu. The game stays paused while the `FpsCamera` comes
+//! off the player to fly freely, the HUD and dialog are hidden, the number keys switch post
+//! effects on and off and P cycles through the environments' palettes. Clicking takes a
+//! screenshot through `CaptureRequest`. Escape puts the camera, its effects and the palette
+//! back as they were and returns to the pause menu.
+
+use crate::GameState;
+use crate::capture::CaptureRequest;
+use crate::environment::Environment;
+use crate::loading::FontAssets;
+use crate::locale::Locale;
+use crate::palette::{ActivePalette, EnvironmentPalettes, Palette, PaletteSqueeze};
+use crate::player::{FpsCamera, Player};
+use crate::post_process::{
+    ChromaticAberration, Distortion, FilmGrain, PostEffect, PostStack, Scanlines, ScreenWarp,
+    Vignette,
+};
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+use bevy::text::FontSmoothing;
+use bevy::window::{CursorGrabMode, CursorOptions, PrimaryWindow};
+
+pub struct PhotoModePlugin;
+
+impl Plugin for PhotoModePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                enter_photo_mode.run_if(resource_added::<PhotoMode>),
+                (
+                    hide_ui,
+                    fly_camera,
+                    toggle_effects,
+                    cycle_palette,
+                    take_photos,
+                    update_panel,
+                )
+                    .chain()
+                    .run_if(resource_exists::<PhotoMode>),
+                leave_photo_mode.run_if(resource_removed::<PhotoMode>),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            OnExit(GameState::Playing),
+            (drop_photo_camera, leave_photo_mode).chain(),
+        );
+    }
+}
+
+/// Present while photo mode is open. The pause menu inserts it and removes it on Escape.
+#[derive(Resource)]
+pub struct PhotoMode;
+
+/// The camera while it is flying, with what it needs to go back on the player.
+#[derive(Component)]
+struct PhotoCamera {
+    /// Transform relative to the player.
+    home: Transform,
+    /// Effects as they were, undoing any toggling.
+    stack: Option<PostStack>,
+    yaw: f32,
+    pitch: f32,
+}
+
+/// The palette picked in photo mode, kept on the flying camera.
+#[derive(Component, Default)]
+struct PhotoPalette {
+    /// Environment whose palette is shown, or `None` for the scene's own.
+    choice: Option<Environment>,
+    /// `ActivePalette::pinned` as it was, to put back.
+    pinned_before: Option<Palette>,
+}
+
+/// A UI root hidden for photo mode, with the visibility to give back.
+#[derive(Component)]
+struct HiddenForPhoto(Visibility);
+
+/// Controls and effect list, shown on the window so it stays out of captures.
+#[derive(Component)]
+struct PhotoModePanel;
+
+const FLY_SPEED: f32 = 4.0;
+/// Speed multiplier while Shift is held.
+const FLY_FAST: f32 = 3.0;
+const LOOK_SENSITIVITY: f32 = 0.001;
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+/// Steps through the loaded environment palettes, then back to the scene's own.
+const PALETTE_KEY: KeyCode = KeyCode::KeyP;
+
+/// A number key switching one effect of the camera's `PostStack`.
+struct EffectToggle {
+    key: KeyCode,
+    label: &'static str,
+    toggle: fn(&mut PostStack),
+    is_enabled: fn(&PostStack) -> bool,
+}
+
+impl EffectToggle {
+    const fn of<E: PostEffect>(key: KeyCode, label: &'static str) -> Self {
+        Self {
+            key,
+            label,
+            toggle: PostStack::toggle::<E>,
+            is_enabled: PostStack::is_enabled::<E>,
+        }
+    }
+}
+
+const TOGGLES: [EffectToggle; 7] = [
+    EffectToggle::of::<PaletteSqueeze>(KeyCode::Digit1, "photo.effect.palette"),
+    EffectToggle::of::<Distortion>(KeyCode::Digit2, "photo.effect.distortion"),
+    EffectToggle::of::<ChromaticAberration>(KeyCode::Digit3, "photo.effect.chromatic"),
+    EffectToggle::of::<ScreenWarp>(KeyCode::Digit4, "photo.effect.warp"),
+    EffectToggle::of::<Scanlines>(KeyCode::Digit5, "photo.effect.scanlines"),
+    EffectToggle::of::<FilmGrain>(KeyCode::Digit6, "photo.effect.grain"),
+    EffectToggle::of::<Vignette>(KeyCode::Digit7, "photo.effect.vignette"),
+];
+
+fn enter_photo_mode(
+    mut commands: Commands,
+    camera_q: Query<(Entity, &GlobalTransform, &Transform, Option<&PostStack>), With<FpsCamera>>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+    fonts: Res<FontAssets>,
+    active: Option<Res<ActivePalette>>,
+) {
+    if let Ok((entity, global, home, stack)) = camera_q.single() {
+        let transform = global.compute_transform();
+        let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        commands.entity(entity).remove::<ChildOf>().insert((
+            transform,
+            PhotoCamera {
+                home: *home,
+                stack: stack.cloned(),
+                yaw,
+                pitch,
+            },
+            PhotoPalette {
+                choice: None,
+                pinned_before: active.and_then(|active| active.pinned.clone()),
+            },
+        ));
+    }
+    if let Ok(mut cursor) = cursor_q.single_mut() {
+        cursor.grab_mode = CursorGrabMode::Locked;
+        cursor.visible = false;
+    }
+
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font: fonts.main.clone(),
+            font_size: 16.0,
+            font_smoothing: FontSmoothing::None,
+            ..default()
+        },
+        TextColor(Color::srgba(0.9, 0.9, 0.9, 0.8)),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(8.0),
+            bottom: Val::Px(8.0),
+            ..default()
+        },
+        GlobalZIndex(100),
+        PhotoModePanel,
+    ));
+}
+
+/// Hide every UI root but the panel, including any that appear while photo mode is open.
+fn hide_ui(
+    mut commands: Commands,
+    mut root_q: Query<
+        (Entity, &mut Visibility),
+        (
+            With<Node>,
+            Without<ChildOf>,
+            Without<PhotoModePanel>,
+            Without<HiddenForPhoto>,
+        ),
+    >,
+) {
+    for (entity, mut visibility) in &mut root_q {
+        // The pause menu may be despawning this frame
+        commands.entity(entity).try_insert(HiddenForPhoto(*visibility));
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn fly_camera(
+    time: Res<Time>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    mut camera_q: Query<(&mut PhotoCamera, &mut Transform)>,
+) {
+    let Ok((mut camera, mut transform)) = camera_q.single_mut() else {
+        return;
+    };
+
+    camera.yaw -= mouse_motion.delta.x * LOOK_SENSITIVITY;
+    camera.pitch =
+        (camera.pitch - mouse_motion.delta.y * LOOK_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    transform.rotation = Quat::from_euler(EulerRot::YXZ, camera.yaw, camera.pitch, 0.0);
+
+    let held = |keys: [KeyCode; 2]| keyboard.any_pressed(keys) as i32 as f32;
+    let input = Vec3::new(
+        held([KeyCode::KeyD, KeyCode::ArrowRight]) - held([KeyCode::KeyA, KeyCode::ArrowLeft]),
+        held([KeyCode::Space, KeyCode::KeyE]) - held([KeyCode::ControlLeft, KeyCode::KeyQ]),
+        held([KeyCode::KeyS, KeyCode::ArrowDown]) - held([KeyCode::KeyW, KeyCode::ArrowUp]),
+    );
+    if input == Vec3::ZERO {
+        return;
+    }
+    // Forward and sideways follow the view, up and down stay vertical
+    let planar = transform.rotation * Vec3::new(input.x, 0.0, input.z);
+    let mut speed = FLY_SPEED * time.delta_secs();
+    if keyboard.pressed(KeyCode::ShiftLeft) {
+        speed *= FLY_FAST;
+    }
+    transform.translation += (planar + Vec3::Y * input.y).normalize_or_zero() * speed;
+}
+
+fn toggle_effects(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut stack_q: Query<&mut PostStack, With<PhotoCamera>>,
+) {
+    for mut stack in &mut stack_q {
+        for toggle in &TOGGLES {
+            if keyboard.just_pressed(toggle.key) {
+                (toggle.toggle)(&mut stack);
+            }
+        }
+    }
+}
+
+fn cycle_palette(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    palettes: Option<Res<EnvironmentPalettes>>,
+    assets: Option<Res<Assets<Palette>>>,
+    active: Option<ResMut<ActivePalette>>,
+    mut photo_q: Query<&mut PhotoPalette>,
+) {
+    if !keyboard.just_pressed(PALETTE_KEY) {
+        return;
+    }
+    let (Some(mut active), Ok(mut photo)) = (active, photo_q.single_mut()) else {
+        return;
+    };
+    let loaded: Vec<(Environment, &Palette)> = Environment::ALL
+        .into_iter()
+        .filter_map(|environment| {
+            let handle = palettes.as_ref()?.0.get(&environment)?;
+            Some((environment, assets.as_ref()?.get(handle)?))
+        })
+        .collect();
+    let next = photo.choice.as_ref().map_or(0, |choice| {
+        loaded
+            .iter()
+            .position(|(environment, _)| environment == choice)
+            .map_or(0, |i| i + 1)
+    });
+    match loaded.get(next) {
+        Some((environment, palette)) => {
+            photo.choice = Some(environment.clone());
+            active.pinned = Some((*palette).clone());
+        }
+        None => {
+            photo.choice = None;
+            active.pinned = photo.pinned_before.clone();
+        }
+    }
+}
+
+fn take_photos(mouse: Res<ButtonInput<MouseButton>>, mut requests: MessageWriter<CaptureRequest>) {
+    if mouse.just_pressed(MouseButton::Left) {
+        requests.write(CaptureRequest::Screenshot);
+    }
+}
+
+fn update_panel(
+    locale: Res<Locale>,
+    stack_q: Query<Ref<PostStack>, With<PhotoCamera>>,
+    photo_q: Query<Ref<PhotoPalette>>,
+    added_q: Query<(), Added<PhotoModePanel>>,
+    mut text_q: Query<&mut Text, With<PhotoModePanel>>,
+) {
+    let stack = stack_q.single().ok();
+    let photo = photo_q.single().ok();
+    if added_q.is_empty()
+        && !locale.is_changed()
+        && !stack.as_ref().is_some_and(|stack| stack.is_changed())
+        && !photo.as_ref().is_some_and(|photo| photo.is_changed())
+    {
+        return;
+    }
+
+    let mut lines = vec![
+        locale.get("photo.title").to_string(),
+        locale.get("photo.controls").to_string(),
+    ];
+    if let Some(stack) = &stack {
+        for (number, toggle) in TOGGLES.iter().enumerate() {
+            let state = if (toggle.is_enabled)(stack) {
+                "photo.on"
+            } else {
+                "photo.off"
+            };
+            lines.push(format!(
+                "[{}] {}: {}",
+                number + 1,
+                locale.get(toggle.label),
+                locale.get(state)
+            ));
+        }
+    }
+    if let Some(photo) = &photo {
+        let choice = photo.choice.as_ref().map_or("photo.colours.scene", Environment::label_key);
+        lines.push(format!(
+            "[P] {}: {}",
+            locale.get("photo.colours"),
+            locale.get(choice)
+        ));
+    }
+    for mut text in &mut text_q {
+        **text = lines.join("\n");
+    }
+}
+
+/// Put the camera back on the player, or drop it if the player is gone, and show the UI again.
+fn leave_photo_mode(
+    mut commands: Commands,
+    camera_q: Query<(Entity, &PhotoCamera, &PhotoPalette)>,
+    active: Option<ResMut<ActivePalette>>,
+    player_q: Query<Entity, With<Player>>,
+    mut hidden_q: Query<(Entity, &HiddenForPhoto, &mut Visibility)>,
+    panel_q: Query<Entity, With<PhotoModePanel>>,
+    mut cursor_q: Query<&mut CursorOptions, With<PrimaryWindow>>,
+) {
+    if let (Some(mut active), Ok((_, _, photo))) = (active, camera_q.single()) {
+        active.pinned = photo.pinned_before.clone();
+    }
+    for (entity, camera, _) in &camera_q {
+        let Ok(player) = player_q.single() else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        let mut camera_commands = commands.entity(entity);
+        camera_commands
+            .remove::<(PhotoCamera, PhotoPalette)>()
+            .insert(camera.home);
+        if let Some(stack) = &camera.stack {
+            camera_commands.insert(stack.clone());
+        }
+        commands.entity(player).add_child(entity);
+    }
+    for (entity, hidden, mut visibility) in &mut hidden_q {
+        *visibility = hidden.0;
+        commands.entity(entity).try_remove::<HiddenForPhoto>();
+    }
+    for entity in &panel_q {
+        commands.entity(entity).despawn();
+    }
+    // Back on the pause menu, which wants the pointer
+    if !camera_q.is_empty()
+        && let Ok(mut cursor) = cursor_q.single_mut()
+    {
+        cursor.grab_mode = CursorGrabMode::None;
+        cursor.visible = true;
+    }
+}
+
+/// Leaving `Playing` despawns the player, so there is nothing to put the camera back on.
+fn drop_photo_camera(
+    mut commands: Commands,
+    camera_q: Query<(Entity, &PhotoPalette), With<PhotoCamera>>,
+    active: Option<ResMut<ActivePalette>>,
+) {
+    if let (Some(mut active), Ok((_, photo))) = (active, camera_q.single()) {
+        active.pinned = photo.pinned_before.clone();
+    }
+    for (entity, _) in &camera_q {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::CapturePlugin;
+    use crate::pause::{PauseMenu, PausePhotoMode, PausePlugin, Paused};
+    use crate::testing::TestApp;
+
+    fn menu_is_open(app: &mut TestApp) -> bool {
+        let world = app.app.world_mut();
+        world.query::<&PauseMenu>().iter(world).next().is_some()
+    }
+
+    fn press_photo_mode_button(app: &mut TestApp) {
+        let world = app.app.world_mut();
+        let button = world
+            .query_filtered::<Entity, With<PausePhotoMode>>()
+            .single(world)
+            .expect("the pause menu has a photo mode button");
+        world.entity_mut(button).insert(Interaction::Pressed);
+        app.step();
+    }
+
+    #[test]
+    fn photo_mode_flies_the_camera_and_puts_it_back() {
+        let mut app = TestApp::new().with_plugins((PausePlugin, CapturePlugin, PhotoModePlugin));
+        app.start_playing();
+        let player = app.spawn_player(Vec3::new(0.0, 1.7, 5.0));
+        let camera = app
+            .app
+            .world_mut()
+            .query_filtered::<Entity, With<FpsCamera>>()
+            .single(app.app.world())
+            .unwrap();
+        let stack = PostStack::default()
+            .with::<PaletteSqueeze>(true)
+            .with::<Distortion>(true);
+        app.app.world_mut().entity_mut(camera).insert(stack);
+        let hud = app.app.world_mut().spawn(Node::default()).id();
+
+        app.tap_key(KeyCode::Escape);
+        assert!(app.resource::<Paused>().0);
+        assert!(menu_is_open(&mut app));
+        press_photo_mode_button(&mut app);
+        app.step();
+
+        assert!(app.has_resource::<PhotoMode>());
+        assert!(!menu_is_open(&mut app), "pause menu left up in photo mode");
+        let world = app.app.world();
+        assert!(world.get::<ChildOf>(camera).is_none());
+        assert_eq!(world.get::<Visibility>(hud), Some(&Visibility::Hidden));
+
+        app.press_key(KeyCode::KeyW);
+        app.advance(0.5);
+        app.release_key(KeyCode::KeyW);
+        app.tap_key(KeyCode::Digit1);
+
+        let world = app.app.world();
+        let flown = world.get::<Transform>(camera).unwrap().translation;
+        assert!(flown.z < 4.0, "camera at {flown}");
+        assert!(!world.get::<PostStack>(camera).unwrap().is_enabled::<PaletteSqueeze>());
+
+        // Escape leaves photo mode, not the pause
+        app.tap_key(KeyCode::Escape);
+        app.step();
+
+        assert!(!app.has_resource::<PhotoMode>());
+        assert!(app.resource::<Paused>().0);
+        assert!(menu_is_open(&mut app), "pause menu not rebuilt");
+        let world = app.app.world();
+        assert_eq!(world.get::<ChildOf>(camera).map(ChildOf::parent), Some(player));
+        assert_eq!(world.get::<Transform>(camera), Some(&Transform::default()));
+        assert!(world.get::<PostStack>(camera).unwrap().is_enabled::<PaletteSqueeze>());
+        assert_eq!(world.get::<Visibility>(hud), Some(&Visibility::Inherited));
+    }
+
+    #[test]
+    fn photo_mode_cycles_the_loaded_palettes_and_restores_the_scene() {
+        let mut app = TestApp::new().with_plugins((PausePlugin, CapturePlugin, PhotoModePlugin));
+        app.app
+            .init_asset::<Palette>()
+            .init_resource::<ActivePalette>();
+        app.start_playing();
+        app.spawn_player(Vec3::ZERO);
+        let red = Palette::from_hex("000000\nff0000\n").unwrap();
+        let blue = Palette::from_hex("000000\n0000ff\n").unwrap();
+        let mut assets = app.resource_mut::<Assets<Palette>>();
+        let palettes = EnvironmentPalettes(
+            [
+                (Environment::Hypervigilance, assets.add(blue.clone())),
+                (Environment::Delirium, assets.add(red.clone())),
+            ]
+            .into(),
+        );
+        app.app.insert_resource(palettes);
+        let scene = app.resource::<ActivePalette>().current();
+
+        app.tap_key(KeyCode::Escape);
+        press_photo_mode_button(&mut app);
+        app.step();
+
+        // Environment order, then back to the scene's own
+        for expected in [&red, &blue, &scene] {
+            app.tap_key(PALETTE_KEY);
+            assert_eq!(app.resource::<ActivePalette>().current(), *expected);
+        }
+
+        app.tap_key(PALETTE_KEY);
+        app.tap_key(KeyCode::Escape);
+        assert_eq!(app.resource::<ActivePalette>().current(), scene);
+        assert!(app.resource::<ActivePalette>().pinned.is_none());
+    }
+}
@@ -6,6 +6,9 @@ use crate::dialog::dialog_not_active;
 use crate::dispel::DispelState;
 use crate::palette::PaletteSqueeze;
 use crate::pause::game_not_paused;
+use crate::post_process::{
+    ChromaticAberration, Distortion, FilmGrain, PostStack, Scanlines, ScreenWarp, Vignette,
+};
 use crate::scaling::CanvasImage;
 use bevy::input::mouse::AccumulatedMouseMotion;
 use bevy::prelude::*;
@@ -63,7 +66,25 @@ fn spawn_player(mut commands: Commands, canvas: Res<CanvasImage>) {
                 bevy::camera::RenderTarget::from(canvas.0.clone()),
                 Transform::default(),
                 FpsCamera,
-                PaletteSqueeze::default(),
+                (
+                    Distortion::default(),
+                    ChromaticAberration::default(),
+                    ScreenWarp::default(),
+                    Scanlines::default(),
+                    FilmGrain::default(),
+                    Vignette::default(),
+                    PaletteSqueeze::default(),
+                    // Effects are there to switch on; only the sanity hook and the palette run
+                    // by default
+                    PostStack::default()
+                        .with::<Distortion>(true)
+                        .with::<ChromaticAberration>(false)
+                        .with::<ScreenWarp>(false)
+                        .with::<Scanlines>(false)
+                        .with::<FilmGrain>(false)
+                        .with::<Vignette>(false)
+                        .with::<PaletteSqueeze>(true),
+                ),
             ));
             parent.spawn((
                 PointLight {
@@ -91,11 +112,11 @@ fn player_mouse_look(
     }
 
     // Ensure cursor is locked whenever player has control
-    if let Ok(mut cursor) = cursor_q.single_mut() {
-        if cursor.grab_mode != CursorGrabMode::Locked {
-            cursor.grab_mode = CursorGrabMode::Locked;
-            cursor.visible = false;
-        }
+    if let Ok(mut cursor) = cursor_q.single_mut()
+        && cursor.grab_mode != CursorGrabMode::Locked
+    {
+        cursor.grab_mode = CursorGrabMode::Locked;
+        cursor.visible = false;
     }
 
     let Ok(mut actor) = player_query.single_mut() else {
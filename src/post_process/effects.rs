@@ -0,0 +1,133 @@
+//! The stock effects. Each is a camera component extracted as its own uniform; the matching
+//! struct in its shader must keep the same field order.
+
+use super::PostEffect;
+use bevy::prelude::*;
+use bevy::render::extract_component::ExtractComponent;
+use bevy::render::render_resource::ShaderType;
+
+/// Splits red and blue apart toward the screen edges, as through a cheap lens.
+#[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Debug)]
+pub struct ChromaticAberration {
+    /// Offset of the red and blue channels at the corners, in UV units.
+    pub intensity: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> Self {
+        Self { intensity: 0.006 }
+    }
+}
+
+impl PostEffect for ChromaticAberration {
+    const SHADER: &'static str = "shaders/post/chromatic_aberration.wgsl";
+}
+
+/// Bulges the image like an old CRT, blacking out what falls off the edge.
+#[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Debug)]
+pub struct ScreenWarp {
+    /// 0.0 = flat.
+    pub curvature: f32,
+}
+
+impl Default for ScreenWarp {
+    fn default() -> Self {
+        Self { curvature: 0.12 }
+    }
+}
+
+impl PostEffect for ScreenWarp {
+    const SHADER: &'static str = "shaders/post/screen_warp.wgsl";
+}
+
+/// Dark horizontal lines rolling slowly down the screen.
+#[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Debug)]
+pub struct Scanlines {
+    /// How dark the lines get, 0.0 to 1.0.
+    pub intensity: f32,
+    /// Distance from one line to the next, in pixels.
+    pub spacing: f32,
+    /// Pixels per second the lines roll down.
+    pub speed: f32,
+}
+
+impl Default for Scanlines {
+    fn default() -> Self {
+        Self {
+            intensity: 0.3,
+            spacing: 3.0,
+            speed: 6.0,
+        }
+    }
+}
+
+impl PostEffect for Scanlines {
+    const SHADER: &'static str = "shaders/post/scanlines.wgsl";
+}
+
+/// Flickering noise over the whole image, stronger in the shadows.
+#[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Debug)]
+pub struct FilmGrain {
+    /// Largest brightness change a grain makes.
+    pub intensity: f32,
+}
+
+impl Default for FilmGrain {
+    fn default() -> Self {
+        Self { intensity: 0.05 }
+    }
+}
+
+impl PostEffect for FilmGrain {
+    const SHADER: &'static str = "shaders/post/film_grain.wgsl";
+}
+
+/// Darkens the corners.
+#[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Debug)]
+pub struct Vignette {
+    /// How dark the corners get, 0.0 to 1.0.
+    pub intensity: f32,
+    /// Distance from the centre where darkening starts, with the corners at 1.0.
+    pub radius: f32,
+    /// Distance over which it fades in.
+    pub softness: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            intensity: 0.6,
+            radius: 0.6,
+            softness: 0.5,
+        }
+    }
+}
+
+impl PostEffect for Vignette {
+    const SHADER: &'static str = "shaders/post/vignette.wgsl";
+}
+
+/// Wobbles the image in slow waves, for when the player's grip on things is slipping.
+#[derive(Component, ExtractComponent, ShaderType, Clone, Copy, Debug)]
+pub struct Distortion {
+    /// Largest displacement, in UV units. 0.0 = off.
+    pub amount: f32,
+    /// Waves across the screen.
+    pub frequency: f32,
+    /// How fast the waves move.
+    pub speed: f32,
+}
+
+impl Default for Distortion {
+    fn default() -> Self {
+        Self {
+            amount: 0.0,
+            frequency: 6.0,
+            speed: 1.5,
+        }
+    }
+}
+
+impl PostEffect for Distortion {
+    const SHADER: &'static str = "shaders/post/distortion.wgsl";
+}
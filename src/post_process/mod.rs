@@ -0,0 +1,506 @@
+//! Fullscreen post-processing. Each effect is a component on the camera with its own uniform
+//! and fragment shader; this module owns the pipelines, bind groups and render graph node they
+//! share. Effects run after the UI pass, in the order of the camera's `PostStack`.
+
+use bevy::core_pipeline::FullscreenShader;
+use bevy::core_pipeline::core_2d::graph::{Core2d, Node2d};
+use bevy::core_pipeline::core_3d::graph::{Core3d, Node3d};
+use bevy::ecs::query::QueryItem;
+use bevy::asset::LoadState;
+use bevy::image::{BevyDefault, ImageLoaderSettings};
+use bevy::prelude::*;
+use bevy::render::extract_component::{
+    ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+    UniformComponentPlugin,
+};
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::globals::{GlobalsBuffer, GlobalsUniform};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphContext, RenderGraphExt, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::encase::internal::WriteInto;
+use bevy::render::render_resource::{
+    AddressMode, BindGroupEntries, BindGroupLayoutDescriptor, BindGroupLayoutEntries,
+    CachedRenderPipelineId, ColorTargetState, ColorWrites, FilterMode, FragmentState, Operations,
+    PipelineCache, RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, TextureFormat,
+    TextureSampleType,
+    binding_types::{sampler, texture_2d, uniform_buffer},
+};
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::texture::{FallbackImage, GpuImage};
+use bevy::render::view::ViewTarget;
+use bevy::render::{RenderApp, RenderStartup};
+use bevy::ui_render::graph::NodeUi;
+use std::any::TypeId;
+use std::marker::PhantomData;
+
+pub mod effects;
+pub mod noise;
+
+pub use effects::{
+    ChromaticAberration, Distortion, FilmGrain, Scanlines, ScreenWarp, Vignette,
+};
+
+/// Bindings every effect shader gets, imported as `game::post_process`.
+const COMMON_SHADER: &str = "shaders/post_process.wgsl";
+/// Blue noise to use in place of the generated one, if the file is there.
+const BLUE_NOISE_TEXTURE: &str = "textures/blue_noise_rgba.png";
+
+pub struct PostProcessPlugin;
+
+impl Plugin for PostProcessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<PostStack>::default(),
+            ExtractResourcePlugin::<BlueNoise>::default(),
+        ))
+        .add_systems(Startup, load_blue_noise)
+        .add_systems(
+            Update,
+            generate_missing_blue_noise.run_if(resource_exists::<BlueNoise>),
+        );
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .init_resource::<PostEffects>()
+                .add_systems(RenderStartup, init_post_process)
+                .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(
+                    Core3d,
+                    PostProcessLabel,
+                )
+                .add_render_graph_edges(
+                    Core3d,
+                    (NodeUi::UiPass, PostProcessLabel, Node3d::Upscaling),
+                )
+                .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(
+                    Core2d,
+                    PostProcessLabel,
+                )
+                .add_render_graph_edges(
+                    Core2d,
+                    (NodeUi::UiPass, PostProcessLabel, Node2d::Upscaling),
+                );
+        }
+
+        // Registration order is the order effects run in on cameras without a `PostStack`
+        app.add_plugins((
+            PostEffectPlugin::<Distortion>::default(),
+            PostEffectPlugin::<ChromaticAberration>::default(),
+            PostEffectPlugin::<ScreenWarp>::default(),
+            PostEffectPlugin::<Scanlines>::default(),
+            PostEffectPlugin::<FilmGrain>::default(),
+            PostEffectPlugin::<Vignette>::default(),
+        ));
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct PostProcessLabel;
+
+/// A fullscreen effect: a camera component extracted to a uniform, bound at binding 2 of
+/// `SHADER`, next to the shared bindings of `post_process.wgsl`. Register it with
+/// `PostEffectPlugin` after `PostProcessPlugin`.
+pub trait PostEffect: ExtractComponent<Out: Component + ShaderType + WriteInto + Clone> {
+    /// Fragment shader, relative to `assets/`.
+    const SHADER: &'static str;
+}
+
+/// Sets up the uniform, pipeline and render pass for one `PostEffect`.
+pub struct PostEffectPlugin<E>(PhantomData<fn() -> E>);
+
+impl<E> Default for PostEffectPlugin<E> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E: PostEffect> Plugin for PostEffectPlugin<E> {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<E>::default(),
+            UniformComponentPlugin::<E::Out>::default(),
+        ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .world_mut()
+            .get_resource_or_init::<PostEffects>()
+            .0
+            .push(EffectRunner {
+                id: EffectId::of::<E>(),
+                run: run_effect::<E>,
+            });
+        render_app.add_systems(RenderStartup, init_effect_pipeline::<E>);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EffectId(TypeId);
+
+impl EffectId {
+    fn of<E: PostEffect>() -> Self {
+        Self(TypeId::of::<E>())
+    }
+}
+
+/// Order of a camera's effects and which of them are on. Effects the camera has but the stack
+/// doesn't list run after the listed ones, in the order they were registered.
+#[derive(Component, ExtractComponent, Clone, Default, Debug)]
+pub struct PostStack {
+    entries: Vec<(EffectId, bool)>,
+}
+
+impl PostStack {
+    /// Append `E` to the end of the stack.
+    pub fn with<E: PostEffect>(mut self, enabled: bool) -> Self {
+        self.set_enabled::<E>(enabled);
+        self
+    }
+
+    pub fn is_enabled<E: PostEffect>(&self) -> bool {
+        self.entry(EffectId::of::<E>()).is_none_or(|(_, enabled)| *enabled)
+    }
+
+    /// Turn `E` on or off, appending it if it isn't listed yet.
+    pub fn set_enabled<E: PostEffect>(&mut self, enabled: bool) {
+        let id = EffectId::of::<E>();
+        match self.entries.iter_mut().find(|(entry, _)| *entry == id) {
+            Some((_, on)) => *on = enabled,
+            None => self.entries.push((id, enabled)),
+        }
+    }
+
+    pub fn toggle<E: PostEffect>(&mut self) {
+        let enabled = self.is_enabled::<E>();
+        self.set_enabled::<E>(!enabled);
+    }
+
+    /// Move `E` to `index` in the stack, clamped to its end.
+    pub fn move_to<E: PostEffect>(&mut self, index: usize) {
+        let id = EffectId::of::<E>();
+        let entry = match self.entries.iter().position(|(entry, _)| *entry == id) {
+            Some(current) => self.entries.remove(current),
+            None => (id, true),
+        };
+        self.entries.insert(index.min(self.entries.len()), entry);
+    }
+
+    fn entry(&self, id: EffectId) -> Option<&(EffectId, bool)> {
+        self.entries.iter().find(|(entry, _)| *entry == id)
+    }
+
+    /// The enabled effects out of `registered`, in the order they should run.
+    fn order(&self, registered: &[EffectId]) -> Vec<EffectId> {
+        let listed = self
+            .entries
+            .iter()
+            .filter(|(id, enabled)| *enabled && registered.contains(id))
+            .map(|(id, _)| *id);
+        let unlisted = registered.iter().copied().filter(|id| self.entry(*id).is_none());
+        listed.chain(unlisted).collect()
+    }
+}
+
+/// Tiling texture of blue-noise thresholds, read from its red channel, for dithering and
+/// grain. Loaded at startup from `BLUE_NOISE_TEXTURE` unless the app inserts one first, and
+/// generated instead if that file can't be loaded.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct BlueNoise(pub Handle<Image>);
+
+fn load_blue_noise(
+    mut commands: Commands,
+    noise: Option<Res<BlueNoise>>,
+    asset_server: Res<AssetServer>,
+) {
+    if noise.is_none() {
+        // Thresholds, not colours
+        let handle = asset_server.load_with_settings(
+            BLUE_NOISE_TEXTURE,
+            |settings: &mut ImageLoaderSettings| settings.is_srgb = false,
+        );
+        commands.insert_resource(BlueNoise(handle));
+    }
+}
+
+fn generate_missing_blue_noise(
+    mut noise: ResMut<BlueNoise>,
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if let Some(LoadState::Failed(_)) = asset_server.get_load_state(&noise.0) {
+        noise.0 = images.add(noise::blue_noise_image());
+    }
+}
+
+/// Every registered effect, in registration order.
+#[derive(Resource, Default)]
+struct PostEffects(Vec<EffectRunner>);
+
+type RunEffect = for<'w> fn(&'w World, &mut RenderContext<'w>, Entity, &ViewTarget);
+
+struct EffectRunner {
+    id: EffectId,
+    run: RunEffect,
+}
+
+/// Render world state shared by every effect.
+#[derive(Resource)]
+struct PostProcessShared {
+    sampler: Sampler,
+    noise_sampler: Sampler,
+    /// Kept loaded so effect shaders can import it.
+    _common_shader: Handle<Shader>,
+}
+
+fn init_post_process(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    asset_server: Res<AssetServer>,
+) {
+    commands.insert_resource(PostProcessShared {
+        sampler: render_device.create_sampler(&SamplerDescriptor::default()),
+        noise_sampler: render_device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..default()
+        }),
+        _common_shader: asset_server.load(COMMON_SHADER),
+    });
+}
+
+#[derive(Resource)]
+struct EffectPipeline<E> {
+    layout: BindGroupLayoutDescriptor,
+    pipeline_id: CachedRenderPipelineId,
+    pipeline_id_hdr: CachedRenderPipelineId,
+    marker: PhantomData<fn() -> E>,
+}
+
+fn init_effect_pipeline<E: PostEffect>(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    fullscreen_shader: Res<FullscreenShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    let layout = BindGroupLayoutDescriptor::new(
+        "post_process_bind_group_layout",
+        &BindGroupLayoutEntries::sequential(
+            ShaderStages::FRAGMENT,
+            (
+                // binding 0: screen texture
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                // binding 1: screen sampler
+                sampler(SamplerBindingType::Filtering),
+                // binding 2: the effect's uniform
+                uniform_buffer::<E::Out>(true),
+                // binding 3: noise texture
+                texture_2d(TextureSampleType::Float { filterable: true }),
+                // binding 4: noise sampler
+                sampler(SamplerBindingType::Filtering),
+                // binding 5: globals
+                uniform_buffer::<GlobalsUniform>(false),
+            ),
+        ),
+    );
+
+    let mut desc = RenderPipelineDescriptor {
+        label: Some("post_process_pipeline".into()),
+        layout: vec![layout.clone()],
+        vertex: fullscreen_shader.to_vertex_state(),
+        fragment: Some(FragmentState {
+            shader: asset_server.load(E::SHADER),
+            targets: vec![Some(ColorTargetState {
+                format: TextureFormat::bevy_default(),
+                blend: None,
+                write_mask: ColorWrites::ALL,
+            })],
+            ..default()
+        }),
+        ..default()
+    };
+
+    let pipeline_id = pipeline_cache.queue_render_pipeline(desc.clone());
+    desc.fragment.as_mut().unwrap().targets[0]
+        .as_mut()
+        .unwrap()
+        .format = ViewTarget::TEXTURE_FORMAT_HDR;
+    let pipeline_id_hdr = pipeline_cache.queue_render_pipeline(desc);
+
+    commands.insert_resource(EffectPipeline::<E> {
+        layout,
+        pipeline_id,
+        pipeline_id_hdr,
+        marker: PhantomData,
+    });
+}
+
+#[derive(Default)]
+struct PostProcessNode;
+
+impl ViewNode for PostProcessNode {
+    type ViewQuery = (&'static ViewTarget, Option<&'static PostStack>);
+
+    fn run<'w>(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext<'w>,
+        (view_target, stack): QueryItem<'w, '_, Self::ViewQuery>,
+        world: &'w World,
+    ) -> Result<(), NodeRunError> {
+        let effects = &world.resource::<PostEffects>().0;
+        let registered: Vec<EffectId> = effects.iter().map(|effect| effect.id).collect();
+        let order = stack.cloned().unwrap_or_default().order(&registered);
+        for id in order {
+            if let Some(effect) = effects.iter().find(|effect| effect.id == id) {
+                (effect.run)(world, render_context, graph.view_entity(), view_target);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One pass of `E`, if the view has it and everything it needs is ready.
+fn run_effect<'w, E: PostEffect>(
+    world: &'w World,
+    render_context: &mut RenderContext<'w>,
+    view: Entity,
+    view_target: &ViewTarget,
+) {
+    let Some(uniform_index) = world.get::<DynamicUniformIndex<E::Out>>(view) else {
+        return;
+    };
+    let (Some(pipeline_res), Some(shared)) = (
+        world.get_resource::<EffectPipeline<E>>(),
+        world.get_resource::<PostProcessShared>(),
+    ) else {
+        return;
+    };
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let pipeline_id = if view_target.is_hdr() {
+        pipeline_res.pipeline_id_hdr
+    } else {
+        pipeline_res.pipeline_id
+    };
+    let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
+        return;
+    };
+    let Some(uniform_binding) = world.resource::<ComponentUniforms<E::Out>>().uniforms().binding()
+    else {
+        return;
+    };
+    let Some(globals_binding) = world.resource::<GlobalsBuffer>().buffer.binding() else {
+        return;
+    };
+
+    // Until the noise reaches the GPU, use the fallback image rather than skip the effect
+    let noise_gpu = world
+        .get_resource::<BlueNoise>()
+        .and_then(|noise| world.resource::<RenderAssets<GpuImage>>().get(&noise.0))
+        .unwrap_or(&world.resource::<FallbackImage>().d2);
+
+    let post_process = view_target.post_process_write();
+
+    let bind_group = render_context.render_device().create_bind_group(
+        "post_process_bind_group",
+        &pipeline_cache.get_bind_group_layout(&pipeline_res.layout),
+        &BindGroupEntries::sequential((
+            post_process.source,
+            &shared.sampler,
+            uniform_binding,
+            &noise_gpu.texture_view,
+            &shared.noise_sampler,
+            globals_binding,
+        )),
+    );
+
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("post_process_pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: post_process.destination,
+            depth_slice: None,
+            resolve_target: None,
+            ops: Operations::default(),
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+
+    render_pass.set_render_pipeline(pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+    render_pass.draw(0..3, 0..1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::palette::PaletteSqueeze;
+    use crate::testing::TestApp;
+
+    fn registered() -> Vec<EffectId> {
+        vec![
+            EffectId::of::<Distortion>(),
+            EffectId::of::<FilmGrain>(),
+            EffectId::of::<Vignette>(),
+            EffectId::of::<PaletteSqueeze>(),
+        ]
+    }
+
+    #[test]
+    fn effects_without_a_stack_run_in_registration_order() {
+        let order = PostStack::default().order(&registered());
+        assert_eq!(order, registered());
+    }
+
+    #[test]
+    fn stacks_reorder_and_toggle_effects() {
+        let mut stack = PostStack::default()
+            .with::<Vignette>(true)
+            .with::<Scanlines>(true)
+            .with::<FilmGrain>(false);
+        // Scanlines isn't registered, so it's skipped; the unlisted ones follow the stack
+        assert_eq!(
+            stack.order(&registered()),
+            vec![
+                EffectId::of::<Vignette>(),
+                EffectId::of::<Distortion>(),
+                EffectId::of::<PaletteSqueeze>(),
+            ]
+        );
+
+        stack.toggle::<FilmGrain>();
+        stack.toggle::<Vignette>();
+        stack.move_to::<FilmGrain>(0);
+        assert!(stack.is_enabled::<FilmGrain>());
+        assert!(!stack.is_enabled::<Vignette>());
+        assert!(stack.is_enabled::<Distortion>(), "unlisted effects are on");
+        assert_eq!(
+            stack.order(&registered()),
+            vec![
+                EffectId::of::<FilmGrain>(),
+                EffectId::of::<Distortion>(),
+                EffectId::of::<PaletteSqueeze>(),
+            ]
+        );
+    }
+
+    #[test]
+    fn blue_noise_is_generated_without_a_texture() {
+        let mut app = TestApp::new().with_plugins(PostProcessPlugin);
+        for _ in 0..600 {
+            app.step();
+            let noise = &app.resource::<BlueNoise>().0;
+            if let Some(image) = app.resource::<Assets<Image>>().get(noise) {
+                assert_eq!(image.texture_descriptor.format, TextureFormat::R8Unorm);
+                return;
+            }
+        }
+        panic!("no blue noise after the texture failed to load");
+    }
+}
@@ -0,0 +1,189 @@
+//! Blue noise for dithering and grain, made with Ulichney's void-and-cluster method.
+//!
+//! Every pixel of a tile is ranked by the order it gets switched on, always filling the
+//! largest empty space first, so the pixels under any threshold are spread evenly, with no
+//! clumps and no grid. The tile wraps around, so it repeats across the screen without seams.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Side of the generated tile, in pixels.
+pub const NOISE_SIZE: u32 = 64;
+/// Width of the Gaussian measuring how crowded a pixel's neighbourhood is.
+const SIGMA: f32 = 1.5;
+/// Share of pixels switched on in the starting pattern.
+const INITIAL_DENSITY: f32 = 0.1;
+/// Seed for the starting pattern, so the dither looks the same every run.
+const SEED: u64 = 0x5eed;
+
+/// A greyscale `NOISE_SIZE` tile of dither thresholds.
+pub fn blue_noise_image() -> Image {
+    let ranks = void_and_cluster(NOISE_SIZE, &mut StdRng::seed_from_u64(SEED));
+    let count = ranks.len() as u64;
+    let data = ranks.iter().map(|&rank| (rank as u64 * 256 / count) as u8).collect();
+    Image::new(
+        Extent3d {
+            width: NOISE_SIZE,
+            height: NOISE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::R8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+/// Rank of each pixel of a `size`×`size` tile, row by row: a permutation of `0..size²`.
+pub fn void_and_cluster(size: u32, rng: &mut impl Rng) -> Vec<u32> {
+    let count = (size * size) as usize;
+    let mut pattern = Pattern::new(size);
+
+    // Start from a sparse random pattern
+    let initial = ((count as f32 * INITIAL_DENSITY) as usize).max(1);
+    while pattern.on_count < initial {
+        let p = rng.random_range(0..count);
+        if !pattern.on[p] {
+            pattern.set(p, true);
+        }
+    }
+
+    // Even it out: move the tightest cluster into the largest void until it stays put
+    for _ in 0..count {
+        let cluster = pattern.tightest_cluster();
+        pattern.set(cluster, false);
+        let void = pattern.largest_void();
+        pattern.set(void, true);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+
+    // The starting pixels rank below it, the most crowded highest
+    let mut removing = pattern.clone();
+    while removing.on_count > 0 {
+        let cluster = removing.tightest_cluster();
+        removing.set(cluster, false);
+        ranks[cluster] = removing.on_count as u32;
+    }
+
+    // Every other pixel ranks by when it fills the largest void left
+    while pattern.on_count < count {
+        let void = pattern.largest_void();
+        ranks[void] = pattern.on_count as u32;
+        pattern.set(void, true);
+    }
+
+    ranks
+}
+
+/// A binary pattern with how crowded each pixel's surroundings are.
+#[derive(Clone)]
+struct Pattern {
+    size: u32,
+    on: Vec<bool>,
+    on_count: usize,
+    /// Sum of the Gaussian over every pixel that is on, at each pixel.
+    energy: Vec<f32>,
+    /// The Gaussian by wrapped offset, row by row.
+    gaussian: Vec<f32>,
+}
+
+impl Pattern {
+    fn new(size: u32) -> Self {
+        let count = (size * size) as usize;
+        let gaussian = (0..count)
+            .map(|i| {
+                let wrapped = |d: u32| d.min(size - d) as f32;
+                let dx = wrapped(i as u32 % size);
+                let dy = wrapped(i as u32 / size);
+                (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+            })
+            .collect();
+        Self {
+            size,
+            on: vec![false; count],
+            on_count: 0,
+            energy: vec![0.0; count],
+            gaussian,
+        }
+    }
+
+    fn set(&mut self, p: usize, on: bool) {
+        if self.on[p] == on {
+            return;
+        }
+        self.on[p] = on;
+        if on {
+            self.on_count += 1;
+        } else {
+            self.on_count -= 1;
+        }
+
+        let sign = if on { 1.0 } else { -1.0 };
+        let size = self.size as usize;
+        let (px, py) = (p % size, p / size);
+        for (q, energy) in self.energy.iter_mut().enumerate() {
+            let dx = (q % size + size - px) % size;
+            let dy = (q / size + size - py) % size;
+            *energy += sign * self.gaussian[dy * size + dx];
+        }
+    }
+
+    /// The pixel that is on with the most company.
+    fn tightest_cluster(&self) -> usize {
+        self.extreme(true, |a, b| a > b)
+    }
+
+    /// The pixel that is off with the least company.
+    fn largest_void(&self) -> usize {
+        self.extreme(false, |a, b| a < b)
+    }
+
+    fn extreme(&self, on: bool, better: impl Fn(f32, f32) -> bool) -> usize {
+        let mut best: Option<usize> = None;
+        for (p, &energy) in self.energy.iter().enumerate() {
+            if self.on[p] == on && best.is_none_or(|b| better(energy, self.energy[b])) {
+                best = Some(p);
+            }
+        }
+        best.expect("pattern has a pixel in that state")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_pixel_gets_its_own_rank() {
+        let mut ranks = void_and_cluster(16, &mut StdRng::seed_from_u64(SEED));
+        ranks.sort();
+        assert_eq!(ranks, (0..256).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn low_thresholds_are_spread_out() {
+        let size = 32;
+        let ranks = void_and_cluster(size, &mut StdRng::seed_from_u64(SEED));
+        // One pixel in sixteen: about four pixels apart if perfectly even
+        let points: Vec<(i32, i32)> = (0..ranks.len())
+            .filter(|&p| ranks[p] < 64)
+            .map(|p| ((p as u32 % size) as i32, (p as u32 / size) as i32))
+            .collect();
+
+        let wrapped = |d: i32| d.abs().min(size as i32 - d.abs());
+        for (i, a) in points.iter().enumerate() {
+            for b in &points[i + 1..] {
+                let (dx, dy) = (wrapped(a.0 - b.0), wrapped(a.1 - b.1));
+                let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                assert!(distance >= 2.0, "{a:?} and {b:?} are only {distance} apart");
+            }
+        }
+    }
+}
@@ -1,20 +1,154 @@
+//! The world and menu render into a low-resolution canvas image, which an upscale camera draws
+//! onto the window. `CanvasSettings` picks the canvas size and how it is fitted to the window;
+//! `CanvasLayout` is the resulting placement, for converting between window and canvas pixels.
+
+use bevy::image::ImageSampler;
 use bevy::prelude::*;
-use bevy::render::render_resource::TextureFormat;
+use bevy::render::render_resource::{Extent3d, TextureFormat};
 use bevy::window::PrimaryWindow;
 
 pub struct ScalingPlugin;
 
 impl Plugin for ScalingPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_canvas)
-            .add_systems(Update, resize_canvas);
+        app.init_resource::<CanvasSettings>()
+            .init_resource::<CanvasLayout>()
+            .add_systems(Startup, setup_canvas)
+            .add_systems(Update, apply_canvas_settings);
+    }
+}
+
+/// How big the canvas is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanvasResolution {
+    /// The window's size in physical pixels divided by this, so it always scales evenly.
+    Window(u32),
+    /// A fixed size, however big the window.
+    Fixed(UVec2),
+}
+
+impl CanvasResolution {
+    /// The choices offered in the menu, in the order it cycles through them.
+    pub const PRESETS: [CanvasResolution; 4] = [
+        CanvasResolution::Window(2),
+        CanvasResolution::Fixed(UVec2::new(320, 180)),
+        CanvasResolution::Fixed(UVec2::new(480, 270)),
+        CanvasResolution::Fixed(UVec2::new(640, 360)),
+    ];
+
+    /// Canvas size for a window of `window` physical pixels.
+    pub fn size(self, window: UVec2) -> UVec2 {
+        match self {
+            CanvasResolution::Window(divisor) => window / divisor.max(1),
+            CanvasResolution::Fixed(size) => size,
+        }
+        .max(UVec2::ONE)
     }
 }
 
-/// The scale factor: game renders at 1/CANVAS_SCALE of window resolution.
-pub const CANVAS_SCALE: f32 = 2.0;
+/// How the canvas is fitted to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CanvasScaling {
+    /// Fill the window, stretching the picture if the aspect ratios differ.
+    Stretch,
+    /// As big as fits with the aspect ratio kept, with bars on two sides.
+    Fit,
+    /// The largest whole multiple that fits, with bars around it, so every canvas pixel
+    /// covers the same number of window pixels. Falls back to `Fit` in too small a window.
+    #[default]
+    Integer,
+}
+
+impl CanvasScaling {
+    pub const ALL: [CanvasScaling; 3] = [
+        CanvasScaling::Stretch,
+        CanvasScaling::Fit,
+        CanvasScaling::Integer,
+    ];
+}
 
-/// Handle to the half-resolution render target image.
+/// Canvas size and presentation, changed from the menu.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CanvasSettings {
+    pub resolution: CanvasResolution,
+    pub scaling: CanvasScaling,
+    /// Sample the canvas without filtering and keep it on whole window pixels. Off, it is
+    /// filtered, which smooths out uneven scales at the cost of sharpness.
+    pub pixel_perfect: bool,
+}
+
+impl Default for CanvasSettings {
+    fn default() -> Self {
+        Self {
+            resolution: CanvasResolution::Window(2),
+            scaling: CanvasScaling::Integer,
+            pixel_perfect: true,
+        }
+    }
+}
+
+/// Where the canvas sits in the window, in logical window pixels.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct CanvasLayout {
+    /// Canvas size in canvas pixels.
+    pub size: UVec2,
+    /// Window pixels per canvas pixel on each axis.
+    pub scale: Vec2,
+    /// Top-left corner of the canvas.
+    pub offset: Vec2,
+}
+
+impl Default for CanvasLayout {
+    fn default() -> Self {
+        Self {
+            size: UVec2::new(640, 360),
+            scale: Vec2::splat(2.0),
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl CanvasLayout {
+    /// Lay out the canvas for a window of `window` physical pixels at `scale_factor`.
+    pub fn new(settings: &CanvasSettings, window: UVec2, scale_factor: f32) -> Self {
+        let size = settings.resolution.size(window);
+        let ratio = window.as_vec2() / size.as_vec2();
+        let fit = ratio.min_element();
+        let scale = match settings.scaling {
+            CanvasScaling::Stretch => ratio,
+            CanvasScaling::Fit => Vec2::splat(fit),
+            CanvasScaling::Integer if fit >= 1.0 => Vec2::splat(fit.floor()),
+            CanvasScaling::Integer => Vec2::splat(fit),
+        };
+        // Rounding can leave a hair under zero when the canvas exactly fills an axis
+        let mut offset = ((window.as_vec2() - size.as_vec2() * scale) / 2.0).max(Vec2::ZERO);
+        if settings.pixel_perfect {
+            offset = offset.floor();
+        }
+        Self {
+            size,
+            scale: scale / scale_factor,
+            offset: offset / scale_factor,
+        }
+    }
+
+    /// Window position of a canvas pixel position, e.g. from `Camera::world_to_viewport`.
+    pub fn canvas_to_window(&self, position: Vec2) -> Vec2 {
+        self.offset + position * self.scale
+    }
+
+    /// Canvas position of a window position, e.g. the cursor.
+    pub fn window_to_canvas(&self, position: Vec2) -> Vec2 {
+        (position - self.offset) / self.scale
+    }
+
+    /// Size of the canvas on screen.
+    pub fn display_size(&self) -> Vec2 {
+        self.size.as_vec2() * self.scale
+    }
+}
+
+/// Handle to the low-resolution render target image.
 #[derive(Resource)]
 pub struct CanvasImage(pub Handle<Image>);
 
@@ -24,24 +158,14 @@ struct UpscaleCamera;
 #[derive(Component)]
 struct UpscaleSprite;
 
-fn setup_canvas(
-    mut commands: Commands,
-    mut images: ResMut<Assets<Image>>,
-    window_q: Query<&Window, With<PrimaryWindow>>,
-) {
-    let Ok(window) = window_q.single() else {
-        return;
-    };
-
-    let w = (window.physical_width() as f32 / CANVAS_SCALE) as u32;
-    let h = (window.physical_height() as f32 / CANVAS_SCALE) as u32;
-
-    let canvas = Image::new_target_texture(w.max(1), h.max(1), TextureFormat::Rgba8UnormSrgb, None);
+fn setup_canvas(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    // Sized properly by `apply_canvas_settings` once the window is known
+    let canvas = Image::new_target_texture(1, 1, TextureFormat::Rgba8UnormSrgb, None);
     let canvas_handle = images.add(canvas);
 
     commands.insert_resource(CanvasImage(canvas_handle.clone()));
 
-    // Upscale camera: renders to the window, displays the canvas image fullscreen
+    // Upscale camera: renders to the window, displays the canvas image
     commands.spawn((
         Camera2d,
         Camera {
@@ -52,22 +176,24 @@ fn setup_canvas(
         UpscaleCamera,
     ));
 
-    // Fullscreen sprite showing the half-res canvas
+    // Sprite showing the canvas; the clear colour around it makes the bars
     commands.spawn((
         Sprite {
             image: canvas_handle,
-            custom_size: Some(Vec2::new(window.width(), window.height())),
             ..default()
         },
         UpscaleSprite,
     ));
 }
 
-fn resize_canvas(
-    window_q: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+/// Resize and place the canvas when the window or the settings change.
+fn apply_canvas_settings(
+    window_q: Query<Ref<Window>, With<PrimaryWindow>>,
+    settings: Res<CanvasSettings>,
     canvas: Option<Res<CanvasImage>>,
+    mut layout: ResMut<CanvasLayout>,
     mut images: ResMut<Assets<Image>>,
-    mut sprite_q: Query<&mut Sprite, With<UpscaleSprite>>,
+    mut sprite_q: Query<(&mut Sprite, &mut Transform), With<UpscaleSprite>>,
 ) {
     let Ok(window) = window_q.single() else {
         return;
@@ -75,19 +201,95 @@ fn resize_canvas(
     let Some(canvas) = canvas else {
         return;
     };
+    if !window.is_changed() && !settings.is_changed() && !canvas.is_added() {
+        return;
+    }
 
-    let w = (window.physical_width() as f32 / CANVAS_SCALE) as u32;
-    let h = (window.physical_height() as f32 / CANVAS_SCALE) as u32;
+    let new_layout = CanvasLayout::new(&settings, window.physical_size(), window.scale_factor());
+    layout.set_if_neq(new_layout);
 
     if let Some(image) = images.get_mut(&canvas.0) {
-        image.resize(bevy::render::render_resource::Extent3d {
-            width: w.max(1),
-            height: h.max(1),
-            depth_or_array_layers: 1,
-        });
+        if image.size() != new_layout.size {
+            image.resize(Extent3d {
+                width: new_layout.size.x,
+                height: new_layout.size.y,
+                depth_or_array_layers: 1,
+            });
+        }
+        image.sampler = if settings.pixel_perfect {
+            ImageSampler::nearest()
+        } else {
+            ImageSampler::linear()
+        };
+    }
+
+    // Sprites are placed from the window centre, y up
+    let display = new_layout.display_size();
+    let centre = new_layout.offset + display / 2.0 - window.size() / 2.0;
+    for (mut sprite, mut transform) in &mut sprite_q {
+        sprite.custom_size = Some(display);
+        transform.translation = Vec3::new(centre.x, -centre.y, 0.0);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(resolution: CanvasResolution, scaling: CanvasScaling, window: UVec2) -> CanvasLayout {
+        let settings = CanvasSettings {
+            resolution,
+            scaling,
+            pixel_perfect: true,
+        };
+        CanvasLayout::new(&settings, window, 1.0)
+    }
+
+    #[test]
+    fn integer_scaling_letterboxes_fixed_resolutions() {
+        let small = CanvasResolution::Fixed(UVec2::new(320, 180));
+        let window = UVec2::new(1366, 768);
+
+        let integer = layout(small, CanvasScaling::Integer, window);
+        assert_eq!(integer.scale, Vec2::splat(4.0));
+        assert_eq!(integer.offset, Vec2::new(43.0, 24.0));
+
+        let fit = layout(small, CanvasScaling::Fit, window);
+        assert!((fit.display_size().x - 1365.3).abs() < 0.1);
+        assert_eq!(fit.offset.y, 0.0);
 
-    for mut sprite in &mut sprite_q {
-        sprite.custom_size = Some(Vec2::new(window.width(), window.height()));
+        let stretch = layout(small, CanvasScaling::Stretch, window);
+        assert!(stretch.display_size().abs_diff_eq(window.as_vec2(), 0.01));
+
+        // Too small a window shrinks it rather than cropping
+        let tiny = layout(small, CanvasScaling::Integer, UVec2::new(160, 90));
+        assert_eq!(tiny.scale, Vec2::splat(0.5));
+    }
+
+    #[test]
+    fn window_relative_canvases_track_the_window() {
+        let half = layout(
+            CanvasResolution::Window(2),
+            CanvasScaling::Integer,
+            UVec2::new(1281, 720),
+        );
+        assert_eq!(half.size, UVec2::new(640, 360));
+        assert_eq!(half.scale, Vec2::splat(2.0));
+        assert_eq!(half.offset, Vec2::ZERO);
+    }
+
+    #[test]
+    fn conversions_round_trip_and_respect_hidpi() {
+        let settings = CanvasSettings {
+            resolution: CanvasResolution::Fixed(UVec2::new(480, 270)),
+            ..default()
+        };
+        // 1920x1200 physical at 2x: scale 4 leaves 60 physical pixels of bars top and bottom
+        let layout = CanvasLayout::new(&settings, UVec2::new(1920, 1200), 2.0);
+        assert_eq!(layout.scale, Vec2::splat(2.0));
+        assert_eq!(layout.offset, Vec2::new(0.0, 30.0));
+        let point = Vec2::new(100.0, 50.0);
+        assert_eq!(layout.window_to_canvas(layout.canvas_to_window(point)), point);
+        assert_eq!(layout.canvas_to_window(Vec2::ZERO), layout.offset);
     }
 }
@@ -0,0 +1,231 @@
+//! Headless test harness for gameplay plugins.
+//!
+//! Builds an `App` from `MinimalPlugins` plus whichever `GamePlugin` subsystems a test needs.
+//! Presentation plugins (scaling, palette, menu, loading, audio output, world/player spawning)
+//! are left out, so nothing here needs a window, a GPU or an audio device. Input is injected by
+//! writing `ButtonInput` directly and time advances by a fixed step per update.
+
+use crate::GameState;
+use crate::actor::{Actor, ActorIntent};
+use crate::loading::{AudioAssets, FontAssets, TextureAssets};
+use crate::locale::Locale;
+use crate::player::{FpsCamera, Player};
+use crate::scaling::CanvasLayout;
+use bevy::app::Plugins;
+use bevy::asset::AssetPlugin;
+use bevy::camera::{CameraProjection, ComputedCameraValues, RenderTargetInfo};
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll};
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::{CursorGrabMode, CursorOptions, PrimaryWindow, WindowResolution};
+use bevy_kira_audio::Audio;
+use std::time::Duration;
+
+/// Simulated frame length.
+pub const STEP_SECS: f32 = 1.0 / 60.0;
+/// Size of the fake primary window, in logical pixels.
+pub const WINDOW_SIZE: UVec2 = UVec2::new(1280, 720);
+
+pub struct TestApp {
+    pub app: App,
+}
+
+impl TestApp {
+    /// An app with the engine plumbing gameplay systems expect, but no game plugins yet.
+    pub fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            TransformPlugin,
+            AssetPlugin::default(),
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            STEP_SECS,
+        )))
+        .init_asset::<Image>()
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .init_resource::<ButtonInput<MouseButton>>()
+        .init_resource::<AccumulatedMouseMotion>()
+        .init_resource::<AccumulatedMouseScroll>()
+        .init_resource::<Audio>()
+        .init_resource::<Locale>()
+        .init_resource::<UiScale>()
+        // The default layout is a half-size canvas filling a `WINDOW_SIZE` window
+        .init_resource::<CanvasLayout>()
+        .insert_resource(FontAssets {
+            main: Handle::default(),
+        })
+        .insert_resource(TextureAssets {
+            bevy: Handle::default(),
+            github: Handle::default(),
+            textbox: Handle::default(),
+            unknown: Handle::default(),
+            feather_cursor: Handle::default(),
+            splash: Handle::default(),
+            death: Handle::default(),
+            talk_bubble: Handle::default(),
+        })
+        .insert_resource(AudioAssets {
+            death: Handle::default(),
+            dispel: Handle::default(),
+            footsteps: Handle::default(),
+            fx1: Handle::default(),
+            talk: Handle::default(),
+        })
+        .init_state::<GameState>();
+
+        app.world_mut().spawn((
+            Window {
+                resolution: WindowResolution::new(WINDOW_SIZE.x, WINDOW_SIZE.y),
+                ..default()
+            },
+            // Locked like `PlayerPlugin` leaves it, or `PausePlugin` would pause straight away
+            CursorOptions {
+                grab_mode: CursorGrabMode::Locked,
+                visible: false,
+                ..default()
+            },
+            PrimaryWindow,
+        ));
+
+        Self { app }
+    }
+
+    pub fn with_plugins<M>(mut self, plugins: impl Plugins<M>) -> Self {
+        self.app.add_plugins(plugins);
+        self
+    }
+
+    /// Leave `Loading` for `Playing`, running the `OnEnter` systems.
+    pub fn start_playing(&mut self) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::Playing);
+        self.step();
+    }
+
+    /// Run a single frame, then clear the per-frame input edges like `InputPlugin` would.
+    pub fn step(&mut self) {
+        self.app.update();
+        self.app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .clear();
+        self.app
+            .world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .clear();
+    }
+
+    /// Run frames until at least `secs` of game time have passed.
+    pub fn advance(&mut self, secs: f32) {
+        let frames = (secs / STEP_SECS).ceil() as u32;
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    pub fn press_key(&mut self, key: KeyCode) {
+        self.app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+
+    pub fn release_key(&mut self, key: KeyCode) {
+        self.app
+            .world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+
+    /// Press a key for exactly one frame.
+    pub fn tap_key(&mut self, key: KeyCode) {
+        self.press_key(key);
+        self.step();
+        self.release_key(key);
+        self.step();
+    }
+
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        self.app
+            .world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .press(button);
+    }
+
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        self.app
+            .world_mut()
+            .resource_mut::<ButtonInput<MouseButton>>()
+            .release(button);
+    }
+
+    /// Move the fake cursor, in logical window pixels.
+    pub fn set_cursor(&mut self, pos: Vec2) {
+        let mut window = self
+            .app
+            .world_mut()
+            .query_filtered::<&mut Window, With<PrimaryWindow>>()
+            .single_mut(self.app.world_mut())
+            .expect("harness spawns a primary window");
+        window.set_cursor_position(Some(pos));
+    }
+
+    /// Spawn a player at `translation` with an `FpsCamera` child whose projection is filled in
+    /// by hand, since no render target exists to compute it. The camera covers the window at
+    /// half resolution, matching the real canvas.
+    pub fn spawn_player(&mut self, translation: Vec3) -> Entity {
+        let canvas_size = WINDOW_SIZE / 2;
+        let projection = PerspectiveProjection {
+            aspect_ratio: canvas_size.x as f32 / canvas_size.y as f32,
+            ..default()
+        };
+        let camera = Camera {
+            computed: ComputedCameraValues {
+                clip_from_view: projection.get_clip_from_view(),
+                target_info: Some(RenderTargetInfo {
+                    physical_size: canvas_size,
+                    scale_factor: 1.0,
+                }),
+                ..default()
+            },
+            ..default()
+        };
+
+        self.app
+            .world_mut()
+            .spawn((
+                Transform::from_translation(translation),
+                Visibility::default(),
+                Player,
+                Actor {
+                    speed: 7.0,
+                    height: translation.y,
+                    yaw: 0.0,
+                    vertical_velocity: 0.0,
+                    grounded: true,
+                },
+                ActorIntent::default(),
+            ))
+            .with_child((camera, Transform::default(), FpsCamera))
+            .id()
+    }
+
+    pub fn resource<R: Resource>(&self) -> &R {
+        self.app.world().resource::<R>()
+    }
+
+    pub fn resource_mut<R: Resource>(&mut self) -> Mut<'_, R> {
+        self.app.world_mut().resource_mut::<R>()
+    }
+
+    pub fn has_resource<R: Resource>(&self) -> bool {
+        self.app.world().contains_resource::<R>()
+    }
+}
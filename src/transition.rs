@@ -1,6 +1,9 @@
 use crate::GameState;
 use crate::palette::PaletteDarken;
+use crate::pause::{Paused, game_not_paused};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use std::collections::VecDeque;
 
 pub struct TransitionPlugin;
 
@@ -11,14 +14,152 @@ impl Plugin for TransitionPlugin {
             start_fade_in_on_enter_playing,
         )
         .add_systems(OnEnter(GameState::Menu), start_fade_in_on_enter_menu)
+        .add_message::<TransitionFinished>()
         .add_systems(Update, tick_transition);
     }
 }
 
-/// Controls a fade-to-black / fade-from-black transition via PaletteDarken.
-#[derive(Resource)]
+/// Coming back from the menu or the loading screen: the world smears into place.
+const ENTER_PLAYING: Transition = Transition::smear(1.0).eased(Ease::OutCubic);
+/// The menu opens out of a closing iris.
+const ENTER_MENU: Transition = Transition::iris(1.0).eased(Ease::OutQuad);
+
+/// How the screen goes dark, as drawn by the palette shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransitionStyle {
+    /// Everything dims evenly.
+    #[default]
+    Fade,
+    /// Pixels drop out one by one, in blue-noise order.
+    Dissolve,
+    /// A shrinking circle centred on the screen.
+    Iris,
+    /// Rows streak sideways at different speeds as they dim.
+    Smear,
+    /// Brightness wraps around the palette faster and faster, then sinks to its darkest entry.
+    PaletteCycle,
+}
+
+/// Easing curve from a transition's elapsed fraction to its darkness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ease {
+    #[default]
+    Linear,
+    /// Starts slow.
+    InQuad,
+    /// Ends slow.
+    OutQuad,
+    OutCubic,
+    /// Slow at both ends.
+    InOutCubic,
+}
+
+impl Ease {
+    /// `t` in 0.0..=1.0 mapped through the curve, keeping both ends fixed.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::InQuad => t * t,
+            Ease::OutQuad => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Ease::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// One fade, in or out: how it looks, how it is paced and how long it takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub style: TransitionStyle,
+    pub ease: Ease,
+    pub duration: f32,
+}
+
+impl Transition {
+    pub const fn fade(duration: f32) -> Self {
+        Self::new(TransitionStyle::Fade, duration)
+    }
+
+    pub const fn dissolve(duration: f32) -> Self {
+        Self::new(TransitionStyle::Dissolve, duration)
+    }
+
+    pub const fn iris(duration: f32) -> Self {
+        Self::new(TransitionStyle::Iris, duration)
+    }
+
+    pub const fn smear(duration: f32) -> Self {
+        Self::new(TransitionStyle::Smear, duration)
+    }
+
+    pub const fn palette_cycle(duration: f32) -> Self {
+        Self::new(TransitionStyle::PaletteCycle, duration)
+    }
+
+    const fn new(style: TransitionStyle, duration: f32) -> Self {
+        Self {
+            style,
+            ease: Ease::Linear,
+            duration,
+        }
+    }
+
+    pub const fn eased(mut self, ease: Ease) -> Self {
+        self.ease = ease;
+        self
+    }
+}
+
+/// Which way a fade went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fade {
+    Out,
+    In,
+}
+
+/// Sent when a fade completes: the screen has just gone fully dark, or fully clear.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct TransitionFinished {
+    pub fade: Fade,
+    pub style: TransitionStyle,
+}
+
+/// Something run partway through a sequence, usually while the screen is dark.
+pub struct TransitionCallback(Box<dyn FnOnce(&mut World) + Send + Sync>);
+
+/// One step of a queued sequence.
+pub enum TransitionStep {
+    FadeOut(Transition),
+    FadeIn(Transition),
+    /// Stay as dark as the screen is for this many seconds.
+    Hold(f32),
+    /// Run straight away, then carry on with the next step.
+    Run(TransitionCallback),
+}
+
+impl TransitionStep {
+    pub fn run(callback: impl FnOnce(&mut World) + Send + Sync + 'static) -> Self {
+        Self::Run(TransitionCallback(Box::new(callback)))
+    }
+}
+
+/// Controls a fade-to-black / fade-from-black transition via PaletteDarken, and any steps
+/// queued to follow it. Replaced on entering a `GameState`, so steps queued after a state
+/// change are dropped; the new state fades itself in.
+///
+/// Freezes while the game is paused, unless started with `play_through_pause`.
+#[derive(Resource, Default)]
 pub struct SceneTransition {
     phase: TransitionPhase,
+    queue: VecDeque<TransitionStep>,
+    through_pause: bool,
 }
 
 #[derive(Default)]
@@ -26,111 +167,381 @@ enum TransitionPhase {
     #[default]
     Idle,
     FadingOut {
-        duration: f32,
+        transition: Transition,
         elapsed: f32,
     },
+    /// Faded out, staying black until told otherwise.
+    Dark {
+        style: TransitionStyle,
+    },
     FadingIn {
-        duration: f32,
+        transition: Transition,
         elapsed: f32,
     },
+    Holding {
+        remaining: f32,
+        darken: f32,
+        style: TransitionStyle,
+    },
 }
 
 #[allow(dead_code)]
 impl SceneTransition {
     pub fn new() -> Self {
-        Self {
-            phase: TransitionPhase::Idle,
-        }
+        Self::default()
     }
 
-    /// Begin fading from current state to fully black over `duration` seconds.
-    pub fn fade_out(&mut self, duration: f32) {
-        self.phase = TransitionPhase::FadingOut {
-            duration,
-            elapsed: 0.0,
-        };
+    /// Begin going from the current state to fully black, dropping any queued steps.
+    pub fn fade_out(&mut self, transition: Transition) {
+        self.play([TransitionStep::FadeOut(transition)]);
     }
 
-    /// Begin fading from fully black back to normal over `duration` seconds.
-    pub fn fade_in(&mut self, duration: f32) {
-        self.phase = TransitionPhase::FadingIn {
-            duration,
-            elapsed: 0.0,
-        };
+    /// Begin coming back from fully black to normal, dropping any queued steps.
+    pub fn fade_in(&mut self, transition: Transition) {
+        self.play([TransitionStep::FadeIn(transition)]);
+    }
+
+    /// Drop what is under way and queued, and run `steps` from the next frame.
+    pub fn play(&mut self, steps: impl IntoIterator<Item = TransitionStep>) {
+        self.phase = self.settled();
+        self.queue = steps.into_iter().collect();
+        self.through_pause = false;
     }
 
-    /// Returns true when the screen is fully black (darken >= 1.0) and idle or just finished fading out.
+    /// Like `play`, but keeps running while the game is paused, e.g. when leaving from the
+    /// pause menu.
+    pub fn play_through_pause(&mut self, steps: impl IntoIterator<Item = TransitionStep>) {
+        self.play(steps);
+        self.through_pause = true;
+    }
+
+    /// Queue `step` to run once everything before it is done.
+    pub fn then(&mut self, step: TransitionStep) -> &mut Self {
+        self.queue.push_back(step);
+        self
+    }
+
+    /// Returns true when the screen is fully black and nothing else is under way.
     pub fn is_fully_dark(&self) -> bool {
-        matches!(self.phase, TransitionPhase::Idle) && self.darken_value() >= 1.0
+        matches!(self.phase, TransitionPhase::Dark { .. }) && self.queue.is_empty()
     }
 
     /// Returns true when idle and not dark.
     pub fn is_idle(&self) -> bool {
-        matches!(self.phase, TransitionPhase::Idle)
+        matches!(self.phase, TransitionPhase::Idle) && self.queue.is_empty()
     }
 
     /// Current darken value: 0.0 = normal, 1.0 = fully black.
     pub fn darken_value(&self) -> f32 {
         match &self.phase {
             TransitionPhase::Idle => 0.0,
-            TransitionPhase::FadingOut { duration, elapsed } => {
-                (elapsed / duration).clamp(0.0, 1.0)
-            }
-            TransitionPhase::FadingIn { duration, elapsed } => {
-                1.0 - (elapsed / duration).clamp(0.0, 1.0)
+            TransitionPhase::FadingOut {
+                transition,
+                elapsed,
+            } => transition.ease.apply(elapsed / transition.duration),
+            TransitionPhase::Dark { .. } => 1.0,
+            TransitionPhase::FadingIn {
+                transition,
+                elapsed,
+            } => 1.0 - transition.ease.apply(elapsed / transition.duration),
+            TransitionPhase::Holding { darken, .. } => *darken,
+        }
+    }
+
+    /// Look of the transition under way.
+    pub fn style(&self) -> TransitionStyle {
+        match &self.phase {
+            TransitionPhase::Idle => TransitionStyle::default(),
+            TransitionPhase::FadingOut { transition, .. }
+            | TransitionPhase::FadingIn { transition, .. } => transition.style,
+            TransitionPhase::Dark { style } | TransitionPhase::Holding { style, .. } => *style,
+        }
+    }
+
+    /// The resting phase matching what is on screen: dark or clear.
+    fn settled(&self) -> TransitionPhase {
+        if self.darken_value() >= 1.0 {
+            TransitionPhase::Dark {
+                style: self.style(),
             }
+        } else {
+            TransitionPhase::Idle
         }
     }
 
-    fn tick(&mut self, dt: f32) {
-        match &mut self.phase {
-            TransitionPhase::Idle => {}
-            TransitionPhase::FadingOut { duration, elapsed } => {
-                *elapsed += dt;
-                if *elapsed >= *duration {
-                    // Stay dark — caller decides what happens next
-                    self.phase = TransitionPhase::Idle;
+    /// Advance the current phase, returning the fade that finished on the way.
+    fn tick(&mut self, dt: f32) -> Option<TransitionFinished> {
+        let (fade, transition, done) = match &mut self.phase {
+            TransitionPhase::Idle | TransitionPhase::Dark { .. } => return None,
+            TransitionPhase::Holding { remaining, .. } => {
+                *remaining -= dt;
+                if *remaining <= 0.0 {
+                    self.phase = self.settled();
                 }
+                return None;
             }
-            TransitionPhase::FadingIn { duration, elapsed } => {
+            TransitionPhase::FadingOut {
+                transition,
+                elapsed,
+            } => {
                 *elapsed += dt;
-                if *elapsed >= *duration {
-                    self.phase = TransitionPhase::Idle;
-                }
+                (Fade::Out, *transition, *elapsed >= transition.duration)
+            }
+            TransitionPhase::FadingIn {
+                transition,
+                elapsed,
+            } => {
+                *elapsed += dt;
+                (Fade::In, *transition, *elapsed >= transition.duration)
+            }
+        };
+        if !done {
+            return None;
+        }
+        self.phase = match fade {
+            // Stay dark — the queue or the caller decides what happens next
+            Fade::Out => TransitionPhase::Dark {
+                style: transition.style,
+            },
+            Fade::In => TransitionPhase::Idle,
+        };
+        Some(TransitionFinished {
+            fade,
+            style: transition.style,
+        })
+    }
+
+    /// Once the current phase is over, start the next queued step. Callbacks come back to
+    /// the caller to run; call again after each for the steps behind it.
+    fn start_next(&mut self) -> Option<TransitionCallback> {
+        if !matches!(
+            self.phase,
+            TransitionPhase::Idle | TransitionPhase::Dark { .. }
+        ) {
+            return None;
+        }
+        match self.queue.pop_front()? {
+            TransitionStep::FadeOut(transition) => {
+                self.phase = TransitionPhase::FadingOut {
+                    transition,
+                    elapsed: 0.0,
+                };
+            }
+            TransitionStep::FadeIn(transition) => {
+                self.phase = TransitionPhase::FadingIn {
+                    transition,
+                    elapsed: 0.0,
+                };
             }
+            TransitionStep::Hold(seconds) => {
+                self.phase = TransitionPhase::Holding {
+                    remaining: seconds,
+                    darken: self.darken_value(),
+                    style: self.style(),
+                };
+            }
+            TransitionStep::Run(callback) => return Some(callback),
+        }
+        self.start_next()
+    }
+}
+
+/// Changes `GameState` through darkness rather than with a hard cut. The new state fades
+/// itself back in.
+#[derive(SystemParam)]
+pub struct GameStateTransition<'w> {
+    transition: Option<ResMut<'w, SceneTransition>>,
+    next_state: ResMut<'w, NextState<GameState>>,
+}
+
+impl GameStateTransition<'_> {
+    /// Fade out with `out`, then switch to `state`. Switches at once if no transition runs.
+    pub fn go_to(&mut self, state: GameState, out: Transition) {
+        match self.transition.as_mut() {
+            Some(transition) => transition.play_through_pause([
+                TransitionStep::FadeOut(out),
+                TransitionStep::run(move |world| {
+                    world.resource_mut::<NextState<GameState>>().set(state);
+                }),
+            ]),
+            None => self.next_state.set(state),
         }
     }
 }
 
 fn tick_transition(
+    mut commands: Commands,
     time: Res<Time>,
     transition: Option<ResMut<SceneTransition>>,
     mut darken: Option<ResMut<PaletteDarken>>,
+    mut finished: MessageWriter<TransitionFinished>,
+    paused: Option<Res<Paused>>,
 ) {
     let Some(mut transition) = transition else {
         return;
     };
-    transition.tick(time.delta_secs());
+    // A paused game holds its fades, so nothing queued runs behind the pause menu
+    if !transition.through_pause && !game_not_paused(paused) {
+        return;
+    }
+    if let Some(fade) = transition.tick(time.delta_secs()) {
+        finished.write(fade);
+    }
+    while let Some(TransitionCallback(callback)) = transition.start_next() {
+        commands.queue(callback);
+    }
     if let Some(darken) = darken.as_mut() {
         darken.value = transition.darken_value();
+        darken.style = transition.style();
     }
 }
 
 fn start_fade_in_on_enter_playing(mut commands: Commands) {
-    commands.insert_resource(SceneTransition {
-        phase: TransitionPhase::FadingIn {
-            duration: 1.0,
-            elapsed: 0.0,
-        },
-    });
+    let mut transition = SceneTransition::new();
+    transition.fade_in(ENTER_PLAYING);
+    commands.insert_resource(transition);
 }
 
 fn start_fade_in_on_enter_menu(mut commands: Commands) {
     commands.insert_resource(PaletteDarken::default());
-    commands.insert_resource(SceneTransition {
-        phase: TransitionPhase::FadingIn {
-            duration: 1.0,
-            elapsed: 0.0,
-        },
-    });
+    let mut transition = SceneTransition::new();
+    transition.fade_in(ENTER_MENU);
+    commands.insert_resource(transition);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn easing_keeps_the_ends_and_shapes_the_middle() {
+        for ease in [
+            Ease::Linear,
+            Ease::InQuad,
+            Ease::OutQuad,
+            Ease::OutCubic,
+            Ease::InOutCubic,
+        ] {
+            assert_eq!(ease.apply(0.0), 0.0, "{ease:?}");
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-6, "{ease:?}");
+            assert_eq!(ease.apply(2.0), ease.apply(1.0), "{ease:?} overshot");
+        }
+        assert!(Ease::InQuad.apply(0.5) < 0.5);
+        assert!(Ease::OutCubic.apply(0.5) > Ease::OutQuad.apply(0.5));
+        assert!((Ease::InOutCubic.apply(0.5) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn darken_follows_the_transition_style_and_curve() {
+        let mut app = TestApp::new().with_plugins(TransitionPlugin);
+        app.app.insert_resource(PaletteDarken::default());
+        let mut transition = SceneTransition::new();
+        transition.fade_out(Transition::dissolve(1.0).eased(Ease::InQuad));
+        app.app.insert_resource(transition);
+
+        app.advance(0.5);
+        let darken = app.resource::<PaletteDarken>();
+        assert_eq!(darken.style, TransitionStyle::Dissolve);
+        assert!(darken.value > 0.2 && darken.value < 0.3, "{}", darken.value);
+    }
+
+    #[derive(Resource, Default)]
+    struct Finished(Vec<TransitionFinished>);
+
+    fn collect_finished(mut reader: MessageReader<TransitionFinished>, mut log: ResMut<Finished>) {
+        log.0.extend(reader.read().copied());
+    }
+
+    fn setup() -> TestApp {
+        let mut app = TestApp::new().with_plugins(TransitionPlugin);
+        app.app
+            .init_resource::<Finished>()
+            .insert_resource(PaletteDarken::default())
+            .insert_resource(SceneTransition::new())
+            .add_systems(Update, collect_finished.after(tick_transition));
+        app
+    }
+
+    #[derive(Resource)]
+    struct Swapped;
+
+    #[test]
+    fn queued_steps_run_in_order_and_report_each_fade() {
+        let mut app = setup();
+        app.resource_mut::<SceneTransition>().play([
+            TransitionStep::FadeOut(Transition::fade(0.5)),
+            TransitionStep::run(|world| world.insert_resource(Swapped)),
+            TransitionStep::Hold(0.5),
+            TransitionStep::FadeIn(Transition::iris(0.5)),
+        ]);
+
+        app.advance(0.6);
+        assert!(app.has_resource::<Swapped>());
+        assert_eq!(app.resource::<PaletteDarken>().value, 1.0);
+        assert_eq!(
+            app.resource::<Finished>().0,
+            vec![TransitionFinished {
+                fade: Fade::Out,
+                style: TransitionStyle::Fade,
+            }]
+        );
+
+        // Held dark, not snapped back
+        app.advance(0.3);
+        assert_eq!(app.resource::<PaletteDarken>().value, 1.0);
+
+        app.advance(0.8);
+        assert!(app.resource::<SceneTransition>().is_idle());
+        assert_eq!(app.resource::<PaletteDarken>().value, 0.0);
+        assert_eq!(
+            app.resource::<Finished>().0[1],
+            TransitionFinished {
+                fade: Fade::In,
+                style: TransitionStyle::Iris,
+            }
+        );
+    }
+
+    #[test]
+    fn a_finished_fade_out_stays_dark() {
+        let mut app = setup();
+        app.resource_mut::<SceneTransition>()
+            .fade_out(Transition::smear(0.2));
+        app.advance(1.0);
+        assert!(app.resource::<SceneTransition>().is_fully_dark());
+        assert_eq!(app.resource::<PaletteDarken>().value, 1.0);
+    }
+
+    #[test]
+    fn game_state_changes_go_through_darkness() {
+        fn leave(mut states: GameStateTransition) {
+            states.go_to(GameState::Menu, Transition::dissolve(0.5));
+        }
+
+        let mut app = setup();
+        app.start_playing();
+        app.app.world_mut().run_system_once(leave).unwrap();
+
+        app.advance(0.3);
+        assert_eq!(*app.resource::<State<GameState>>().get(), GameState::Playing);
+        app.advance(0.4);
+        assert_eq!(*app.resource::<State<GameState>>().get(), GameState::Menu);
+    }
+
+    #[test]
+    fn leaving_from_the_pause_menu_still_fades() {
+        fn leave(mut states: GameStateTransition) {
+            states.go_to(GameState::Menu, Transition::fade(0.5));
+        }
+
+        let mut app = setup();
+        app.start_playing();
+        app.app.insert_resource(Paused(true));
+        app.app.world_mut().run_system_once(leave).unwrap();
+
+        app.advance(0.7);
+        assert_eq!(*app.resource::<State<GameState>>().get(), GameState::Menu);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::GameState;
 use crate::actor::GROUND_Y;
-use crate::dialog::Npc;
+use crate::dialog::{Npc, NpcIdentityRon};
 use crate::pause::game_not_paused;
 use crate::player::Player;
 use bevy::prelude::*;
@@ -35,6 +35,8 @@ struct NpcRon {
     position: (f32, f32, f32),
     sprite: String,
     range: f32,
+    #[serde(default)]
+    identity: NpcIdentityRon,
 }
 
 // --- Components ---
@@ -87,6 +89,7 @@ fn setup_world(
                     npc.position.2,
                 ),
                 Npc { range: npc.range },
+                npc.identity.load(&asset_server),
                 WorldEntity,
             ))
             .with_children(|parent| {